@@ -30,7 +30,7 @@ async fn main()
 		framed.send( "A second line".to_string() ).await.expect( "Send a line" );
 
 		debug!( "closing server side" );
-		<Framed<WsStream<Endpoint>, LinesCodec> as futures::SinkExt<String>>::close( &mut framed ).await.expect( "close server" );
+		<Framed<WsStream<async_tungstenite::WebSocketStream<Endpoint>>, LinesCodec> as futures::SinkExt<String>>::close( &mut framed ).await.expect( "close server" );
 		debug!( "closed server side" );
 
 		let read = framed.next().await.transpose().expect( "close connection" );
@@ -0,0 +1,168 @@
+use crate::{ import::*, WsStream, WsStreamConfig, WsErr, HandshakeInfo };
+
+
+/// Does the client side websocket handshake over a connection you provide and returns a ready [`WsStream`]
+/// along with the subprotocol the server selected and the headers of its response.
+///
+/// Setting up the connection, eg. TCP and TLS, is left to you so this works with any runtime:
+///
+/// ```no_run
+/// use
+/// {
+///    ws_stream_tungstenite :: { WsConnector, WsErr } ,
+///    async_std             :: { net::TcpStream     } ,
+/// };
+///
+/// # async fn run() -> Result<(), WsErr> {
+/// let tcp = TcpStream::connect( "127.0.0.1:3012" ).await?;
+///
+/// let (ws, info) = WsConnector::new( "ws://127.0.0.1:3012/chat" )
+///
+///    .protocols( [ "chat.v2", "chat.v1" ] )
+///    .connect( tcp ).await?
+/// ;
+///
+/// match info.subprotocol.as_deref()
+/// {
+///    Some( "chat.v2" ) => { /* speak v2 */ }
+///    _                 => { /* speak v1 */ }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Errors
+///
+/// - [`WsErr::Subprotocol`]: The server selected a subprotocol we did not offer, selected none while we offered
+///   some, or selected one while we offered none.
+/// - [`WsErr::Tungstenite`]: The handshake failed, eg. the url is not a valid websocket url or the server
+///   refused the connection. In the latter case the error contains the response of the server.
+/// - [`WsErr::InvalidConfig`]: The [`WsConnector::config`] is not valid for the [`WebSocketConfig`].
+//
+#[ derive( Debug, Clone ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "client" )) ) ]
+//
+pub struct WsConnector
+{
+	url      : String                                     ,
+	protocols: Vec<String>                                ,
+	headers  : HeaderMap                                  ,
+	ws_config: Option<WebSocketConfig>                    ,
+	config   : WsStreamConfig                             ,
+	addrs    : ( Option<SocketAddr>, Option<SocketAddr> ) ,
+}
+
+
+
+impl WsConnector
+{
+	/// Connect to `url`, eg. `ws://example.com/chat`. The host and path are sent in the upgrade request.
+	//
+	pub fn new( url: impl Into<String> ) -> Self
+	{
+		Self
+		{
+			url      : url.into()                ,
+			protocols: Vec::new()                ,
+			headers  : HeaderMap::new()          ,
+			ws_config: None                      ,
+			config   : WsStreamConfig::default() ,
+			addrs    : ( None, None )            ,
+		}
+	}
+
+
+	/// The subprotocols we offer in `Sec-WebSocket-Protocol`, in order of preference. The server must select
+	/// one of them, otherwise connecting fails with [`WsErr::Subprotocol`].
+	//
+	pub fn protocols( mut self, protocols: impl IntoIterator< Item = impl Into<String> > ) -> Self
+	{
+		self.protocols = protocols.into_iter().map( Into::into ).collect();
+		self
+	}
+
+
+	/// Extra headers for the upgrade request, eg. for authentication. They replace headers of the same name,
+	/// so don't set the ones the handshake needs, like `Sec-WebSocket-Key`.
+	//
+	pub fn headers( mut self, headers: HeaderMap ) -> Self
+	{
+		self.headers = headers;
+		self
+	}
+
+
+	/// The tungstenite config for the connection.
+	//
+	pub fn websocket_config( mut self, config: WebSocketConfig ) -> Self
+	{
+		self.ws_config = Some( config );
+		self
+	}
+
+
+	/// The peer and local address of the transport, for the [`HandshakeInfo`].
+	//
+	pub fn addrs( mut self, peer: Option<SocketAddr>, local: Option<SocketAddr> ) -> Self
+	{
+		self.addrs = ( peer, local );
+		self
+	}
+
+
+	/// The options for the [`WsStream`]. The [`HandshakeInfo`] of the connection is set on it.
+	//
+	pub fn config( mut self, config: WsStreamConfig ) -> Self
+	{
+		self.config = config;
+		self
+	}
+
+
+	/// Do the handshake over `transport` and create the [`WsStream`]. The returned [`HandshakeInfo`] holds the
+	/// uri we requested, the headers of the response and the subprotocol the server selected.
+	//
+	pub async fn connect<T>( self, transport: T ) -> Result< (WsStream< ATungSocket<T> >, HandshakeInfo), WsErr >
+
+		where T: AsyncRead + AsyncWrite + Send + Unpin
+	{
+		let mut request = self.url.as_str().into_client_request()?;
+
+		request.headers_mut().extend( self.headers );
+
+		// tungstenite splits the offered protocols on commas without trimming, so don't add spaces.
+		//
+		if !self.protocols.is_empty()
+		{
+			let protocols = HeaderValue::from_str( &self.protocols.join( "," ) )
+
+				.map_err( |e| TungErr::HttpFormat( e.into() ) )?
+			;
+
+			request.headers_mut().insert( SEC_WEBSOCKET_PROTOCOL, protocols );
+		}
+
+		let uri                = request.uri().clone();
+		let (socket, response) = client_async_with_config( request, transport, self.ws_config ).await?;
+
+		let subprotocol = response.headers().get( SEC_WEBSOCKET_PROTOCOL )
+
+			.and_then( |value| value.to_str().ok() )
+			.map( String::from )
+		;
+
+		let info = HandshakeInfo
+		{
+			uri                                    ,
+			headers   : response.headers().clone() ,
+			subprotocol                            ,
+			peer_addr : self.addrs.0               ,
+			local_addr: self.addrs.1               ,
+		};
+
+		let ws = self.config.handshake_info( info.clone() ).build( socket )?;
+
+		Ok(( ws, info ))
+	}
+}
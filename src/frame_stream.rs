@@ -0,0 +1,606 @@
+use crate::{ import::*, WsBackend };
+
+
+/// A [`WsBackend`] that hands on the data frames of a message as they arrive, instead of reassembling the
+/// message first. Over it, [`WsStream`](crate::WsStream) makes the data of a big message readable through
+/// `AsyncRead` as each fragment comes in, so memory is bounded by the size of a fragment rather than by the
+/// size of a message.
+///
+/// The limit per fragment is `max_frame_size` in the [`WebSocketConfig`]. `max_message_size` only applies to
+/// messages we need as a whole: text messages, and all messages when used by a
+/// [`WsMessageStream`](crate::WsMessageStream). Note that [`WsStream::read_message`](crate::WsStream::read_message)
+/// returns the fragments as they come.
+///
+/// It runs the websocket protocol itself on a transport over which the http upgrade is done, like
+/// `WebSocketStream::from_raw_socket`. Pings are answered and the close handshake behaves like _tungstenite_.
+///
+/// ```no_run
+/// use
+/// {
+///    ws_stream_tungstenite :: { FrameStream, WsStream                    } ,
+///    tungstenite           :: { protocol::{ Role, WebSocketConfig }      } ,
+///    futures               :: { AsyncReadExt                             } ,
+/// };
+///
+/// # async fn run( tcp: async_std::net::TcpStream ) -> std::io::Result<()> {
+/// // After the handshake, eg. with async_tungstenite::accept_async, get the transport back with
+/// // `WebSocketStream::into_inner`.
+/// //
+/// let config = WebSocketConfig { max_frame_size: Some( 64 * 1024 ), ..Default::default() };
+/// let mut ws = WsStream::new( FrameStream::new( tcp, Role::Server, Some( config ) ) );
+///
+/// let mut buf = [0; 1024];
+/// let read    = ws.read( &mut buf ).await?;
+/// # Ok(())
+/// # }
+/// ```
+//
+pub struct FrameStream<S>
+{
+	socket    : S               ,
+	role      : Role            ,
+	config    : WebSocketConfig ,
+	state     : State           ,
+
+	// Bytes read from the socket that are not part of a frame we returned yet, and the header of the frame
+	// we are reading, if we have it.
+	//
+	in_buf    : Vec<u8>                      ,
+	header    : Option<( FrameHeader, u64 )> ,
+
+	// Formatted frames waiting to be written to the socket.
+	//
+	out_buf   : Vec<u8>       ,
+
+	// A pong or close frame we reply with. It's sent out on the next flush, or while reading.
+	//
+	reply     : Option<Frame> ,
+	unflushed : bool          ,
+
+	// Whether we are in the middle of a fragmented message.
+	//
+	fragmented: bool          ,
+
+	// We returned None or an error from the stream, so it's done.
+	//
+	ended     : bool          ,
+}
+
+
+
+// Where we are in the close handshake, the same as the states of tungstenite.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+enum State
+{
+	Active           ,
+	ClosedByUs       ,
+	ClosedByPeer     ,
+	CloseAcknowledged,
+	Terminated       ,
+}
+
+
+impl State
+{
+	fn can_read( self ) -> bool
+	{
+		matches!( self, State::Active | State::ClosedByUs )
+	}
+}
+
+
+
+impl<S> FrameStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+{
+	/// How much we try to read from the socket at once.
+	//
+	const READ_SIZE: usize = 8 * 1024;
+
+
+	/// Run the websocket protocol on a transport over which the http upgrade is done. Without a config, the
+	/// defaults of tungstenite are used.
+	//
+	pub fn new( socket: S, role: Role, config: Option<WebSocketConfig> ) -> Self
+	{
+		Self
+		{
+			socket                                 ,
+			role                                   ,
+			config    : config.unwrap_or_default() ,
+			state     : State::Active              ,
+			in_buf    : Vec::new()                 ,
+			header    : None                       ,
+			out_buf   : Vec::new()                 ,
+			reply     : None                       ,
+			unflushed : false                      ,
+			fragmented: false                      ,
+			ended     : false                      ,
+		}
+	}
+
+
+	/// Get the transport back, with data that was read from it but not returned yet.
+	//
+	pub fn into_inner( self ) -> ( S, Vec<u8> )
+	{
+		( self.socket, self.in_buf )
+	}
+
+
+	// The stream is done. Closing the connection is not an error.
+	//
+	fn end( &mut self, err: TungErr ) -> Poll< Option< Result<TungMessage, TungErr> > >
+	{
+		self.ended = true;
+		self.state = State::Terminated;
+
+		match err
+		{
+			TungErr::ConnectionClosed | TungErr::AlreadyClosed => None.into(),
+			err                                                => Some(Err( err )).into(),
+		}
+	}
+
+
+	// Queue a pong or close reply, but never replace a close frame with a pong.
+	//
+	fn set_reply( &mut self, frame: Frame )
+	{
+		let closing = matches!( &self.reply, Some( f ) if f.header().opcode == OpCode::Control( Control::Close ) );
+
+		if !closing
+		{
+			self.reply = Some( frame );
+		}
+	}
+
+
+	// Format a frame into the out buffer, masked when we are the client.
+	//
+	fn buffer_frame( &mut self, mut frame: Frame ) -> Result<(), TungErr>
+	{
+		if self.role == Role::Client
+		{
+			frame.header_mut().mask = Some( rand::random() );
+		}
+
+		if self.out_buf.len() + frame.len() > self.config.max_write_buffer_size
+		{
+			return Err( TungErr::WriteBufferFull( TungMessage::Frame( frame ) ) );
+		}
+
+		frame.format( &mut self.out_buf )
+	}
+
+
+	// Write out the reply and the out buffer, and flush the socket. Once the close handshake is done, the
+	// server is done with the connection, which returns `ConnectionClosed` like tungstenite.
+	//
+	fn poll_send( &mut self, cx: &mut Context<'_> ) -> Poll< Result<(), TungErr> >
+	{
+		loop
+		{
+			if let Some( frame ) = self.reply.take()
+			{
+				match self.buffer_frame( frame )
+				{
+					// Try again once the out buffer is written.
+					//
+					Err( TungErr::WriteBufferFull( TungMessage::Frame( frame ) ) ) if !self.out_buf.is_empty() =>
+
+						self.reply = Some( frame ),
+
+					result => result?,
+				}
+			}
+
+			if self.out_buf.is_empty()
+			{
+				break;
+			}
+
+			while !self.out_buf.is_empty()
+			{
+				let written = ready!( Pin::new( &mut self.socket ).poll_write( cx, &self.out_buf ) )?;
+
+				if written == 0
+				{
+					return Poll::Ready( Err( io::Error::from( io::ErrorKind::WriteZero ).into() ) );
+				}
+
+				self.out_buf.drain( ..written );
+			}
+		}
+
+		ready!( Pin::new( &mut self.socket ).poll_flush( cx ) )?;
+
+		if self.role == Role::Server && !self.state.can_read()
+		{
+			self.state = State::Terminated;
+			return Err( TungErr::ConnectionClosed ).into();
+		}
+
+		Ok(()).into()
+	}
+
+
+	// Read the next frame from the socket. Returns None when the socket ends.
+	//
+	fn poll_frame( &mut self, cx: &mut Context<'_> ) -> Poll< Result< Option<Frame>, TungErr > >
+	{
+		loop
+		{
+			if self.header.is_none()
+			{
+				let mut cursor = io::Cursor::new( &self.in_buf );
+
+				if let Some( header ) = FrameHeader::parse( &mut cursor )?
+				{
+					let len = cursor.position() as usize;
+
+					self.in_buf.drain( ..len );
+					self.header = Some( header );
+				}
+			}
+
+			if let Some(( _, len )) = self.header
+			{
+				let max_size = self.config.max_frame_size.unwrap_or( usize::MAX );
+
+				if len > max_size as u64
+				{
+					return Err( CapacityError::MessageTooLong{ size: len as usize, max_size }.into() ).into();
+				}
+
+				if self.in_buf.len() as u64 >= len
+				{
+					let rest          = self.in_buf.split_off( len as usize );
+					let payload       = std::mem::replace( &mut self.in_buf, rest );
+					let ( header, _ ) = self.header.take().expect( "checked above" );
+
+					return Ok( Some( Frame::from_payload( header, payload ) ) ).into();
+				}
+			}
+
+			let start = self.in_buf.len();
+			self.in_buf.resize( start + Self::READ_SIZE, 0 );
+
+			let read = Pin::new( &mut self.socket ).poll_read( cx, &mut self.in_buf[ start.. ] );
+
+			match read
+			{
+				Poll::Ready( Ok( n ) ) =>
+				{
+					self.in_buf.truncate( start + n );
+
+					if n == 0
+					{
+						return Ok( None ).into();
+					}
+				}
+
+				Poll::Ready( Err( e ) ) =>
+				{
+					self.in_buf.truncate( start );
+					return Err( e.into() ).into();
+				}
+
+				Poll::Pending =>
+				{
+					self.in_buf.truncate( start );
+					return Poll::Pending;
+				}
+			}
+		}
+	}
+
+
+	// Check a frame against the protocol and turn it into the message we return, if any. Data frames are
+	// returned as `TungMessage::Frame`.
+	//
+	fn on_frame( &mut self, mut frame: Frame ) -> Result< Option<TungMessage>, TungErr >
+	{
+		if !self.state.can_read()
+		{
+			return Err( ProtocolError::ReceivedAfterClosing.into() );
+		}
+
+		let header = frame.header();
+
+		if header.rsv1 || header.rsv2 || header.rsv3
+		{
+			return Err( ProtocolError::NonZeroReservedBits.into() );
+		}
+
+		match ( self.role, frame.header_mut().mask.take() )
+		{
+			( Role::Server, Some( mask ) ) =>
+			{
+				for (i, byte) in frame.payload_mut().iter_mut().enumerate()
+				{
+					*byte ^= mask[ i % 4 ];
+				}
+			}
+
+			( Role::Server, None ) if !self.config.accept_unmasked_frames =>
+
+				return Err( ProtocolError::UnmaskedFrameFromClient.into() ),
+
+			( Role::Client, Some(_) ) => return Err( ProtocolError::MaskedFrameFromServer.into() ),
+
+			_ => {}
+		}
+
+		let is_final = frame.header().is_final;
+
+		match frame.header().opcode
+		{
+			OpCode::Control(_) if !is_final => Err( ProtocolError::FragmentedControlFrame.into() ),
+
+			OpCode::Control(_) if frame.payload().len() > 125 =>
+
+				Err( ProtocolError::ControlFrameTooBig.into() ),
+
+			OpCode::Control( Control::Close ) => self.on_close( frame.into_data() ),
+
+			OpCode::Control( Control::Ping ) =>
+			{
+				let data = frame.into_data();
+
+				// No more pongs once we sent a close frame.
+				//
+				if self.state == State::Active
+				{
+					self.set_reply( Frame::pong( data.clone() ) );
+				}
+
+				Ok( Some( TungMessage::Ping( data ) ) )
+			}
+
+			OpCode::Control( Control::Pong ) => Ok( Some( TungMessage::Pong( frame.into_data() ) ) ),
+
+			OpCode::Control( Control::Reserved(i) ) => Err( ProtocolError::UnknownControlFrameType(i).into() ),
+
+			OpCode::Data( Data::Reserved(i) ) => Err( ProtocolError::UnknownDataFrameType(i).into() ),
+
+			OpCode::Data( Data::Continue ) if !self.fragmented => Err( ProtocolError::UnexpectedContinueFrame.into() ),
+
+			OpCode::Data( data @ ( Data::Text | Data::Binary ) ) if self.fragmented =>
+
+				Err( ProtocolError::ExpectedFragment( data ).into() ),
+
+			OpCode::Data(_) =>
+			{
+				self.fragmented = !is_final;
+
+				Ok( Some( TungMessage::Frame( frame ) ) )
+			}
+		}
+	}
+
+
+	// The remote sent a close frame. Reply to it unless it is the reply to ours.
+	//
+	fn on_close( &mut self, payload: Vec<u8> ) -> Result< Option<TungMessage>, TungErr >
+	{
+		let close = match payload.len()
+		{
+			0 => None,
+			1 => return Err( ProtocolError::InvalidCloseSequence.into() ),
+
+			_ =>
+			{
+				let code   = CloseCode::from( u16::from_be_bytes([ payload[0], payload[1] ]) );
+				let reason = String::from_utf8( payload[ 2.. ].to_vec() ).map_err( |_| TungErr::Utf8 )?;
+
+				// Like tungstenite, don't pass on codes that may not be sent.
+				//
+				Some( match code.is_allowed()
+				{
+					true  => CloseFrame{ code                      , reason: reason.into()               },
+					false => CloseFrame{ code: CloseCode::Protocol , reason: "Protocol violation".into() },
+				})
+			}
+		};
+
+		match self.state
+		{
+			State::Active =>
+			{
+				self.state = State::ClosedByPeer;
+				self.set_reply( Frame::close( close.clone() ) );
+
+				Ok( Some( TungMessage::Close( close ) ) )
+			}
+
+			State::ClosedByUs =>
+			{
+				self.state = State::CloseAcknowledged;
+
+				Ok( Some( TungMessage::Close( close ) ) )
+			}
+
+			_ => Ok( None ),
+		}
+	}
+}
+
+
+
+impl<S> fmt::Debug for FrameStream<S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "FrameStream" )
+
+			.field( "role" , &self.role  )
+			.field( "state", &self.state )
+			.finish_non_exhaustive()
+	}
+}
+
+
+
+impl<S> Stream for FrameStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+{
+	type Item = Result<TungMessage, TungErr>;
+
+
+	fn poll_next( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		let this = self.get_mut();
+
+		if this.ended
+		{
+			return None.into();
+		}
+
+		loop
+		{
+			// Send out replies while reading, like tungstenite.
+			//
+			if this.reply.is_some() || this.unflushed
+			{
+				match this.poll_send( cx )
+				{
+					Poll::Pending        => this.unflushed = true  ,
+					Poll::Ready( Ok(()) ) => this.unflushed = false ,
+					Poll::Ready( Err(e) ) => return this.end( e )   ,
+				}
+			}
+
+			else if this.role == Role::Server && !this.state.can_read()
+			{
+				return this.end( TungErr::ConnectionClosed );
+			}
+
+			let frame = match ready!( this.poll_frame( cx ) )
+			{
+				Ok ( Some( frame ) ) => frame,
+
+				// The remote may only drop the connection once the close handshake is done.
+				//
+				Ok ( None ) => return match this.state
+				{
+					State::ClosedByPeer | State::CloseAcknowledged => this.end( TungErr::ConnectionClosed ),
+					_ => this.end( ProtocolError::ResetWithoutClosingHandshake.into() ),
+				},
+
+				Err( e ) => return this.end( e ),
+			};
+
+			match this.on_frame( frame )
+			{
+				Ok ( Some( msg ) ) => return Some(Ok( msg )).into(),
+				Ok ( None        ) => continue,
+				Err( e           ) => return this.end( e ),
+			}
+		}
+	}
+}
+
+
+
+impl<S> Sink<TungMessage> for FrameStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+{
+	type Error = TungErr;
+
+
+	fn poll_ready( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		let this = self.get_mut();
+
+		if this.out_buf.len() < this.config.write_buffer_size
+		{
+			return Ok(()).into();
+		}
+
+		match ready!( this.poll_send( cx ) )
+		{
+			Err( TungErr::ConnectionClosed ) => Ok(()).into(),
+			result                           => result.into(),
+		}
+	}
+
+
+	fn start_send( self: Pin<&mut Self>, msg: TungMessage ) -> Result<(), Self::Error>
+	{
+		let this = self.get_mut();
+
+		match this.state
+		{
+			State::Terminated => return Err( TungErr::AlreadyClosed ),
+			State::Active     => {}
+			_                 => return Err( ProtocolError::SendAfterClosing.into() ),
+		}
+
+		let max_size = this.config.max_message_size.unwrap_or( usize::MAX );
+
+		if msg.len() > max_size && ( msg.is_binary() || msg.is_text() )
+		{
+			return Err( CapacityError::MessageTooLong{ size: msg.len(), max_size }.into() );
+		}
+
+		let frame = match msg
+		{
+			TungMessage::Text  ( text ) => Frame::message( text.into_bytes(), OpCode::Data( Data::Text   ), true ),
+			TungMessage::Binary( data ) => Frame::message( data            , OpCode::Data( Data::Binary ), true ),
+			TungMessage::Ping  ( data ) => Frame::ping( data ),
+			TungMessage::Frame ( frame) => frame,
+
+			TungMessage::Pong( data ) =>
+			{
+				this.set_reply( Frame::pong( data ) );
+				return Ok(());
+			}
+
+			TungMessage::Close( close ) =>
+			{
+				this.state = State::ClosedByUs;
+				Frame::close( close )
+			}
+		};
+
+		this.buffer_frame( frame )
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		match ready!( self.get_mut().poll_send( cx ) )
+		{
+			Err( TungErr::ConnectionClosed ) => Ok(()).into(),
+			result                           => result.into(),
+		}
+	}
+
+
+	fn poll_close( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		let this = self.get_mut();
+
+		if this.state == State::Active
+		{
+			this.state = State::ClosedByUs;
+			this.buffer_frame( Frame::close( None ) )?;
+		}
+
+		match ready!( this.poll_send( cx ) )
+		{
+			Err( TungErr::ConnectionClosed ) => Ok(()).into(),
+			result                           => result.into(),
+		}
+	}
+}
+
+
+
+impl<S> WsBackend for FrameStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+{
+	fn config( &self ) -> &WebSocketConfig
+	{
+		&self.config
+	}
+}
@@ -0,0 +1,32 @@
+use crate::{ import::* };
+
+
+/// What we know about the http upgrade of a connection, returned alongside the [`WsStream`](crate::WsStream) by
+/// `WsListener` on a server and by `WsConnector` on a client.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+#[ non_exhaustive ]
+//
+pub struct HandshakeInfo
+{
+	/// The uri from the request line, eg. `/chat?room=1`. On a client, this is the full url we requested.
+	//
+	pub uri: Uri,
+
+	/// The headers sent by the remote: those of the upgrade request on a server, those of the response on a client.
+	//
+	pub headers: HeaderMap,
+
+	/// The subprotocol that was selected in the `Sec-WebSocket-Protocol` header of the response, if any.
+	//
+	pub subprotocol: Option<String>,
+
+	/// The address of the remote, if it was provided to the listener or connector.
+	//
+	pub peer_addr: Option<SocketAddr>,
+
+	/// Our own address on this connection, if it was provided to the listener or connector.
+	//
+	pub local_addr: Option<SocketAddr>,
+}
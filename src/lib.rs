@@ -1,10 +1,10 @@
 #![ cfg_attr( nightly, feature(doc_cfg) ) ]
 #![ doc = include_str!("../README.md") ]
 
-#![ doc    ( html_root_url = "https://docs.rs/ws_stream_tungstenite"                  ) ]
-#![ deny   ( missing_docs                                                             ) ]
-#![ forbid ( unsafe_code                                                              ) ]
-#![ allow  ( clippy::suspicious_else_formatting, clippy::too_long_first_doc_paragraph ) ]
+#![ doc    ( html_root_url = "https://docs.rs/ws_stream_tungstenite"                                            ) ]
+#![ deny   ( missing_docs                                                                                       ) ]
+#![ forbid ( unsafe_code                                                                                        ) ]
+#![ allow  ( clippy::suspicious_else_formatting, clippy::too_long_first_doc_paragraph, clippy::result_large_err ) ]
 
 #![ warn
 (
@@ -22,36 +22,71 @@
 )]
 
 
-mod ws_stream ;
-mod ws_event  ;
-mod ws_err    ;
+mod ws_stream         ;
+mod ws_stream_config  ;
+mod ws_event          ;
+mod ws_err            ;
+mod text_policy       ;
+mod write_mode        ;
+mod timer             ;
+mod ws_stats          ;
+mod ws_split          ;
+mod ws_message_stream ;
+mod ws_backend        ;
+
+#[ cfg( feature = "handshake" ) ] mod handshake_info;
+#[ cfg( feature = "server"    ) ] mod server        ;
+#[ cfg( feature = "client"    ) ] mod client        ;
+#[ cfg( feature = "mux"       ) ] mod mux           ;
+#[ cfg( feature = "resume"    ) ] mod resume        ;
+#[ cfg( feature = "frames"    ) ] mod frame_stream  ;
 
 pub(crate) mod tung_websocket;
 
 pub use
 {
-	self::ws_stream :: { WsStream } ,
-	self::ws_event  :: { WsEvent  } ,
-	self::ws_err    :: { WsErr    } ,
+	self::ws_stream         :: { WsStream, WsStreamParts          } ,
+	self::ws_stream_config  :: { WsStreamConfig                   } ,
+	self::ws_event          :: { WsEvent                          } ,
+	self::ws_err            :: { WsErr                            } ,
+	self::text_policy       :: { TextPolicy                       } ,
+	self::write_mode        :: { WriteMode                        } ,
+	self::timer             :: { Timer                            } ,
+	self::ws_stats          :: { WsStats                          } ,
+	self::ws_split          :: { WsReader, WsWriter, ReuniteError } ,
+	self::ws_message_stream :: { WsMessageStream                  } ,
+	self::ws_backend        :: { WsBackend                        } ,
 };
 
+#[ cfg( feature = "handshake" ) ] pub use self::handshake_info::HandshakeInfo;
+#[ cfg( feature = "server"    ) ] pub use self::server::{ WsListener, DEFAULT_MAX_HANDSHAKES };
+#[ cfg( feature = "client"    ) ] pub use self::client::WsConnector;
+#[ cfg( feature = "mux"       ) ] pub use self::mux::{ Mux, MuxConfig, Substream };
+#[ cfg( feature = "resume"    ) ] pub use self::resume::{ ResumableWsStream, ResumeRequest, SessionId };
+#[ cfg( feature = "frames"    ) ] pub use self::frame_stream::FrameStream;
+
 
 mod import
 {
 	pub(crate) use
 	{
-		bitflags          :: { bitflags                                                                                     } ,
-		futures_core      :: { ready, Stream                                                                                } ,
-		futures_sink      :: { Sink                                                                                         } ,
-		futures_io        :: { AsyncRead, AsyncWrite, AsyncBufRead                                                          } ,
-		futures_util      :: { FutureExt                                                                                    } ,
-		tracing           :: { error                                                                                        } ,
-		std               :: { io, io::{ IoSlice, IoSliceMut }, pin::Pin, fmt, borrow::Cow                                  } ,
-		std               :: { collections::VecDeque, sync::Arc, task::{ Context, Poll }                                    } ,
-		async_tungstenite :: { WebSocketStream as ATungSocket                                                               } ,
-		tungstenite       :: { Message as TungMessage, Error as TungErr, protocol::{ CloseFrame, frame::coding::CloseCode } } ,
-		pharos            :: { Observable, ObserveConfig, Observe, Pharos, PharErr                                          } ,
-		async_io_stream   :: { IoStream                                                                                     } ,
+		bitflags          :: { bitflags                                                                                      } ,
+		futures_core      :: { ready, Stream                                                                                 } ,
+		futures_sink      :: { Sink                                                                                          } ,
+		futures_io        :: { AsyncRead, AsyncWrite, AsyncBufRead                                                           } ,
+		futures_util      :: { future, FutureExt, task::noop_waker                                                           } ,
+		futures_channel   :: { oneshot                                                                                       } ,
+		tracing           :: { error, warn, debug, trace, info_span, Span, field                                             } ,
+		std               :: { io, io::{ IoSlice, IoSliceMut }, pin::Pin, fmt, borrow::Cow                                   } ,
+		std               :: { collections::VecDeque, sync::{ Arc, Mutex, MutexGuard, PoisonError }, task::{ Context, Poll } } ,
+		std               :: { task::Waker                                                                                   } ,
+		std               :: { future::Future, time::{ Duration, Instant, SystemTime }                                       } ,
+		async_tungstenite :: { WebSocketStream as ATungSocket                                                                } ,
+		tungstenite       :: { Message as TungMessage, Error as TungErr, protocol::{ CloseFrame, frame::coding::CloseCode }  } ,
+		tungstenite       :: { error::{ ProtocolError, CapacityError, SubProtocolError }                                     } ,
+		tungstenite       :: { protocol::{ WebSocketConfig, frame::{ Frame, coding::{ OpCode, Data } } }                     } ,
+		pharos            :: { Observable, ObserveConfig, Observe, Events, Pharos, PharErr                                   } ,
+		async_io_stream   :: { IoStream                                                                                      } ,
 	};
 
 
@@ -65,17 +100,100 @@ mod import
 
 
 
+	#[ cfg( feature = "tokio_io" ) ]
+	//
+	pub(crate) use
+	{
+		tokio_tungstenite::{ WebSocketStream as TokioTungSocket },
+	};
+
+
+
+	#[ cfg( feature = "handshake" ) ]
+	//
+	pub(crate) use
+	{
+		tungstenite::http::{ Uri, HeaderMap },
+		std::net::SocketAddr,
+	};
+
+
+
+	#[ cfg( any( feature = "server", feature = "client" ) ) ]
+	//
+	pub(crate) use
+	{
+		tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL,
+	};
+
+
+
+	#[ cfg( feature = "client" ) ]
+	//
+	pub(crate) use
+	{
+		async_tungstenite :: { client_async_with_config                     } ,
+		tungstenite       :: { client::IntoClientRequest, http::HeaderValue } ,
+	};
+
+
+
+	#[ cfg( feature = "server" ) ]
+	//
+	pub(crate) use
+	{
+		async_tungstenite :: { accept_hdr_async_with_config                            } ,
+		tungstenite       :: { handshake::server::{ Request, Response, ErrorResponse } } ,
+		futures_util      :: { stream::FuturesUnordered, future::Either                } ,
+	};
+
+
+
+	#[ cfg( any( test, feature = "mux", feature = "frames" ) ) ]
+	//
+	pub(crate) use
+	{
+		tungstenite::protocol::Role,
+	};
+
+
+
+	#[ cfg( feature = "frames" ) ]
+	//
+	pub(crate) use
+	{
+		tungstenite::protocol::frame::{ FrameHeader, coding::Control },
+	};
+
+
+
+	#[ cfg( feature = "mux" ) ]
+	//
+	pub(crate) use
+	{
+		std::task::Wake,
+	};
+
+
+
+	#[ cfg( feature = "metrics" ) ]
+	//
+	pub(crate) use
+	{
+		metrics::{ Label, Counter, Gauge, SharedString, counter, gauge },
+	};
+
+
+
 	#[ cfg( test ) ]
 	//
 	pub(crate) use
 	{
 		futures           :: { executor::block_on, SinkExt, StreamExt } ,
-		futures_test      :: { task::noop_waker                       } ,
 		pharos            :: { Channel                                } ,
 		assert_matches    :: { assert_matches                         } ,
 		futures_ringbuf   :: { Endpoint                               } ,
 		futures           :: { future::{ join }                       } ,
-		tungstenite       :: { protocol::{ Role }                     } ,
 		tracing           :: { *                                      } ,
 	};
 }
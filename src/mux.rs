@@ -0,0 +1,261 @@
+mod frame     ;
+mod inner     ;
+mod substream ;
+mod wake_all  ;
+
+pub use substream::Substream;
+
+use
+{
+	crate :: { import::*, WsMessageStream, WsBackend, WsErr } ,
+	inner :: { Inner                                        } ,
+};
+
+
+// One lock covers the websocket and all substreams, because whichever handle gets polled reads frames for
+// every substream and dispatches them. A poll of one substream can hold it for a while that way, but
+// dispatching only moves data into buffers and wakes tasks, it never waits on anything. Frames to send are
+// queued and the websocket is only ever polled, so there is no await under the lock and a std mutex works.
+//
+type Shared<B> = Arc< Mutex< Inner<B> > >;
+
+
+// A panic in a poll method poisons the lock for every substream. The connection is probably broken by
+// then anyway, so don't make all the other substreams panic too.
+//
+fn lock<B>( shared: &Shared<B> ) -> MutexGuard<'_, Inner<B>>
+
+	where B: WsBackend
+{
+	shared.lock().unwrap_or_else( PoisonError::into_inner )
+}
+
+
+
+/// Options for a [`Mux`].
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "mux" )) ) ]
+//
+pub struct MuxConfig
+{
+	pub(crate) window     : u32   ,
+	pub(crate) max_frame  : usize ,
+	pub(crate) max_streams: usize ,
+}
+
+
+
+impl MuxConfig
+{
+	/// Default flow control window: 256KiB per substream.
+	//
+	pub const DEFAULT_WINDOW: u32 = 256 * 1024;
+
+	/// Default maximum amount of data in one websocket message: 16KiB.
+	//
+	pub const DEFAULT_MAX_FRAME: usize = 16 * 1024;
+
+	/// Default maximum number of substreams the remote can have open at the same time: 256.
+	//
+	pub const DEFAULT_MAX_STREAMS: usize = 256;
+
+
+	/// Create a config with the default options.
+	//
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+
+
+	/// How many bytes the remote may send on a substream before we read them. Once we read half of it, we give
+	/// the remote more credit. This is the most memory a substream buffers when it isn't read. At least 1.
+	//
+	pub fn window( mut self, window: u32 ) -> Self
+	{
+		self.window = window.max( 1 );
+		self
+	}
+
+
+	/// The maximum amount of data in one websocket message, so a big write on one substream doesn't
+	/// delay the others for too long. At least 1.
+	//
+	pub fn max_frame_size( mut self, size: usize ) -> Self
+	{
+		self.max_frame = size.max( 1 );
+		self
+	}
+
+
+	/// How many substreams opened by the remote can be open at the same time, including the ones that
+	/// weren't accepted yet. Each of them can buffer up to the window, so this bounds the memory the remote
+	/// can make us use. Substreams opened beyond it are reset. With 0, the remote can't open any.
+	//
+	pub fn max_streams( mut self, max: usize ) -> Self
+	{
+		self.max_streams = max;
+		self
+	}
+}
+
+
+
+impl Default for MuxConfig
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			window     : Self::DEFAULT_WINDOW      ,
+			max_frame  : Self::DEFAULT_MAX_FRAME   ,
+			max_streams: Self::DEFAULT_MAX_STREAMS ,
+		}
+	}
+}
+
+
+
+/// Runs many independent byte streams, [`Substream`]s, over one [`WsMessageStream`]. Each substream implements
+/// `AsyncRead`/`AsyncWrite` and has its own flow control window, so a substream that isn't read doesn't
+/// block the others.
+///
+/// Open substreams with [`Mux::open`] and accept the ones the remote opens by polling the `Mux` as a `Stream`.
+/// One side has to be created with [`Role::Client`](tungstenite::protocol::Role) and the other with
+/// `Role::Server`, so both can open substreams without their ids colliding.
+///
+/// There is no background task. Every poll of a substream or of the `Mux` itself drives the connection, but
+/// keep polling the `Mux` for incoming substreams as long as the connection is used, otherwise those will not
+/// be accepted. It returns `None` when the connection ended.
+///
+/// The `Mux` can be cloned to open substreams from other tasks. Only poll one of the clones for incoming
+/// substreams.
+///
+/// ```no_run
+/// use
+/// {
+///    ws_stream_tungstenite :: { WsMessageStream, Mux, WsBackend                  } ,
+///    futures               :: { AsyncReadExt, AsyncWriteExt, StreamExt            } ,
+///    tungstenite           :: { protocol::Role                                   } ,
+/// };
+///
+/// # async fn run( socket: impl WsBackend ) -> std::io::Result<()> {
+/// let mut mux    = Mux::new( WsMessageStream::new( socket ), Role::Client );
+/// let mut stream = mux.open()?;
+///
+/// stream.write_all( b"hello" ).await?;
+/// stream.close().await?;
+///
+/// // Meanwhile accept substreams opened by the remote.
+/// //
+/// while let Some( mut incoming ) = mux.next().await
+/// {
+///    let mut buf = Vec::new();
+///    incoming.read_to_end( &mut buf ).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Protocol
+///
+/// Each websocket message is one frame: the substream id as a big endian `u32`, a byte for the kind of frame
+/// and the payload. The kinds are open (0), data (1), credit (2), close (3) and reset (4). Open and credit carry
+/// a big endian `u32`: the window of the opener and the number of bytes the receiver consumed. A client uses
+/// odd ids, a server even ones.
+///
+/// ## Errors
+///
+/// When the remote sends an invalid frame, opens a substream that is already open or the connection fails, all
+/// substreams return that error. When the remote sends data beyond the window or after closing, only that
+/// substream is reset. So are substreams the remote opens beyond [`MuxConfig::max_streams`].
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "mux" )) ) ]
+//
+pub struct Mux<B> where B: WsBackend
+{
+	shared: Shared<B>,
+}
+
+
+
+impl<B> Mux<B> where B: WsBackend
+{
+	/// Multiplex over `ws` with the default [`MuxConfig`].
+	//
+	pub fn new( ws: WsMessageStream<B>, role: Role ) -> Self
+	{
+		Self::with_config( ws, role, MuxConfig::default() )
+	}
+
+
+	/// Multiplex over `ws` with the given options.
+	//
+	pub fn with_config( ws: WsMessageStream<B>, role: Role, config: MuxConfig ) -> Self
+	{
+		Self { shared: Arc::new( Mutex::new( Inner::new( ws, role, config ) ) ) }
+	}
+
+
+	/// Open a new substream. The remote is told when the mux is polled next, eg. by writing to the
+	/// substream. Writing waits until the remote accepted it and gave us credit.
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::Closed`]: The connection ended or is closing.
+	/// - Any error that made the connection fail before.
+	//
+	pub fn open( &self ) -> Result< Substream<B>, WsErr >
+	{
+		let id = lock( &self.shared ).open()?;
+
+		Ok( Substream::new( id, self.shared.clone() ) )
+	}
+
+
+	/// Close the websocket connection. Substreams that are still open will return errors. Keep polling the
+	/// `Mux` for incoming substreams until it returns `None` to drive the close handshake to completion.
+	//
+	pub fn close( &self ) -> impl Future< Output = io::Result<()> > + '_
+	{
+		future::poll_fn( move |cx| lock( &self.shared ).poll_close_mux( cx ) )
+	}
+}
+
+
+
+impl<B> Clone for Mux<B> where B: WsBackend
+{
+	fn clone( &self ) -> Self
+	{
+		Self { shared: self.shared.clone() }
+	}
+}
+
+
+
+impl<B> fmt::Debug for Mux<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Mux over Tungstenite" )
+	}
+}
+
+
+
+impl<B> Stream for Mux<B> where B: WsBackend
+{
+	type Item = Substream<B>;
+
+	/// Accept the next substream opened by the remote.
+	//
+	fn poll_next( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		let id = ready!( lock( &self.shared ).poll_accept( cx ) );
+
+		Poll::Ready( id.map( |id| Substream::new( id, self.shared.clone() ) ) )
+	}
+}
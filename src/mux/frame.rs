@@ -0,0 +1,142 @@
+// Every frame is one binary websocket message: the stream id as a big endian u32, a byte for the kind
+// of frame and the payload.
+//
+const HEADER: usize = 5;
+
+const OPEN  : u8 = 0;
+const DATA  : u8 = 1;
+const CREDIT: u8 = 2;
+const CLOSE : u8 = 3;
+const RESET : u8 = 4;
+
+
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub(super) enum Frame
+{
+	// Open a new substream. The window is how many bytes the opener accepts before giving more credit.
+	//
+	Open{ id: u32, window: u32 },
+
+	// Data for a substream.
+	//
+	Data{ id: u32, data: Vec<u8> },
+
+	// The receiver consumed data, so the sender may send this many more bytes.
+	//
+	Credit{ id: u32, credit: u32 },
+
+	// The sender won't send any more data on this substream.
+	//
+	Close{ id: u32 },
+
+	// Abort the substream in both directions.
+	//
+	Reset{ id: u32 },
+}
+
+
+
+impl Frame
+{
+	// Encode a data frame without copying the data into a `Frame` first.
+	//
+	pub(super) fn encode_data( id: u32, data: &[u8] ) -> Vec<u8>
+	{
+		let mut msg = Vec::with_capacity( HEADER + data.len() );
+
+		msg.extend_from_slice( &id.to_be_bytes() );
+		msg.push( DATA );
+		msg.extend_from_slice( data );
+
+		msg
+	}
+
+
+	pub(super) fn encode( &self ) -> Vec<u8>
+	{
+		let (id, kind, payload) = match self
+		{
+			Frame::Open  { id, window } => ( id, OPEN  , window.to_be_bytes().to_vec() ),
+			Frame::Credit{ id, credit } => ( id, CREDIT, credit.to_be_bytes().to_vec() ),
+			Frame::Close { id         } => ( id, CLOSE , Vec::new()                    ),
+			Frame::Reset { id         } => ( id, RESET , Vec::new()                    ),
+			Frame::Data  { id, data   } => return Self::encode_data( *id, data )        ,
+		};
+
+		let mut msg = Vec::with_capacity( HEADER + payload.len() );
+
+		msg.extend_from_slice( &id.to_be_bytes() );
+		msg.push( kind );
+		msg.extend_from_slice( &payload );
+
+		msg
+	}
+
+
+	// Returns None when the message is not a valid frame.
+	//
+	pub(super) fn decode( mut msg: Vec<u8> ) -> Option<Self>
+	{
+		if msg.len() < HEADER
+		{
+			return None;
+		}
+
+		let payload = msg.split_off( HEADER );
+		let id      = u32::from_be_bytes( msg[ ..4 ].try_into().ok()? );
+
+		let frame = match msg[4]
+		{
+			OPEN   => Frame::Open  { id, window: u32::from_be_bytes( payload.as_slice().try_into().ok()? ) },
+			CREDIT => Frame::Credit{ id, credit: u32::from_be_bytes( payload.as_slice().try_into().ok()? ) },
+			DATA   => Frame::Data  { id, data  : payload                                                    },
+			CLOSE  if payload.is_empty() => Frame::Close{ id },
+			RESET  if payload.is_empty() => Frame::Reset{ id },
+			_      => return None,
+		};
+
+		Some( frame )
+	}
+}
+
+
+
+#[ cfg( test ) ]
+//
+mod tests
+{
+	use super::*;
+
+
+	#[ test ]
+	//
+	fn round_trip()
+	{
+		let frames = vec!
+		[
+			Frame::Open  { id: 1         , window: 65_536      } ,
+			Frame::Data  { id: 2         , data  : vec![ 1, 2 ] } ,
+			Frame::Data  { id: 3         , data  : Vec::new()   } ,
+			Frame::Credit{ id: 4         , credit: 10          } ,
+			Frame::Close { id: u32::MAX                        } ,
+			Frame::Reset { id: 0                               } ,
+		];
+
+		for frame in frames
+		{
+			assert_eq!( Some( frame.clone() ), Frame::decode( frame.encode() ) );
+		}
+	}
+
+
+	#[ test ]
+	//
+	fn invalid()
+	{
+		assert_eq!( None, Frame::decode( vec![ 0, 0, 0, 1       ] ) ); // too short
+		assert_eq!( None, Frame::decode( vec![ 0, 0, 0, 1, 9    ] ) ); // unknown kind
+		assert_eq!( None, Frame::decode( vec![ 0, 0, 0, 1, 0, 1 ] ) ); // open without a full window
+		assert_eq!( None, Frame::decode( vec![ 0, 0, 0, 1, 3, 1 ] ) ); // close with a payload
+	}
+}
@@ -0,0 +1,774 @@
+use
+{
+	crate :: { import::*, WsMessageStream, WsBackend, WsErr   } ,
+	super :: { MuxConfig, frame::Frame, wake_all::WakeAll     } ,
+	std   :: { collections::HashMap                           } ,
+};
+
+
+// How many control frames can be queued before we stop reading. Some incoming frames, like opens we refuse,
+// are answered with a control frame, so a remote that sends those faster than it reads our answers would
+// otherwise grow the queue without limit.
+//
+const MAX_CONTROL: usize = 64;
+
+
+// The state of one substream.
+//
+#[ derive( Debug ) ]
+//
+struct StreamState
+{
+	// Data received but not read yet.
+	//
+	read_buf: VecDeque<u8>,
+
+	read_waker : Option<Waker>,
+	write_waker: Option<Waker>,
+
+	// How many bytes we may send before the remote gives us more credit.
+	//
+	send_credit: u64,
+
+	// How many bytes the remote may send before it exceeds the window we gave it.
+	//
+	recv_allowed: u64,
+
+	// Bytes read by the user that we haven't given back to the remote as credit yet.
+	//
+	unacked: u64,
+
+	local_closed : bool,
+	remote_closed: bool,
+	reset        : bool,
+}
+
+
+impl StreamState
+{
+	fn new( send_credit: u64, window: u64 ) -> Self
+	{
+		Self
+		{
+			read_buf     : VecDeque::new() ,
+			read_waker   : None            ,
+			write_waker  : None            ,
+			send_credit                    ,
+			recv_allowed : window          ,
+			unacked      : 0               ,
+			local_closed : false           ,
+			remote_closed: false           ,
+			reset        : false           ,
+		}
+	}
+
+
+	fn wake( &mut self )
+	{
+		if let Some( waker ) = self.read_waker .take() { waker.wake() }
+		if let Some( waker ) = self.write_waker.take() { waker.wake() }
+	}
+}
+
+
+
+// The shared state of a Mux and all of its substreams. There is no background task, every poll of a
+// substream or of the accepting Mux drives the connection: it sends queued control frames and dispatches
+// incoming frames to the substreams they belong to, waking them up.
+//
+// Data is sent directly from poll_write, but control frames (open, credit, close, reset) are queued and sent
+// before any data, so a substream is always opened before its data arrives.
+//
+pub(super) struct Inner<B> where B: WsBackend
+{
+	ws          : WsMessageStream<B>             ,
+	config      : MuxConfig                      ,
+	next_id     : u32                            ,
+	streams     : HashMap<u32, StreamState>      ,
+	accept      : VecDeque<u32>                  ,
+	accept_waker: Option<Waker>                  ,
+	control     : VecDeque< Vec<u8> >            ,
+	needs_flush : bool                           ,
+	read_wake   : Arc<WakeAll>                   ,
+	write_wake  : Arc<WakeAll>                   ,
+
+	// How many of the substreams the remote opened, to hold it to max_streams.
+	//
+	remote_count: usize,
+
+	// The websocket returned None.
+	//
+	ended: bool,
+
+	// We started closing the websocket.
+	//
+	closing: bool,
+
+	// A fatal error, every substream gets a copy.
+	//
+	error: Option<WsErr>,
+}
+
+
+
+impl<B> Inner<B> where B: WsBackend
+{
+	pub(super) fn new( ws: WsMessageStream<B>, role: Role, config: MuxConfig ) -> Self
+	{
+		// Each side uses its own ids, so both can open substreams at the same time.
+		//
+		let next_id = match role
+		{
+			Role::Client => 1,
+			Role::Server => 2,
+		};
+
+		Self
+		{
+			ws                               ,
+			config                           ,
+			next_id                          ,
+			streams     : HashMap::new()     ,
+			remote_count: 0                  ,
+			accept      : VecDeque::new()    ,
+			accept_waker: None               ,
+			control     : VecDeque::new()    ,
+			needs_flush : false              ,
+			read_wake   : Arc::default()     ,
+			write_wake  : Arc::default()     ,
+			ended       : false              ,
+			closing     : false              ,
+			error       : None               ,
+		}
+	}
+
+
+	pub(super) fn open( &mut self ) -> Result<u32, WsErr>
+	{
+		if let Some( err ) = &self.error
+		{
+			return Err( err.clone() );
+		}
+
+		if self.ended || self.closing
+		{
+			return Err( WsErr::Closed );
+		}
+
+		let id = self.next_id;
+
+		// We ran out of ids, which takes two billion substreams.
+		//
+		self.next_id = id.checked_add( 2 ).ok_or( WsErr::Closed )?;
+
+		self.streams.insert( id, StreamState::new( 0, self.config.window.into() ) );
+		self.control.push_back( Frame::Open{ id, window: self.config.window }.encode() );
+
+		Ok( id )
+	}
+
+
+	// Abort a substream in both directions.
+	//
+	pub(super) fn reset( &mut self, id: u32 )
+	{
+		if let Some( stream ) = self.streams.get_mut( &id )
+		{
+			if !stream.reset
+			{
+				stream.reset = true;
+				stream.read_buf.clear();
+				stream.wake();
+
+				self.control.push_back( Frame::Reset{ id }.encode() );
+			}
+		}
+	}
+
+
+	// The handle of a substream was dropped. If it wasn't closed in both directions, tell the remote we
+	// are gone.
+	//
+	pub(super) fn remove( &mut self, id: u32 )
+	{
+		if let Some( stream ) = self.streams.remove( &id )
+		{
+			if !self.is_local( id )
+			{
+				self.remote_count -= 1;
+			}
+
+			let done = stream.reset || ( stream.local_closed && stream.remote_closed );
+
+			if !done
+			{
+				self.control.push_back( Frame::Reset{ id }.encode() );
+			}
+		}
+	}
+
+
+	// Whether we opened the substream with this id.
+	//
+	fn is_local( &self, id: u32 ) -> bool
+	{
+		id % 2 == self.next_id % 2
+	}
+
+
+	fn fail( &mut self, err: WsErr )
+	{
+		if self.error.is_none()
+		{
+			error!( "mux connection failed: {err}" );
+			self.error = Some( err );
+		}
+
+		self.wake_all();
+	}
+
+
+	fn wake_all( &mut self )
+	{
+		for stream in self.streams.values_mut()
+		{
+			stream.wake();
+		}
+
+		if let Some( waker ) = self.accept_waker.take()
+		{
+			waker.wake();
+		}
+	}
+
+
+	// Send the queued control frames and flush them.
+	//
+	fn poll_control( &mut self, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		if let Some( err ) = &self.error
+		{
+			return Poll::Ready( Err( err.clone().into() ) );
+		}
+
+		// Once the websocket is closed, there is nobody to tell anything.
+		//
+		if self.ended || self.closing
+		{
+			self.control.clear();
+			return Poll::Ready( Ok(()) );
+		}
+
+		self.write_wake.register( cx.waker() );
+
+		let     waker = Waker::from( self.write_wake.clone() );
+		let mut wcx   = Context::from_waker( &waker );
+
+		while let Some( frame ) = self.control.pop_front()
+		{
+			match Pin::new( &mut self.ws ).poll_ready( &mut wcx )
+			{
+				Poll::Ready( Ok(()) ) => {}
+
+				Poll::Pending =>
+				{
+					self.control.push_front( frame );
+					return Poll::Pending;
+				}
+
+				Poll::Ready( Err(e) ) => return Poll::Ready( Err( self.fail_io(e) ) ),
+			}
+
+			if let Err(e) = Pin::new( &mut self.ws ).start_send( frame )
+			{
+				return Poll::Ready( Err( self.fail_io(e) ) );
+			}
+
+			self.needs_flush = true;
+		}
+
+		if self.needs_flush
+		{
+			if let Err(e) = ready!( Pin::new( &mut self.ws ).poll_flush( &mut wcx ) )
+			{
+				return Poll::Ready( Err( self.fail_io(e) ) );
+			}
+
+			self.needs_flush = false;
+		}
+
+		Poll::Ready( Ok(()) )
+	}
+
+
+	// Send control frames when the websocket is ready for them, without waiting for it. For the read
+	// side, which shouldn't block on writing. Errors are stored and returned on the next poll.
+	//
+	fn drive_control( &mut self, cx: &mut Context<'_> )
+	{
+		let _ = self.poll_control( cx );
+	}
+
+
+	fn fail_io( &mut self, err: io::Error ) -> io::Error
+	{
+		let err = WsErr::from_io( err );
+
+		self.fail( err.clone() );
+
+		err.into()
+	}
+
+
+	// Dispatch incoming frames until the websocket is pending. Returns Ready when it ended or failed.
+	//
+	fn poll_incoming( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		self.read_wake.register( cx.waker() );
+
+		let     waker = Waker::from( self.read_wake.clone() );
+		let mut rcx   = Context::from_waker( &waker );
+
+		loop
+		{
+			if self.ended || self.error.is_some()
+			{
+				return Poll::Ready(());
+			}
+
+			// Only read on once the answers we owe the remote are sent. The write side wakes us.
+			//
+			if self.control.len() >= MAX_CONTROL && self.poll_control( cx ).is_pending()
+			{
+				return Poll::Pending;
+			}
+
+			match ready!( Pin::new( &mut self.ws ).poll_next( &mut rcx ) )
+			{
+				Some( Ok ( msg ) ) => self.dispatch( msg ),
+				Some( Err( err ) ) => { self.fail_io( err ); }
+
+				None =>
+				{
+					self.ended = true;
+					self.wake_all();
+				}
+			}
+		}
+	}
+
+
+	fn dispatch( &mut self, msg: Vec<u8> )
+	{
+		let Some( frame ) = Frame::decode( msg ) else
+		{
+			warn!( "received an invalid mux frame" );
+			return self.fail( WsErr::Protocol );
+		};
+
+		trace!( "mux received: {frame:?}" );
+
+		match frame
+		{
+			Frame::Open{ id, window } =>
+			{
+				// The remote must use the other ids. Refuse the substream, but don't touch one of ours that
+				// might have the same id.
+				//
+				if self.is_local( id )
+				{
+					warn!( "remote opened substream {id}, which is not a valid id" );
+					return self.control.push_back( Frame::Reset{ id }.encode() );
+				}
+
+				// The remote never opens the same substream twice, so it doesn't follow the protocol.
+				//
+				if self.streams.contains_key( &id )
+				{
+					warn!( "remote opened substream {id}, which is already open" );
+					return self.fail( WsErr::Protocol );
+				}
+
+				if self.remote_count >= self.config.max_streams
+				{
+					warn!( "remote opened substream {id}, but it already has {} open", self.remote_count );
+					return self.control.push_back( Frame::Reset{ id }.encode() );
+				}
+
+				self.remote_count += 1;
+				self.streams.insert( id, StreamState::new( window.into(), self.config.window.into() ) );
+				self.control.push_back( Frame::Credit{ id, credit: self.config.window }.encode() );
+				self.accept.push_back( id );
+
+				if let Some( waker ) = self.accept_waker.take()
+				{
+					waker.wake();
+				}
+			}
+
+			Frame::Data{ id, data } =>
+			{
+				// Frames for a substream we already dropped are expected, the remote didn't know yet.
+				//
+				let Some( stream ) = self.streams.get_mut( &id ) else { return };
+
+				if stream.reset
+				{
+					return;
+				}
+
+				if stream.remote_closed || data.len() as u64 > stream.recv_allowed
+				{
+					warn!( "remote sent data on substream {id} after closing it or without credit" );
+					return self.reset( id );
+				}
+
+				stream.recv_allowed -= data.len() as u64;
+				stream.read_buf.extend( data );
+
+				if let Some( waker ) = stream.read_waker.take() { waker.wake() }
+			}
+
+			Frame::Credit{ id, credit } =>
+			{
+				if let Some( stream ) = self.streams.get_mut( &id )
+				{
+					stream.send_credit += u64::from( credit );
+
+					if let Some( waker ) = stream.write_waker.take() { waker.wake() }
+				}
+			}
+
+			Frame::Close{ id } =>
+			{
+				if let Some( stream ) = self.streams.get_mut( &id )
+				{
+					stream.remote_closed = true;
+
+					if let Some( waker ) = stream.read_waker.take() { waker.wake() }
+				}
+			}
+
+			Frame::Reset{ id } =>
+			{
+				if let Some( stream ) = self.streams.get_mut( &id )
+				{
+					stream.reset = true;
+					stream.read_buf.clear();
+					stream.wake();
+				}
+			}
+		}
+	}
+
+
+	pub(super) fn poll_accept( &mut self, cx: &mut Context<'_> ) -> Poll< Option<u32> >
+	{
+		let result = self.poll_accept_inner( cx );
+
+		self.drive_control( cx );
+
+		result
+	}
+
+
+	fn poll_accept_inner( &mut self, cx: &mut Context<'_> ) -> Poll< Option<u32> >
+	{
+		loop
+		{
+			while let Some( id ) = self.accept.pop_front()
+			{
+				if self.streams.contains_key( &id )
+				{
+					return Poll::Ready( Some( id ) );
+				}
+			}
+
+			if self.ended || self.error.is_some()
+			{
+				return Poll::Ready( None );
+			}
+
+			if self.poll_incoming( cx ).is_pending()
+			{
+				if !self.accept.is_empty()
+				{
+					continue;
+				}
+
+				self.accept_waker = Some( cx.waker().clone() );
+
+				return Poll::Pending;
+			}
+		}
+	}
+
+
+	pub(super) fn poll_read( &mut self, id: u32, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		let result = self.poll_read_inner( id, cx, buf );
+
+		// Send the credit for what we just read.
+		//
+		self.drive_control( cx );
+
+		result
+	}
+
+
+	fn poll_read_inner( &mut self, id: u32, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		loop
+		{
+			if let Some( result ) = self.try_read( id, buf )
+			{
+				return Poll::Ready( result );
+			}
+
+			if self.poll_incoming( cx ).is_pending()
+			{
+				if let Some( result ) = self.try_read( id, buf )
+				{
+					return Poll::Ready( result );
+				}
+
+				if let Some( stream ) = self.streams.get_mut( &id )
+				{
+					stream.read_waker = Some( cx.waker().clone() );
+				}
+
+				return Poll::Pending;
+			}
+		}
+	}
+
+
+	// Returns None when we have to wait for the remote.
+	//
+	fn try_read( &mut self, id: u32, buf: &mut [u8] ) -> Option< io::Result<usize> >
+	{
+		let Some( stream ) = self.streams.get_mut( &id ) else
+		{
+			return Some( Err( WsErr::Closed.into() ) );
+		};
+
+		if stream.reset
+		{
+			return Some( Err( WsErr::Reset.into() ) );
+		}
+
+		if !stream.read_buf.is_empty()
+		{
+			let n = buf.len().min( stream.read_buf.len() );
+
+			for (dst, src) in buf.iter_mut().zip( stream.read_buf.drain( ..n ) )
+			{
+				*dst = src;
+			}
+
+			// Give credit back in batches, not for every read.
+			//
+			stream.unacked += n as u64;
+
+			if !stream.remote_closed && stream.unacked >= ( u64::from( self.config.window ) / 2 ).max( 1 )
+			{
+				// unacked is never more than the window, which is a u32.
+				//
+				let credit = u32::try_from( stream.unacked ).unwrap_or( u32::MAX );
+
+				stream.recv_allowed += u64::from( credit );
+				stream.unacked      -= u64::from( credit );
+
+				self.control.push_back( Frame::Credit{ id, credit }.encode() );
+			}
+
+			return Some( Ok(n) );
+		}
+
+		if buf.is_empty() || stream.remote_closed
+		{
+			return Some( Ok(0) );
+		}
+
+		if let Some( err ) = &self.error
+		{
+			return Some( Err( err.clone().into() ) );
+		}
+
+		// The connection ended without the remote closing this substream.
+		//
+		if self.ended
+		{
+			return Some( Err( WsErr::Aborted.into() ) );
+		}
+
+		None
+	}
+
+
+	fn write_error( &self, id: u32 ) -> Option<io::Error>
+	{
+		if let Some( err ) = &self.error
+		{
+			return Some( err.clone().into() );
+		}
+
+		match self.streams.get( &id )
+		{
+			Some( stream ) if stream.reset => Some( WsErr::Reset.into() ),
+			Some( stream ) if stream.local_closed => Some( WsErr::Closed.into() ),
+			Some(_) if self.ended || self.closing => Some( WsErr::Closed.into() ),
+			Some(_) => None,
+			None    => Some( WsErr::Closed.into() ),
+		}
+	}
+
+
+	pub(super) fn poll_write( &mut self, id: u32, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		let credit = loop
+		{
+			if let Some( err ) = self.write_error( id )
+			{
+				return Poll::Ready( Err( err ) );
+			}
+
+			if buf.is_empty()
+			{
+				return Poll::Ready( Ok(0) );
+			}
+
+			ready!( self.poll_control( cx ) )?;
+
+			let credit = self.streams.get( &id ).map_or( 0, |s| s.send_credit );
+
+			if credit > 0
+			{
+				break credit;
+			}
+
+			// The remote can only give credit for data it received, so don't keep it in our buffer.
+			//
+			let _ = self.poll_flush( cx );
+
+			// Wait for the remote to give us credit. Since that arrives on the incoming side, which might
+			// not be polled by anyone else, drive it from here.
+			//
+			if self.poll_incoming( cx ).is_pending()
+			{
+				self.drive_control( cx );
+
+				if let Some( stream ) = self.streams.get_mut( &id )
+				{
+					if stream.send_credit == 0 && !stream.reset
+					{
+						stream.write_waker = Some( cx.waker().clone() );
+						return Poll::Pending;
+					}
+				}
+			}
+		};
+
+		let n = ( buf.len() as u64 ).min( credit ).min( self.config.max_frame as u64 ) as usize;
+
+		self.write_wake.register( cx.waker() );
+
+		let     waker = Waker::from( self.write_wake.clone() );
+		let mut wcx   = Context::from_waker( &waker );
+
+		if let Err(e) = ready!( Pin::new( &mut self.ws ).poll_ready( &mut wcx ) )
+		{
+			return Poll::Ready( Err( self.fail_io(e) ) );
+		}
+
+		if let Err(e) = Pin::new( &mut self.ws ).start_send( Frame::encode_data( id, &buf[ ..n ] ) )
+		{
+			return Poll::Ready( Err( self.fail_io(e) ) );
+		}
+
+		if let Some( stream ) = self.streams.get_mut( &id )
+		{
+			stream.send_credit -= n as u64;
+		}
+
+		Poll::Ready( Ok(n) )
+	}
+
+
+	pub(super) fn poll_flush( &mut self, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		ready!( self.poll_control( cx ) )?;
+
+		if self.ended || self.closing
+		{
+			return Poll::Ready( Ok(()) );
+		}
+
+		let     waker = Waker::from( self.write_wake.clone() );
+		let mut wcx   = Context::from_waker( &waker );
+
+		match ready!( Pin::new( &mut self.ws ).poll_flush( &mut wcx ) )
+		{
+			Ok (()) => Poll::Ready( Ok(()) )               ,
+			Err(e ) => Poll::Ready( Err( self.fail_io(e) ) ) ,
+		}
+	}
+
+
+	// Close our side of a substream. The remote reads EOF after the data we sent.
+	//
+	pub(super) fn poll_close( &mut self, id: u32, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		if let Some( stream ) = self.streams.get_mut( &id )
+		{
+			if !stream.local_closed && !stream.reset
+			{
+				stream.local_closed = true;
+				self.control.push_back( Frame::Close{ id }.encode() );
+			}
+		}
+
+		self.poll_flush( cx )
+	}
+
+
+	// Close the websocket. Substreams that are still open will fail.
+	//
+	pub(super) fn poll_close_mux( &mut self, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		if !self.closing
+		{
+			ready!( self.poll_control( cx ) )?;
+			self.closing = true;
+		}
+
+		if self.ended
+		{
+			return Poll::Ready( Ok(()) );
+		}
+
+		self.write_wake.register( cx.waker() );
+
+		let     waker = Waker::from( self.write_wake.clone() );
+		let mut wcx   = Context::from_waker( &waker );
+
+		Pin::new( &mut self.ws ).poll_close( &mut wcx )
+	}
+}
+
+
+
+impl<B> fmt::Debug for Inner<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "Inner" )
+
+			.field( "ws"     , &self.ws             )
+			.field( "streams", &self.streams.len()  )
+			.field( "ended"  , &self.ended          )
+			.field( "closing", &self.closing        )
+			.field( "error"  , &self.error          )
+			.finish_non_exhaustive()
+	}
+}
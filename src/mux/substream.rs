@@ -0,0 +1,153 @@
+use crate::{ import::*, WsBackend };
+use super::{ Shared, lock };
+
+
+/// One byte stream of a [`Mux`](crate::Mux). It implements `AsyncRead`/`AsyncWrite`.
+///
+/// Closing it tells the remote we won't write anymore, after which the remote reads EOF. We can still read
+/// until the remote closes its side. Dropping it before both sides are closed resets it, as does
+/// [`Substream::reset`].
+///
+/// ## Errors
+///
+/// All errors wrap a [`WsErr`](crate::WsErr):
+///
+/// - [`io::ErrorKind::ConnectionReset`] with [`WsErr::Reset`](crate::WsErr::Reset): The substream was reset
+///   by either side.
+/// - [`io::ErrorKind::ConnectionAborted`] with [`WsErr::Aborted`](crate::WsErr::Aborted): Reading after the
+///   connection ended without the remote closing this substream.
+/// - [`io::ErrorKind::NotConnected`] with [`WsErr::Closed`](crate::WsErr::Closed): Writing after closing this
+///   substream, the [`Mux`](crate::Mux) or when the connection ended.
+/// - Errors of the connection, which all substreams get a copy of.
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "mux" )) ) ]
+//
+pub struct Substream<B> where B: WsBackend
+{
+	id    : u32       ,
+	shared: Shared<B> ,
+}
+
+
+
+impl<B> Substream<B> where B: WsBackend
+{
+	pub(super) fn new( id: u32, shared: Shared<B> ) -> Self
+	{
+		Self { id, shared }
+	}
+
+
+	/// The id of this substream. Substreams opened by the client side have odd ids, those opened by the
+	/// server even ones.
+	//
+	pub fn id( &self ) -> u32
+	{
+		self.id
+	}
+
+
+	/// Abort this substream in both directions. Data we haven't read yet is discarded and the remote gets
+	/// [`io::ErrorKind::ConnectionReset`]. The remote is told when the mux is polled next.
+	//
+	pub fn reset( &mut self )
+	{
+		lock( &self.shared ).reset( self.id );
+	}
+}
+
+
+
+impl<B> Drop for Substream<B> where B: WsBackend
+{
+	fn drop( &mut self )
+	{
+		lock( &self.shared ).remove( self.id );
+	}
+}
+
+
+
+impl<B> fmt::Debug for Substream<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "Substream" ).field( "id", &self.id ).finish_non_exhaustive()
+	}
+}
+
+
+
+impl<B> AsyncRead for Substream<B> where B: WsBackend
+{
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		lock( &self.shared ).poll_read( self.id, cx, buf )
+	}
+}
+
+
+
+impl<B> AsyncWrite for Substream<B> where B: WsBackend
+{
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		lock( &self.shared ).poll_write( self.id, cx, buf )
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		lock( &self.shared ).poll_flush( cx )
+	}
+
+
+	fn poll_close( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		lock( &self.shared ).poll_close( self.id, cx )
+	}
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<B> TokAsyncRead for Substream<B> where B: WsBackend
+{
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_> ) -> Poll< io::Result<()> >
+	{
+		let n = ready!( lock( &self.shared ).poll_read( self.id, cx, buf.initialize_unfilled() ) )?;
+
+		buf.advance( n );
+
+		Poll::Ready( Ok(()) )
+	}
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<B> TokAsyncWrite for Substream<B> where B: WsBackend
+{
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		lock( &self.shared ).poll_write( self.id, cx, buf )
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		lock( &self.shared ).poll_flush( cx )
+	}
+
+
+	fn poll_shutdown( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		lock( &self.shared ).poll_close( self.id, cx )
+	}
+}
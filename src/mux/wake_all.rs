@@ -0,0 +1,50 @@
+use crate::{ import::* };
+
+
+// The websocket only remembers the last waker it was polled with, but several substreams can be waiting
+// on it from different tasks. We poll it with this waker instead, which wakes every task that registered
+// since the last wake up. Otherwise a substream could hang when the task that polled last is not
+// interested anymore.
+//
+#[ derive( Debug, Default ) ]
+//
+pub(super) struct WakeAll
+{
+	wakers: Mutex< Vec<Waker> >,
+}
+
+
+
+impl WakeAll
+{
+	pub(super) fn register( &self, waker: &Waker )
+	{
+		let mut wakers = self.wakers.lock().unwrap_or_else( PoisonError::into_inner );
+
+		if !wakers.iter().any( |w| w.will_wake( waker ) )
+		{
+			wakers.push( waker.clone() );
+		}
+	}
+}
+
+
+
+impl Wake for WakeAll
+{
+	fn wake( self: Arc<Self> )
+	{
+		self.wake_by_ref();
+	}
+
+
+	fn wake_by_ref( self: &Arc<Self> )
+	{
+		let wakers = std::mem::take( &mut *self.wakers.lock().unwrap_or_else( PoisonError::into_inner ) );
+
+		for waker in wakers
+		{
+			waker.wake();
+		}
+	}
+}
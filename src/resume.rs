@@ -0,0 +1,1021 @@
+mod frame;
+
+use
+{
+	crate        :: { import::*, WsStream, WsBackend, WsErr                        } ,
+	futures_util :: { stream::{ self, StreamExt }                                 } ,
+	frame        :: { Frame, Hello, HELLO_LEN                                      } ,
+	std          :: { hash::{ BuildHasher, Hasher }, collections::hash_map::RandomState } ,
+};
+
+
+// Acknowledge consumed data at least this often, even when we haven't read everything yet.
+//
+const ACK_INTERVAL: u64 = 16 * 1024;
+
+
+type Connecting<B> = Pin< Box< dyn Future< Output = Result< WsStream<B>, WsErr > > + Send > >;
+
+
+// Where new connections come from.
+//
+enum Reconnect<B> where B: WsBackend
+{
+	// On the client, a closure opens them.
+	//
+	Connect( Box< dyn FnMut() -> Connecting<B> + Send > ),
+
+	// On the server, they arrive as requests for this session.
+	//
+	Accept( Pin< Box< dyn Stream< Item = ResumeRequest<B> > + Send > > ),
+}
+
+
+enum State<B> where B: WsBackend
+{
+	// We need a new connection.
+	//
+	Down,
+
+	// The connect closure of the client is running.
+	//
+	Connecting( Connecting<B> ),
+
+	// Connected. `hello` is whether the remote introduced itself on this connection yet. Until then we don't
+	// know what to send.
+	//
+	Up{ ws: Box< WsStream<B> >, hello: bool },
+
+	// Both sides closed and the connection ended, or we failed.
+	//
+	Ended,
+}
+
+
+
+/// Identifies the session of a [`ResumableWsStream`] across connections. The client side picks a random one
+/// and the server uses it to find the session a new connection belongs to.
+///
+/// It's generated from the random keys of the std hash map, which is not meant for secrets. If a connection
+/// should not be able to take over a session just by knowing the id, authenticate it, eg. in the http upgrade.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Hash ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "resume" )) ) ]
+//
+pub struct SessionId( [u8; 16] );
+
+
+
+impl SessionId
+{
+	/// A new random session id.
+	//
+	pub fn random() -> Self
+	{
+		let state = RandomState::new();
+		let mut id = [0; 16];
+
+		for (i, chunk) in id.chunks_mut( 8 ).enumerate()
+		{
+			let mut hasher = state.build_hasher();
+
+			hasher.write_usize( i );
+			hasher.write_u128( SystemTime::now().duration_since( SystemTime::UNIX_EPOCH ).unwrap_or_default().as_nanos() );
+
+			chunk.copy_from_slice( &hasher.finish().to_be_bytes() );
+		}
+
+		Self( id )
+	}
+
+
+	/// Create a session id from bytes, eg. to look one up that was stored elsewhere.
+	//
+	pub fn from_bytes( bytes: [u8; 16] ) -> Self
+	{
+		Self( bytes )
+	}
+
+
+	/// The bytes of the session id.
+	//
+	pub fn as_bytes( &self ) -> &[u8; 16]
+	{
+		&self.0
+	}
+}
+
+
+
+impl fmt::Display for SessionId
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		self.0.iter().try_for_each( |b| write!( f, "{b:02x}" ) )
+	}
+}
+
+
+
+/// A new connection to the server side of a [`ResumableWsStream`], of which we read the session id. Look up the
+/// session with [`ResumeRequest::session_id`] and hand the request to the stream of requests of that
+/// session, or start a new session with [`ResumableWsStream::accept`]. Dropping it refuses the connection.
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "resume" )) ) ]
+//
+pub struct ResumeRequest<B> where B: WsBackend
+{
+	ws   : WsStream<B>,
+	hello: Hello,
+}
+
+
+
+impl<B> ResumeRequest<B> where B: WsBackend
+{
+	/// Read the introduction the client sends at the start of each connection.
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::Protocol`]: The client did not start with a valid introduction.
+	/// - Any error from reading the `WsStream`. When the connection ends first, it's an
+	///   [`io::ErrorKind::UnexpectedEof`] in [`WsErr::Io`].
+	//
+	pub async fn read( mut ws: WsStream<B> ) -> Result<Self, WsErr>
+	{
+		let mut buf    = [0; HELLO_LEN];
+		let mut filled = 0;
+
+		while filled < HELLO_LEN
+		{
+			let n = future::poll_fn( |cx| AsyncRead::poll_read( Pin::new( &mut ws ), cx, &mut buf[ filled.. ] ) ).await
+
+				.map_err( WsErr::from_io )?
+			;
+
+			if n == 0
+			{
+				return Err( io::Error::from( io::ErrorKind::UnexpectedEof ).into() );
+			}
+
+			filled += n;
+		}
+
+		match Frame::decode( &buf )
+		{
+			Ok( Some(( Frame::Hello( hello ), _ )) ) => Ok( Self{ ws, hello } ),
+			_                                        => Err( WsErr::Protocol ),
+		}
+	}
+
+
+	/// The session the client wants to continue, or start when it's new to us.
+	//
+	pub fn session_id( &self ) -> SessionId
+	{
+		self.hello.session
+	}
+}
+
+
+
+impl<B> fmt::Debug for ResumeRequest<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ResumeRequest" ).field( "session", &self.hello.session ).finish_non_exhaustive()
+	}
+}
+
+
+
+/// An `AsyncRead`/`AsyncWrite` byte stream that survives losing the connection. When the connection drops,
+/// it gets a new [`WsStream`] and continues where it left off, so codecs running on top don't notice.
+///
+/// Data we write is numbered and kept until the remote acknowledges it. On each new connection, both sides
+/// exchange the session id and how much data they received, after which we resend what the remote is
+/// missing. The remote acknowledges data as it is read, and each side tells the other in its hello how much
+/// unread data it accepts, see [`ResumableWsStream::max_unacked`]. So a writer only gets as far ahead of
+/// the reader on the other side as both allow.
+///
+/// The client side creates the stream with [`ResumableWsStream::connect`] and a closure that opens a new
+/// connection. It's called for the first connection and whenever the connection is lost, so it can wait
+/// before retrying. The server side reads the session id of each incoming connection with
+/// [`ResumeRequest::read`], and passes it to the session it belongs to, eg. through a channel:
+///
+/// ```no_run
+/// use
+/// {
+///    ws_stream_tungstenite :: { ResumableWsStream, ResumeRequest, WsStream, WsErr } ,
+///    futures               :: { AsyncWriteExt, channel::mpsc                    } ,
+///    async_std             :: { net::TcpStream                                  } ,
+///    async_tungstenite     :: { async_std::connect_async                        } ,
+///    std                   :: { collections::HashMap                            } ,
+/// };
+///
+/// # async fn run() -> Result<(), WsErr> {
+/// // On the client.
+/// //
+/// let mut stream = ResumableWsStream::connect( ||
+/// {
+///    async
+///    {
+///       let (socket, _) = connect_async( "ws://127.0.0.1:3012" ).await?;
+///       Ok( WsStream::new( socket ) )
+///    }
+/// });
+///
+/// stream.write_all( b"survives reconnects" ).await?;
+/// stream.flush().await?;
+///
+/// // On the server, for each incoming WsStream.
+/// //
+/// # let mut sessions: HashMap<_, mpsc::UnboundedSender<_>> = HashMap::new();
+/// # let ws: WsStream<async_tungstenite::WebSocketStream<async_tungstenite::async_std::ConnectStream>> = todo!();
+/// let request = ResumeRequest::read( ws ).await?;
+///
+/// match sessions.get( &request.session_id() )
+/// {
+///    Some( tx ) => { let _ = tx.unbounded_send( request ); }
+///
+///    None =>
+///    {
+///       let (tx, rx) = mpsc::unbounded();
+///       sessions.insert( request.session_id(), tx );
+///
+///       let stream = ResumableWsStream::accept( request, rx );
+///
+///       // use the stream...
+///    }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Data is buffered, so flush to make sure it's sent. Closing waits until the remote acknowledged all our
+/// data. Only after both sides closed, the end of the connection is the end of the stream. Until then, any
+/// error on the connection makes us reconnect, so set [`WsStreamConfig::keepalive`](crate::WsStreamConfig::keepalive)
+/// on the `WsStream`s to notice a connection that went silent.
+///
+/// Poll it from one task, or split it with `futures::io::split`.
+///
+/// ## Errors
+///
+/// - [`WsErr::SessionLost`]: The remote does not know our session anymore, or we don't have the data it is
+///   missing.
+/// - [`WsErr::Protocol`]: The remote sent invalid data, or more data than we accept before reading it.
+/// - Any error returned by the connect closure, which is how the client gives up on reconnecting.
+/// - [`WsErr::Closed`]: The stream of requests for a session on the server ended while we needed a new
+///   connection.
+/// - [`io::ErrorKind::NotConnected`]: Writing after closing.
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "resume" )) ) ]
+//
+pub struct ResumableWsStream<B> where B: WsBackend
+{
+	session    : SessionId  ,
+	reconnect  : Reconnect<B> ,
+	state      : State<B>   ,
+	connections: u64        ,
+	max_unacked: usize      ,
+
+	// Data we wrote that the remote did not acknowledge yet. The first byte is at offset `replay_start` in
+	// the session.
+	//
+	replay      : VecDeque<u8> ,
+	replay_start: u64          ,
+
+	// How much of our data the remote consumed and how much more it accepts. We don't send beyond that.
+	//
+	remote_consumed: u64,
+	remote_window  : u64,
+
+	// Up to which offset our data is sent on the current connection, and whether close is.
+	//
+	sent      : u64  ,
+	close_sent: bool ,
+
+	// Encoded frames for the current connection, written from out_pos.
+	//
+	out        : Vec<u8> ,
+	out_pos    : usize   ,
+	needs_flush: bool    ,
+
+	// Bytes from the connection that don't make a whole frame yet.
+	//
+	in_buf: Vec<u8>,
+
+	// Data received but not read yet.
+	//
+	read_buf: VecDeque<u8>,
+
+	// How much data we received, how much of it was read and what we told the remote about that.
+	//
+	received: u64,
+	consumed: u64,
+	acked   : u64,
+
+	local_closed : bool,
+	remote_closed: bool,
+
+	error: Option<WsErr>,
+}
+
+
+
+impl<B> ResumableWsStream<B> where B: WsBackend + 'static
+{
+	/// The default for [`ResumableWsStream::max_unacked`]: 1MiB.
+	//
+	pub const DEFAULT_MAX_UNACKED: usize = 1024 * 1024;
+
+
+	/// Start a new session as the client. `connect` is called for the first connection when the stream is
+	/// polled, and again whenever the connection is lost. Return an error from it to give up.
+	//
+	pub fn connect<F, Fut>( mut connect: F ) -> Self
+
+		where F  : FnMut() -> Fut + Send + 'static                              ,
+		      Fut: Future< Output = Result< WsStream<B>, WsErr > > + Send + 'static ,
+	{
+		let reconnect = Reconnect::Connect( Box::new( move ||
+		{
+			let next: Connecting<B> = Box::pin( connect() );
+			next
+		}));
+
+		Self::new( SessionId::random(), reconnect )
+	}
+
+
+	/// Start a new session as the server, for a request with a session id we don't know. The connection of
+	/// the request is used when the stream is first polled. When it is lost, we wait for the next request for
+	/// this session on `reconnects`. When that ends, we give up.
+	//
+	pub fn accept<S>( request: ResumeRequest<B>, reconnects: S ) -> Self
+
+		where S: Stream< Item = ResumeRequest<B> > + Send + 'static
+	{
+		let session  = request.hello.session;
+		let requests = stream::iter( Some( request ) ).chain( reconnects );
+
+		Self::new( session, Reconnect::Accept( Box::pin( requests ) ) )
+	}
+
+
+	fn new( session: SessionId, reconnect: Reconnect<B> ) -> Self
+	{
+		Self
+		{
+			session                                      ,
+			reconnect                                    ,
+			state        : State::Down                   ,
+			connections  : 0                             ,
+			max_unacked  : Self::DEFAULT_MAX_UNACKED     ,
+			replay       : VecDeque::new()               ,
+			replay_start : 0                             ,
+			remote_consumed: 0                           ,
+			remote_window  : 0                           ,
+			sent         : 0                             ,
+			close_sent   : false                         ,
+			out          : Vec::new()                    ,
+			out_pos      : 0                             ,
+			needs_flush  : false                         ,
+			in_buf       : Vec::new()                    ,
+			read_buf     : VecDeque::new()               ,
+			received     : 0                             ,
+			consumed     : 0                             ,
+			acked        : 0                             ,
+			local_closed : false                         ,
+			remote_closed: false                         ,
+			error        : None                          ,
+		}
+	}
+}
+
+
+
+impl<B> ResumableWsStream<B> where B: WsBackend
+{
+	/// How much written data we keep until the remote acknowledges it. Writing waits when it's full. It's
+	/// also how much data we accept from the remote before we read it, which the remote learns when it
+	/// connects. At least 1.
+	//
+	pub fn max_unacked( mut self, max: usize ) -> Self
+	{
+		self.max_unacked = max.max( 1 );
+		self
+	}
+
+
+	/// The id of this session.
+	//
+	pub fn session_id( &self ) -> SessionId
+	{
+		self.session
+	}
+
+
+	/// How many connections this stream used so far.
+	//
+	pub fn connections( &self ) -> u64
+	{
+		self.connections
+	}
+
+
+	fn is_up( &self ) -> bool
+	{
+		matches!( self.state, State::Up{..} )
+	}
+
+
+	// Whether the remote introduced itself on the current connection.
+	//
+	fn hello( &self ) -> bool
+	{
+		matches!( self.state, State::Up{ hello: true, .. } )
+	}
+
+
+	fn fail( &mut self, err: WsErr )
+	{
+		if self.error.is_none()
+		{
+			error!( "session {} failed: {err}", self.session );
+			self.error = Some( err );
+		}
+
+		self.state = State::Ended;
+	}
+
+
+	fn io_error( &self ) -> Option<io::Error>
+	{
+		self.error.clone().map( Into::into )
+	}
+
+
+	// Start using a new connection. We introduce ourselves, telling the remote how much data we have and
+	// how much more it can send.
+	//
+	fn setup( &mut self, ws: WsStream<B>, hello: Option<Hello> )
+	{
+		self.connections += 1;
+		self.out.clear();
+		self.in_buf.clear();
+		self.out_pos     = 0;
+		self.needs_flush = false;
+		self.close_sent  = false;
+		self.acked       = self.consumed;
+
+		Hello
+		{
+			session : self.session            ,
+			received: self.received           ,
+			consumed: self.consumed           ,
+			window  : self.max_unacked as u64 ,
+		}
+		.encode( &mut self.out );
+
+		self.state = State::Up{ ws: Box::new( ws ), hello: false };
+
+		if let Some( hello ) = hello
+		{
+			self.on_hello( hello );
+		}
+	}
+
+
+	// The remote introduced itself. Drop the data it has and resend the rest.
+	//
+	fn on_hello( &mut self, hello: Hello )
+	{
+		let end = self.replay_start + self.replay.len() as u64;
+
+		if hello.consumed > hello.received
+		{
+			warn!( "the remote consumed more than it received in session {}", self.session );
+			return self.fail( WsErr::Protocol );
+		}
+
+		if hello.session != self.session || hello.received < self.replay_start || hello.received > end
+		{
+			warn!( "the remote can not resume session {}, it has {} bytes of our {end}", self.session, hello.received );
+			return self.fail( WsErr::SessionLost );
+		}
+
+		debug!( "session {} resumes at offset {}, resending {} bytes", self.session, hello.received, end - hello.received );
+
+		self.replay.drain( ..( hello.received - self.replay_start ) as usize );
+		self.replay_start    = hello.received;
+		self.sent            = hello.received;
+		self.remote_consumed = hello.consumed;
+		self.remote_window   = hello.window;
+
+		if let State::Up{ hello, .. } = &mut self.state
+		{
+			*hello = true;
+		}
+
+		self.send_replay();
+	}
+
+
+	// Send the data the remote doesn't have yet, as far as its window allows, and close once all of it is
+	// sent. Before the remote introduced itself, we don't know what it has.
+	//
+	fn send_replay( &mut self )
+	{
+		if !self.hello()
+		{
+			return;
+		}
+
+		let end   = self.replay_start + self.replay.len() as u64;
+		let limit = end.min( self.remote_consumed.saturating_add( self.remote_window ) );
+
+		if limit > self.sent
+		{
+			let from = ( self.sent - self.replay_start ) as usize;
+			let to   = ( limit     - self.replay_start ) as usize;
+
+			Frame::encode_data( &self.replay.make_contiguous()[ from..to ], &mut self.out );
+
+			self.sent = limit;
+		}
+
+		if self.local_closed && !self.close_sent && self.sent == end
+		{
+			Frame::encode_close( &mut self.out );
+			self.close_sent = true;
+		}
+	}
+
+
+	// Tell the remote it can drop what we read. Do it in batches, but always when we read everything, so a
+	// remote waiting for acknowledgements is never waiting on us.
+	//
+	fn ack( &mut self )
+	{
+		if self.hello()
+			&& self.consumed > self.acked
+			&& ( self.read_buf.is_empty() || self.consumed - self.acked >= ACK_INTERVAL )
+		{
+			Frame::encode_ack( self.consumed, &mut self.out );
+			self.acked = self.consumed;
+		}
+	}
+
+
+	// The connection failed or ended. Once both sides closed and the remote has all our data, that's the
+	// end of the stream, otherwise we need a new connection.
+	//
+	fn link_down( &mut self, err: Option<io::Error> )
+	{
+		if self.local_closed && self.remote_closed && self.replay.is_empty()
+		{
+			self.state = State::Ended;
+			return;
+		}
+
+		match err
+		{
+			Some( err ) => debug!( "session {} lost its connection: {err}", self.session ),
+			None        => debug!( "session {} lost its connection", self.session        ),
+		}
+
+		self.state = State::Down;
+	}
+
+
+	// Make sure we have a connection, or that we ended.
+	//
+	fn poll_link( &mut self, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		loop
+		{
+			if let Some( err ) = self.io_error()
+			{
+				return Poll::Ready( Err( err ) );
+			}
+
+			match &mut self.state
+			{
+				State::Up{..} | State::Ended => return Poll::Ready( Ok(()) ),
+
+				State::Down => match &mut self.reconnect
+				{
+					Reconnect::Connect( connect ) => self.state = State::Connecting( connect() ),
+
+					Reconnect::Accept( requests ) => match ready!( requests.as_mut().poll_next( cx ) )
+					{
+						Some( request ) => self.setup( request.ws, Some( request.hello ) ),
+						None            => self.fail( WsErr::Closed ),
+					},
+				},
+
+				State::Connecting( next ) => match ready!( next.as_mut().poll( cx ) )
+				{
+					Ok ( ws  ) => self.setup( ws, None ),
+					Err( err ) => self.fail( err ),
+				},
+			}
+		}
+	}
+
+
+	// Read from the connection until it is pending. Returns Ready when we lost the connection.
+	//
+	fn pump_in( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		let mut chunk = [0; 8 * 1024];
+
+		loop
+		{
+			let State::Up{ ws, .. } = &mut self.state else { return Poll::Ready(()) };
+
+			match ready!( AsyncRead::poll_read( Pin::new( ws ), cx, &mut chunk ) )
+			{
+				Ok (0) => self.link_down( None ),
+				Err(e) => self.link_down( Some(e) ),
+
+				Ok (n) =>
+				{
+					self.in_buf.extend_from_slice( &chunk[ ..n ] );
+					self.process();
+				}
+			}
+		}
+	}
+
+
+	// Handle the whole frames in in_buf.
+	//
+	fn process( &mut self )
+	{
+		let buf     = std::mem::take( &mut self.in_buf );
+		let mut pos = 0;
+
+		while self.is_up()
+		{
+			let (frame, len) = match Frame::decode( &buf[ pos.. ] )
+			{
+				Ok( Some( frame ) ) => frame,
+				Ok( None          ) => break,
+
+				Err(()) =>
+				{
+					warn!( "session {} received an invalid frame", self.session );
+					self.fail( WsErr::Protocol );
+					break;
+				}
+			};
+
+			pos += len;
+
+			match frame
+			{
+				Frame::Hello( hello ) if !self.hello() => self.on_hello( hello ),
+
+				// Anything else must come after the hello, and only once.
+				//
+				Frame::Hello(_) => self.fail( WsErr::Protocol ),
+				_ if !self.hello() => self.fail( WsErr::Protocol ),
+
+				Frame::Data(_) if self.remote_closed => self.fail( WsErr::Protocol ),
+
+				// The remote ignores the window we gave it.
+				//
+				Frame::Data( data ) if self.received + data.len() as u64 - self.consumed > self.max_unacked as u64 =>
+				{
+					warn!( "the remote of session {} sent more data than we accept", self.session );
+					self.fail( WsErr::Protocol );
+				}
+
+				Frame::Data( data ) =>
+				{
+					self.read_buf.extend( data );
+					self.received += data.len() as u64;
+				}
+
+				Frame::Ack( offset ) =>
+				{
+					if offset > self.sent
+					{
+						self.fail( WsErr::Protocol );
+						continue;
+					}
+
+					self.remote_consumed = self.remote_consumed.max( offset );
+
+					// We might already know from a hello that the remote has the data.
+					//
+					if offset > self.replay_start
+					{
+						self.replay.drain( ..( offset - self.replay_start ) as usize );
+						self.replay_start = offset;
+					}
+
+					self.send_replay();
+				}
+
+				// Nothing more will come, so there is no reason to keep the remote waiting for acknowledgements
+				// of what we didn't read yet. It might wait for them to close.
+				//
+				Frame::Close =>
+				{
+					self.remote_closed = true;
+
+					if self.received > self.acked
+					{
+						Frame::encode_ack( self.received, &mut self.out );
+						self.acked = self.received;
+					}
+				}
+			}
+		}
+
+		self.in_buf = buf;
+		self.in_buf.drain( ..pos.min( self.in_buf.len() ) );
+	}
+
+
+	// Write and flush the frames for the connection. Returns Ready when that's done or we lost the
+	// connection.
+	//
+	fn pump_out( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		loop
+		{
+			let State::Up{ ws, .. } = &mut self.state else { return Poll::Ready(()) };
+
+			if self.out_pos < self.out.len()
+			{
+				match ready!( AsyncWrite::poll_write( Pin::new( ws ), cx, &self.out[ self.out_pos.. ] ) )
+				{
+					Ok (0) => self.link_down( Some( io::ErrorKind::WriteZero.into() ) ),
+					Ok (n) => { self.out_pos += n; self.needs_flush = true; }
+					Err(e) => self.link_down( Some(e) ),
+				}
+
+				continue;
+			}
+
+			self.out.clear();
+			self.out_pos = 0;
+
+			if !self.needs_flush
+			{
+				return Poll::Ready(());
+			}
+
+			match ready!( AsyncWrite::poll_flush( Pin::new( ws ), cx ) )
+			{
+				Ok (()) => self.needs_flush = false,
+				Err(e ) => self.link_down( Some(e) ),
+			}
+		}
+	}
+}
+
+
+
+impl<B> fmt::Debug for ResumableWsStream<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ResumableWsStream" )
+
+			.field( "session"    , &self.session      )
+			.field( "connections", &self.connections  )
+			.field( "unacked"    , &self.replay.len() )
+			.field( "error"      , &self.error        )
+			.finish_non_exhaustive()
+	}
+}
+
+
+
+impl<B> AsyncRead for ResumableWsStream<B> where B: WsBackend
+{
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		let this = self.get_mut();
+
+		loop
+		{
+			if !this.read_buf.is_empty()
+			{
+				let n = buf.len().min( this.read_buf.len() );
+
+				for (dst, src) in buf.iter_mut().zip( this.read_buf.drain( ..n ) )
+				{
+					*dst = src;
+				}
+
+				this.consumed += n as u64;
+				this.ack();
+
+				let _ = this.pump_out( cx );
+
+				return Poll::Ready( Ok(n) );
+			}
+
+			if buf.is_empty() || this.remote_closed
+			{
+				return Poll::Ready( Ok(0) );
+			}
+
+			ready!( this.poll_link( cx ) )?;
+
+			let read = this.pump_in( cx );
+			let _    = this.pump_out( cx );
+
+			if read.is_pending() && this.is_up() && this.read_buf.is_empty() && !this.remote_closed
+			{
+				return Poll::Pending;
+			}
+		}
+	}
+}
+
+
+
+impl<B> AsyncWrite for ResumableWsStream<B> where B: WsBackend
+{
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		let this = self.get_mut();
+
+		loop
+		{
+			if let Some( err ) = this.io_error()
+			{
+				return Poll::Ready( Err( err ) );
+			}
+
+			if this.local_closed || matches!( this.state, State::Ended )
+			{
+				return Poll::Ready( Err( io::ErrorKind::NotConnected.into() ) );
+			}
+
+			if buf.is_empty()
+			{
+				return Poll::Ready( Ok(0) );
+			}
+
+			let room = this.max_unacked.saturating_sub( this.replay.len() );
+
+			if room > 0
+			{
+				let n = room.min( buf.len() );
+
+				this.replay.extend( &buf[ ..n ] );
+				this.send_replay();
+
+				let _ = this.pump_out( cx );
+
+				return Poll::Ready( Ok(n) );
+			}
+
+			// Wait for acknowledgements, reconnecting if we have to.
+			//
+			ready!( this.poll_link( cx ) )?;
+
+			let read = this.pump_in( cx );
+			let _    = this.pump_out( cx );
+
+			if read.is_pending() && this.is_up() && this.replay.len() >= this.max_unacked
+			{
+				return Poll::Pending;
+			}
+		}
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		let this = self.get_mut();
+
+		loop
+		{
+			ready!( this.poll_link( cx ) )?;
+
+			if !this.is_up()
+			{
+				return Poll::Ready( Ok(()) );
+			}
+
+			// Until the remote introduced itself, we don't know what to send.
+			//
+			if !this.hello()
+			{
+				let read = this.pump_in( cx );
+				let _    = this.pump_out( cx );
+
+				if read.is_pending() && this.is_up() && !this.hello()
+				{
+					return Poll::Pending;
+				}
+
+				continue;
+			}
+
+			ready!( this.pump_out( cx ) );
+
+			if this.is_up()
+			{
+				return Poll::Ready( Ok(()) );
+			}
+		}
+	}
+
+
+	/// Tell the remote we won't write anymore and wait until it acknowledged all our data. When the remote
+	/// closed as well, the connection is closed.
+	//
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		if !self.local_closed
+		{
+			self.local_closed = true;
+			self.send_replay();
+		}
+
+		loop
+		{
+			ready!( AsyncWrite::poll_flush( self.as_mut(), cx ) )?;
+
+			let this = &mut *self;
+
+			if this.replay.is_empty()
+			{
+				if this.remote_closed
+				{
+					if let State::Up{ ws, .. } = &mut this.state
+					{
+						let _ = ready!( AsyncWrite::poll_close( Pin::new( ws ), cx ) );
+					}
+
+					this.state = State::Ended;
+				}
+
+				return Poll::Ready( Ok(()) );
+			}
+
+			let read = this.pump_in( cx );
+			let _    = this.pump_out( cx );
+
+			if read.is_pending() && this.is_up() && !this.replay.is_empty()
+			{
+				return Poll::Pending;
+			}
+		}
+	}
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<B> TokAsyncRead for ResumableWsStream<B> where B: WsBackend
+{
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_> ) -> Poll< io::Result<()> >
+	{
+		let n = ready!( AsyncRead::poll_read( self, cx, buf.initialize_unfilled() ) )?;
+
+		buf.advance( n );
+
+		Poll::Ready( Ok(()) )
+	}
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<B> TokAsyncWrite for ResumableWsStream<B> where B: WsBackend
+{
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		AsyncWrite::poll_write( self, cx, buf )
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		AsyncWrite::poll_flush( self, cx )
+	}
+
+
+	fn poll_shutdown( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		AsyncWrite::poll_close( self, cx )
+	}
+}
@@ -0,0 +1,216 @@
+use super::SessionId;
+
+
+// Frames are written to the byte stream of the WsStream, so they don't depend on message boundaries. Each
+// starts with a byte for the kind:
+//
+// - hello: the session id (16 bytes), the number of data bytes we received and consumed in this session and
+//          how many bytes we accept beyond what we consumed (u64 each)
+// - data : the length (u32) followed by the data
+// - ack  : the number of data bytes we consumed in this session (u64)
+// - close: we won't write anymore
+//
+// All numbers are big endian.
+//
+const HELLO: u8 = 0;
+const DATA : u8 = 1;
+const ACK  : u8 = 2;
+const CLOSE: u8 = 3;
+
+pub(super) const HELLO_LEN: usize = 1 + 16 + 3 * 8;
+
+// The most data we put in one frame. Bigger frames from the remote are refused, so it can't make us
+// buffer an arbitrary amount before we get to see the data.
+//
+pub(super) const MAX_DATA: usize = 16 * 1024;
+
+
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub(super) struct Hello
+{
+	pub(super) session : SessionId,
+	pub(super) received: u64,
+	pub(super) consumed: u64,
+	pub(super) window  : u64,
+}
+
+
+#[ derive( Debug, PartialEq, Eq ) ]
+//
+pub(super) enum Frame<'a>
+{
+	Hello( Hello    ),
+	Data ( &'a [u8] ),
+	Ack  ( u64      ),
+	Close,
+}
+
+
+
+impl Hello
+{
+	pub(super) fn encode( &self, out: &mut Vec<u8> )
+	{
+		out.push( HELLO );
+		out.extend_from_slice( &self.session.0 );
+		out.extend_from_slice( &self.received.to_be_bytes() );
+		out.extend_from_slice( &self.consumed.to_be_bytes() );
+		out.extend_from_slice( &self.window  .to_be_bytes() );
+	}
+}
+
+
+
+impl Frame<'_>
+{
+	// Data longer than MAX_DATA is split over several frames.
+	//
+	pub(super) fn encode_data( data: &[u8], out: &mut Vec<u8> )
+	{
+		for chunk in data.chunks( MAX_DATA )
+		{
+			out.push( DATA );
+			out.extend_from_slice( &( chunk.len() as u32 ).to_be_bytes() );
+			out.extend_from_slice( chunk );
+		}
+	}
+
+
+	pub(super) fn encode_ack( offset: u64, out: &mut Vec<u8> )
+	{
+		out.push( ACK );
+		out.extend_from_slice( &offset.to_be_bytes() );
+	}
+
+
+	pub(super) fn encode_close( out: &mut Vec<u8> )
+	{
+		out.push( CLOSE );
+	}
+
+
+	// Returns the frame at the start of buf and its length, or Ok(None) when buf doesn't hold a whole frame
+	// yet. Err means the remote didn't send a valid frame.
+	//
+	pub(super) fn decode( buf: &[u8] ) -> Result< Option<( Frame<'_>, usize )>, () >
+	{
+		let Some( (&kind, rest) ) = buf.split_first() else { return Ok( None ) };
+
+		let frame = match kind
+		{
+			HELLO if rest.len() >= HELLO_LEN - 1 =>
+			{
+				let session  = SessionId( rest[ ..16 ].try_into().map_err( |_| () )? );
+				let received = u64::from_be_bytes( rest[ 16..24 ].try_into().map_err( |_| () )? );
+				let consumed = u64::from_be_bytes( rest[ 24..32 ].try_into().map_err( |_| () )? );
+				let window   = u64::from_be_bytes( rest[ 32..40 ].try_into().map_err( |_| () )? );
+
+				( Frame::Hello( Hello{ session, received, consumed, window } ), HELLO_LEN )
+			}
+
+			DATA if rest.len() >= 4 =>
+			{
+				let len = u32::from_be_bytes( rest[ ..4 ].try_into().map_err( |_| () )? ) as usize;
+
+				if len > MAX_DATA
+				{
+					return Err(());
+				}
+
+				if rest.len() < 4 + len
+				{
+					return Ok( None );
+				}
+
+				( Frame::Data( &rest[ 4..4 + len ] ), 1 + 4 + len )
+			}
+
+			ACK if rest.len() >= 8 => ( Frame::Ack( u64::from_be_bytes( rest[ ..8 ].try_into().map_err( |_| () )? ) ), 9 ),
+
+			CLOSE => ( Frame::Close, 1 ),
+
+			HELLO | DATA | ACK => return Ok( None ),
+
+			_ => return Err(()),
+		};
+
+		Ok( Some( frame ) )
+	}
+}
+
+
+
+#[ cfg( test ) ]
+//
+mod tests
+{
+	use super::*;
+
+
+	#[ test ]
+	//
+	fn round_trip()
+	{
+		let hello   = Hello{ session: SessionId( [ 7; 16 ] ), received: 300, consumed: 200, window: 1000 };
+		let mut buf = Vec::new();
+
+		hello.encode( &mut buf );
+		Frame::encode_data( &[ 1, 2, 3 ], &mut buf );
+		Frame::encode_ack( 5, &mut buf );
+		Frame::encode_close( &mut buf );
+
+		let mut frames = Vec::new();
+		let mut pos    = 0;
+
+		while let Some( (frame, len) ) = Frame::decode( &buf[ pos.. ] ).expect( "valid" )
+		{
+			frames.push( frame );
+			pos += len;
+		}
+
+		assert_eq!( buf.len(), pos );
+
+		assert_eq!
+		(
+			vec![ Frame::Hello( hello ), Frame::Data( &[ 1, 2, 3 ] ), Frame::Ack( 5 ), Frame::Close ],
+			frames
+		);
+	}
+
+
+	#[ test ]
+	//
+	fn partial()
+	{
+		let mut buf = Vec::new();
+
+		Frame::encode_data( &[ 1, 2, 3 ], &mut buf );
+
+		for len in 0..buf.len()
+		{
+			assert_eq!( Ok( None ), Frame::decode( &buf[ ..len ] ) );
+		}
+	}
+
+
+	#[ test ]
+	//
+	fn split_data()
+	{
+		let mut buf = Vec::new();
+
+		Frame::encode_data( &vec![ 0; MAX_DATA + 1 ], &mut buf );
+
+		assert_eq!( MAX_DATA + 1 + 2 * 5, buf.len() );
+	}
+
+
+	#[ test ]
+	//
+	fn invalid()
+	{
+		assert_eq!( Err(()), Frame::decode( &[ 9 ] ) );
+		assert_eq!( Err(()), Frame::decode( &[ DATA, 0xff, 0xff, 0xff, 0xff ] ) );
+	}
+}
@@ -0,0 +1,308 @@
+use crate::{ import::*, WsStream, WsStreamConfig, WsErr, Timer, HandshakeInfo };
+
+
+// Decides whether to accept an upgrade request. See `WsListener::callback`.
+//
+type Callback = Arc< dyn Fn( &Request, Response ) -> Result<Response, ErrorResponse> + Send + Sync >;
+
+// Gets the peer and local address of a connection. See `WsListener::addrs`.
+//
+type Addrs<T> = Arc< dyn Fn( &T ) -> ( Option<SocketAddr>, Option<SocketAddr> ) + Send + Sync >;
+
+// A websocket handshake in progress.
+//
+type Handshake<T> = Pin< Box< dyn Future< Output = Result< (ATungSocket<T>, HandshakeInfo), WsErr > > + Send > >;
+
+
+/// The default for [`WsListener::max_handshakes`].
+//
+pub const DEFAULT_MAX_HANDSHAKES: usize = 64;
+
+
+
+/// Turns a stream of incoming connections into a stream of [`WsStream`]s by doing the websocket handshake on
+/// each of them. This saves you writing the accept loop yourself:
+///
+/// ```no_run
+/// use
+/// {
+///    ws_stream_tungstenite :: { WsListener, Timer } ,
+///    futures               :: { StreamExt         } ,
+///    async_std             :: { net::TcpListener  } ,
+///    std                   :: { time::Duration    } ,
+/// };
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let tcp = TcpListener::bind( "127.0.0.1:3012" ).await?;
+///
+/// let mut listener = WsListener::new( tcp.incoming() )
+///
+///    .handshake_timeout( Duration::from_secs(5), Timer::new( futures_timer::Delay::new ) )
+///    .callback( |request, response|
+///    {
+///       match request.uri().path()
+///       {
+///          "/chat" => Ok( response ),
+///          _       => Err( tungstenite::http::Response::builder().status( 404 ).body( None ).unwrap() ),
+///       }
+///    })
+/// ;
+///
+/// while let Some( result ) = listener.next().await
+/// {
+///    let Ok(( ws, info )) = result else { continue };
+///
+///    // spawn a task to handle ws...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Handshakes run concurrently, up to [`WsListener::max_handshakes`]. When that many are in progress, no new
+/// connections are taken from the incoming stream until one of them finishes.
+///
+/// ## Errors
+///
+/// Errors only concern a single connection, so you should keep polling after an error. The stream ends when the
+/// incoming stream has ended and all handshakes have finished.
+///
+/// - [`WsErr::Io`]: The incoming stream returned an error.
+/// - [`WsErr::HandshakeTimeout`]: The handshake did not finish within [`WsListener::handshake_timeout`].
+/// - [`WsErr::Tungstenite`]: The handshake failed, eg. the request was not a valid upgrade request or the
+///   callback rejected it, in which case the error contains the response that was sent.
+/// - [`WsErr::InvalidConfig`]: The [`WsListener::config`] is not valid for the [`WebSocketConfig`] of the connection.
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "server" )) ) ]
+//
+pub struct WsListener<T, St>
+
+	where St: Stream< Item = io::Result<T> > + Unpin           ,
+	      T : AsyncRead + AsyncWrite + Send + Unpin + 'static ,
+{
+	// None once the incoming stream has ended.
+	//
+	incoming      : Option<St>                       ,
+	handshakes    : FuturesUnordered< Handshake<T> > ,
+	callback      : Callback                         ,
+	addrs         : Option< Addrs<T> >               ,
+	timeout       : Option<( Duration, Timer )>      ,
+	max_handshakes: usize                            ,
+	ws_config     : Option<WebSocketConfig>          ,
+	config        : WsStreamConfig                   ,
+}
+
+
+
+impl<T, St> WsListener<T, St>
+
+	where St: Stream< Item = io::Result<T> > + Unpin           ,
+	      T : AsyncRead + AsyncWrite + Send + Unpin + 'static ,
+{
+	/// Accept connections from `incoming`, eg. `TcpListener::incoming` from _async-std_. By default all upgrade
+	/// requests are accepted, without a handshake timeout.
+	//
+	pub fn new( incoming: St ) -> Self
+	{
+		Self
+		{
+			incoming      : Some( incoming )                         ,
+			handshakes    : FuturesUnordered::new()                  ,
+			callback      : Arc::new( |_, response| Ok( response ) ) ,
+			addrs         : None                                     ,
+			timeout       : None                                     ,
+			max_handshakes: DEFAULT_MAX_HANDSHAKES                   ,
+			ws_config     : None                                     ,
+			config        : WsStreamConfig::default()                ,
+		}
+	}
+
+
+	/// Decide for every upgrade request whether to accept it, based on the path and headers. Return the
+	/// response to accept the connection, optionally adding headers like `Sec-WebSocket-Protocol` to select a
+	/// subprotocol. Return an error response to reject it.
+	//
+	pub fn callback<F>( mut self, callback: F ) -> Self
+
+		where F: Fn( &Request, Response ) -> Result<Response, ErrorResponse> + Send + Sync + 'static
+	{
+		self.callback = Arc::new( callback );
+		self
+	}
+
+
+	/// Get the peer and local address of each connection for the [`HandshakeInfo`], eg.
+	/// `|tcp: &TcpStream| ( tcp.peer_addr().ok(), tcp.local_addr().ok() )`. We can't get them from the
+	/// transport ourselves, since its type is up to you.
+	//
+	pub fn addrs<F>( mut self, addrs: F ) -> Self
+
+		where F: Fn( &T ) -> ( Option<SocketAddr>, Option<SocketAddr> ) + Send + Sync + 'static
+	{
+		self.addrs = Some( Arc::new( addrs ) );
+		self
+	}
+
+
+	/// Give up on a handshake when it hasn't finished after `timeout`. This protects against clients that
+	/// open a connection without ever sending an upgrade request.
+	//
+	pub fn handshake_timeout( mut self, timeout: Duration, timer: Timer ) -> Self
+	{
+		self.timeout = Some(( timeout, timer ));
+		self
+	}
+
+
+	/// The maximum number of handshakes in progress at the same time. Defaults to [`DEFAULT_MAX_HANDSHAKES`].
+	/// A value of zero is treated as one.
+	//
+	pub fn max_handshakes( mut self, max: usize ) -> Self
+	{
+		self.max_handshakes = max.max( 1 );
+		self
+	}
+
+
+	/// The tungstenite config for the accepted connections.
+	//
+	pub fn websocket_config( mut self, config: WebSocketConfig ) -> Self
+	{
+		self.ws_config = Some( config );
+		self
+	}
+
+
+	/// The options for the [`WsStream`]s we create. Each connection gets a clone, with the [`HandshakeInfo`]
+	/// of the connection set.
+	//
+	pub fn config( mut self, config: WsStreamConfig ) -> Self
+	{
+		self.config = config;
+		self
+	}
+
+
+	// Start the handshake on a new connection.
+	//
+	fn handshake( &self, transport: T ) -> Handshake<T>
+	{
+		let (tx, rx)                = oneshot::channel();
+		let callback                = self.callback.clone();
+		let delay                   = self.timeout.as_ref().map( |(timeout, timer)| timer.delay( *timeout ) );
+		let (peer_addr, local_addr) = self.addrs.as_ref().map( |addrs| addrs( &transport ) ).unwrap_or_default();
+
+		// Record the request, so we can return it along with the WsStream.
+		//
+		let record = move |request: &Request, response: Response|
+		{
+			let response    = callback( request, response )?;
+			let subprotocol = response.headers().get( SEC_WEBSOCKET_PROTOCOL )
+
+				.and_then( |value| value.to_str().ok() )
+				.map( String::from )
+			;
+
+			let _ = tx.send( HandshakeInfo
+			{
+				uri    : request.uri().clone()     ,
+				headers: request.headers().clone() ,
+				subprotocol                        ,
+				peer_addr                          ,
+				local_addr                         ,
+			});
+
+			Ok( response )
+		};
+
+		let accept = accept_hdr_async_with_config( transport, record, self.ws_config );
+
+		Box::pin( async move
+		{
+			let socket = match delay
+			{
+				None => accept.await?,
+
+				Some( delay ) => match future::select( Box::pin( accept ), delay ).await
+				{
+					Either::Left (( result, _ )) => result?,
+					Either::Right(( ()    , _ )) => return Err( WsErr::HandshakeTimeout ),
+				}
+			};
+
+			// The callback always runs before a handshake succeeds, so the info is there.
+			//
+			let info = rx.await.unwrap_or_default();
+
+			Ok(( socket, info ))
+		})
+	}
+}
+
+
+
+impl<T, St> Stream for WsListener<T, St>
+
+	where St: Stream< Item = io::Result<T> > + Unpin           ,
+	      T : AsyncRead + AsyncWrite + Send + Unpin + 'static ,
+{
+	type Item = Result< (WsStream< ATungSocket<T> >, HandshakeInfo), WsErr >;
+
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		// Take new connections as long as we are below the limit.
+		//
+		while self.handshakes.len() < self.max_handshakes
+		{
+			let Some( incoming ) = self.incoming.as_mut() else { break };
+
+			match Pin::new( incoming ).poll_next( cx )
+			{
+				Poll::Ready( Some( Ok( transport ) ) ) =>
+				{
+					let handshake = self.handshake( transport );
+					self.handshakes.push( handshake );
+				}
+
+				Poll::Ready( Some( Err( err ) ) ) => return Poll::Ready( Some( Err( err.into() ) ) ),
+				Poll::Ready( None               ) => self.incoming = None,
+				Poll::Pending                     => break,
+			}
+		}
+
+		match ready!( Pin::new( &mut self.handshakes ).poll_next( cx ) )
+		{
+			Some( Ok(( socket, info )) ) =>
+			{
+				let result = self.config.clone().handshake_info( info.clone() ).build( socket ).map( |ws| (ws, info) );
+
+				Poll::Ready( Some( result ) )
+			}
+
+			Some( Err( err ) ) => Poll::Ready( Some( Err( err ) ) ),
+
+			// No handshakes in progress. If the incoming stream hasn't ended, it will wake us up.
+			//
+			None if self.incoming.is_some() => Poll::Pending,
+			None                            => Poll::Ready( None ),
+		}
+	}
+}
+
+
+
+impl<T, St> fmt::Debug for WsListener<T, St>
+
+	where St: Stream< Item = io::Result<T> > + Unpin           ,
+	      T : AsyncRead + AsyncWrite + Send + Unpin + 'static ,
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "WsListener" )
+
+			.field( "handshakes"    , &self.handshakes.len() )
+			.field( "max_handshakes", &self.max_handshakes   )
+			.field( "config"        , &self.config           )
+			.finish_non_exhaustive()
+	}
+}
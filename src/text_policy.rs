@@ -0,0 +1,27 @@
+/// What to do when the remote sends a websocket text message. Since [`WsStream`](crate::WsStream) is
+/// a byte stream, text messages are rejected by default. Set the policy with
+/// [`WsStream::set_text_policy`](crate::WsStream::set_text_policy).
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Default ) ]
+//
+pub enum TextPolicy
+{
+	/// Report [`WsErr::ReceivedText`](crate::WsErr::ReceivedText) as an event and close the connection
+	/// with [`CloseCode::Unsupported`](tungstenite::protocol::frame::coding::CloseCode::Unsupported).
+	//
+	#[ default ]
+	//
+	Reject,
+
+	/// Pass the UTF-8 bytes of the message through `AsyncRead` exactly like the data of a binary message.
+	//
+	PassThrough,
+
+	/// Silently drop the message and keep the connection open.
+	//
+	Ignore,
+
+	/// Deliver the message to observers as [`WsEvent::Text`](crate::WsEvent::Text) and keep the connection open.
+	//
+	Event,
+}
@@ -0,0 +1,57 @@
+use crate::{ import::* };
+
+
+// A future that resolves once a duration has passed.
+//
+pub(crate) type Delay = Pin< Box< dyn Future<Output=()> + Send > >;
+
+
+/// Creates delays for the features that need to keep track of time, like keepalive. This allows
+/// _ws_stream_tungstenite_ to stay runtime agnostic. You provide a closure that returns a future
+/// which resolves after the given duration:
+///
+/// ```
+/// use ws_stream_tungstenite::Timer;
+///
+/// let timer = Timer::new( futures_timer::Delay::new );
+/// ```
+///
+/// With tokio this would be `Timer::new( tokio::time::sleep )`.
+//
+#[ derive( Clone ) ]
+//
+pub struct Timer
+{
+	factory: Arc< dyn Fn( Duration ) -> Delay + Send + Sync >,
+}
+
+
+impl Timer
+{
+	/// Create a timer from a closure that creates delay futures.
+	//
+	pub fn new<F, Fut>( factory: F ) -> Self
+
+		where F  : Fn( Duration ) -> Fut + Send + Sync + 'static ,
+		      Fut: Future<Output=()> + Send + 'static            ,
+	{
+		Self { factory: Arc::new( move |dur| -> Delay { Box::pin( factory(dur) ) } ) }
+	}
+
+
+	// Create a delay which resolves after `dur`.
+	//
+	pub(crate) fn delay( &self, dur: Duration ) -> Delay
+	{
+		(self.factory)( dur )
+	}
+}
+
+
+impl fmt::Debug for Timer
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Timer" )
+	}
+}
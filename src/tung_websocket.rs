@@ -1,13 +1,27 @@
-mod notifier;
-mod closer  ;
+mod notifier  ;
+mod closer    ;
+mod pinger    ;
+mod keepalive ;
+mod meter     ;
 
 use
 {
-	crate    :: { import::*, WsEvent, WsErr } ,
-	notifier :: { Notifier                  } ,
-	closer   :: { Closer                    } ,
+	crate     :: { import::*, WsEvent, WsErr, WsStats, WsStreamConfig, TextPolicy, WsBackend } ,
+	crate     :: { timer::Delay                                                              } ,
+	notifier  :: { Notifier                                                                  } ,
+	closer    :: { Closer                                                                    } ,
+	pinger    :: { Pinger, MAX_PINGS                                                         } ,
+	keepalive :: { KeepAlive, Action                                                         } ,
+	meter     :: { Meter, Direction                                                          } ,
 };
 
+#[ cfg( feature = "handshake" ) ] use crate::HandshakeInfo;
+
+
+// A control frame can carry 125 bytes of payload, 2 of which are taken by the close code.
+//
+pub(crate) const MAX_CLOSE_REASON: usize = 123;
+
 
 bitflags!
 {
@@ -22,49 +36,284 @@ bitflags!
 	/// SINK_CLOSED is used to keep track of any state where we should no longer send anything into the sink
 	/// (eg. it returned an error). In that case, we might still poll the stream to drive a close handshake
 	/// to completion.
+	///
+	/// CLOSE_RECEIVED is set when the remote sent us a close frame. Together with SINK_CLOSED, it tells us
+	/// the close handshake is in progress, so the close timeout should run.
+	///
+	/// PAUSED makes the stream return pending without polling tungstenite. It is only set for the duration
+	/// of a poll with a noop waker, so we can look at what `IoStream` has buffered without reading more data.
+	//
+	#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
 	//
 	struct State: u8
 	{
-		const NOTIFIER_PEND = 0x01;
-		const CLOSER_PEND   = 0x02;
-		const PHAROS_CLOSED = 0x04;
-		const SINK_CLOSED   = 0x08;
-		const STREAM_CLOSED = 0x10;
+		const NOTIFIER_PEND  = 0x01;
+		const CLOSER_PEND    = 0x02;
+		const PHAROS_CLOSED  = 0x04;
+		const SINK_CLOSED    = 0x08;
+		const STREAM_CLOSED  = 0x10;
+		const PAUSED         = 0x20;
+		const PINGER_PEND    = 0x40;
+		const CLOSE_RECEIVED = 0x80;
 	}
 }
 
 
+impl State
+{
+	// Flags that track pending work change on almost every poll, so they are only logged at trace level.
+	//
+	const PENDING: State = State::NOTIFIER_PEND.union( State::PINGER_PEND );
+}
+
+
+
+// A message that arrives as separate data frames, from a backend that doesn't reassemble messages.
+//
+enum Fragments
+{
+	// Binary data, handed on as it arrives.
+	//
+	Stream,
+
+	// A message we need as a whole: text, or binary when we deliver whole messages.
+	//
+	Collect{ text: bool, data: Vec<u8> },
+
+	// The rest of a message that was too big.
+	//
+	Discard,
+}
+
+
 
 
 /// A wrapper around a WebSocket provided by tungstenite. This provides Stream/Sink Vec<u8> to
 /// simplify implementing AsyncRead/AsyncWrite on top of async-tungstenite.
 //
-pub(crate) struct TungWebSocket<S>  where S: AsyncRead + AsyncWrite + Send + Unpin
+pub(crate) struct TungWebSocket<B>  where B: WsBackend
 {
-	inner: ATungSocket<S> ,
+	// Only ever None after take_parts, which is only called right before dropping us.
+	//
+	inner: Option< B > ,
+
+	state     : State             ,
+	notifier  : Notifier          ,
+	closer    : Closer            ,
+	pinger    : Pinger            ,
+	keepalive : Option<KeepAlive> ,
+	config    : WsStreamConfig    ,
+
+	// Started when the close handshake starts if the user configured a close timeout.
+	//
+	close_delay: Option<Delay>,
+
+	// The task reading from us. When the close handshake starts from the sink, possibly in another task, it
+	// has to be woken up to poll the close delay.
+	//
+	read_waker: Option<Waker>,
 
-	state    : State    ,
-	notifier : Notifier ,
-	closer   : Closer   ,
+	// Pings sent by the user through `ping`, waiting for the round trip time.
+	//
+	rtt_waiters: Vec<( Vec<u8>, oneshot::Sender<Duration> )>,
+
+	// The close frame the remote sent, if any, to tell why writing fails.
+	//
+	remote_close: Option< CloseFrame<'static> >,
+
+	// The message we are receiving in fragments, and whether fragmented binary messages are put back
+	// together before handing them on.
+	//
+	fragments     : Option<Fragments>,
+	whole_messages: bool             ,
+
+	stats: WsStats,
+	meter: Meter  ,
+
+	// All logging for this connection happens in this span.
+	//
+	span: Span,
 }
 
 
-impl<S> TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> TungWebSocket<B> where B: WsBackend
 {
-	/// Create a new Wrapper for a WebSocket provided by Tungstenite
+	/// Create a new Wrapper for a WebSocket provided by Tungstenite. The config should already be validated.
 	//
-	pub(crate) fn new( inner: ATungSocket<S> ) -> Self
+	pub(crate) fn new( inner: B, config: WsStreamConfig ) -> Self
 	{
+		let keepalive = config.keepalive.map( |(interval, timeout)|
+		{
+			let timer = config.timer.clone().expect( "WsStreamConfig::validate checks keepalive has a timer" );
+
+			KeepAlive::new( interval, timeout, timer )
+		});
+
+		let meter = Meter::new( &config );
+		let span  = Self::span( &config );
+
 		Self
 		{
-			inner                        ,
-			state    : State   ::empty() ,
-			notifier : Notifier::new()   ,
-			closer   : Closer  ::new()   ,
+			inner     : Some( inner )                             ,
+			state     : State   ::empty()                         ,
+			notifier  : Notifier::new( config.observer_capacity ) ,
+			closer    : Closer  ::new()                           ,
+			pinger    : Pinger  ::new()                           ,
+			keepalive                                             ,
+			config                                                ,
+			rtt_waiters : Vec::new()                              ,
+			remote_close: None                                    ,
+			fragments   : None                                    ,
+			whole_messages: false                                 ,
+			close_delay : None                                    ,
+			read_waker  : None                                    ,
+			stats       : WsStats::new()                          ,
+			meter                                                 ,
+			span                                                  ,
 		}
 	}
 
 
+	// The span all logging of this connection happens in.
+	//
+	fn span( config: &WsStreamConfig ) -> Span
+	{
+		let span = info_span!
+		(
+			"ws_stream"                                   ,
+			id          = config.connection_id.as_deref() ,
+			peer        = config.peer.as_deref()          ,
+			uri         = field::Empty                    ,
+			subprotocol = field::Empty                    ,
+		);
+
+		#[ cfg( feature = "handshake" ) ]
+		//
+		if let Some( info ) = &config.handshake
+		{
+			span.record( "uri", field::display( &info.uri ) );
+
+			if let Some( subprotocol ) = &info.subprotocol
+			{
+				span.record( "subprotocol", subprotocol.as_str() );
+			}
+
+			if let ( None, Some( addr ) ) = ( &config.peer, info.peer_addr )
+			{
+				span.record( "peer", field::display( addr ) );
+			}
+		}
+
+		span
+	}
+
+
+	/// What we know about the http upgrade of this connection, if it was given in the config.
+	//
+	#[ cfg( feature = "handshake" ) ]
+	//
+	pub(crate) fn handshake_info( &self ) -> Option<&HandshakeInfo>
+	{
+		self.config.handshake.as_ref()
+	}
+
+
+	/// Choose what happens with incoming text messages.
+	//
+	pub(crate) fn set_text_policy( &mut self, policy: TextPolicy )
+	{
+		self.config.text_policy = policy;
+	}
+
+
+	/// Put binary messages that the backend hands on in fragments back together before returning them.
+	//
+	pub(crate) fn whole_messages( &mut self )
+	{
+		self.whole_messages = true;
+	}
+
+
+	// Access the tungstenite socket.
+	//
+	fn socket( &mut self ) -> &mut B
+	{
+		self.inner.as_mut().expect( "TungWebSocket used after take_parts" )
+	}
+
+
+	/// The traffic statistics of the connection so far.
+	//
+	pub(crate) fn stats( &self ) -> &WsStats
+	{
+		&self.stats
+	}
+
+
+	// Change a flag in the state, logging the transition if there is one.
+	//
+	fn set_state( &mut self, flag: State, on: bool )
+	{
+		if self.state.contains( flag ) == on
+		{
+			return;
+		}
+
+		self.state.set( flag, on );
+
+		if on && flag.intersects( State::SINK_CLOSED | State::CLOSE_RECEIVED )
+		{
+			self.start_close_timeout();
+		}
+
+		let change = if on { "set" } else { "cleared" };
+
+		if State::PENDING.contains( flag )
+		{
+			trace!( parent: &self.span, state = ?self.state, "{:?} {}", flag, change );
+		}
+
+		else
+		{
+			debug!( parent: &self.span, state = ?self.state, "{:?} {}", flag, change );
+		}
+	}
+
+
+	// No more messages will come in. Remember when that happened.
+	//
+	fn close_stream( &mut self )
+	{
+		self.set_state( State::STREAM_CLOSED, true );
+		self.stats.closed_at.get_or_insert_with( SystemTime::now );
+		self.meter.closed();
+	}
+
+
+	/// While paused, poll_next returns pending without polling the underlying socket.
+	//
+	pub(crate) fn pause( &mut self, paused: bool )
+	{
+		self.set_state( State::PAUSED, paused );
+	}
+
+
+	/// Take out the tungstenite socket, a close frame that was queued but not yet handed to tungstenite
+	/// and any events that haven't been delivered to observers yet.
+	///
+	/// This object can no longer be used afterwards. When it's dropped, the meter stops counting the connection
+	/// as open, since we won't see it close.
+	//
+	pub(crate) fn take_parts( &mut self ) -> ( B, Option< CloseFrame<'static> >, Vec<WsEvent> )
+	{
+		let socket = self.inner.take().expect( "TungWebSocket::take_parts called twice" );
+		let frame  = self.closer.take_frame();
+		let events = self.notifier.take_events();
+
+		(socket, frame, events)
+	}
+
+
 	// Check whether there is messages queued up for notification.
 	// Returns Pending until all of them are processed.
 	//
@@ -78,10 +327,10 @@ impl<S> TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
 		match ready!( self.notifier.run( cx ) )
 		{
 			Ok (_) => {}
-			Err(_) => self.state.insert( State::PHAROS_CLOSED ),
+			Err(_) => self.set_state( State::PHAROS_CLOSED, true ),
 		}
 
-		self.state.remove( State::NOTIFIER_PEND );
+		self.set_state( State::NOTIFIER_PEND, false );
 
 		().into()
 	}
@@ -99,9 +348,114 @@ impl<S> TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
 			"If this happens, it's a bug in ws_stream_tungstenite, please report."
 		);
 
+		if let WsEvent::Error(_) = evt
+		{
+			self.stats.errors += 1;
+		}
+
 		self.notifier.queue( evt );
 
-		self.state.insert( State::NOTIFIER_PEND );
+		self.set_state( State::NOTIFIER_PEND, true );
+	}
+
+
+	/// Queue a close frame with a code and reason chosen by the user. It will be sent out by the next call
+	/// to `poll_next`, `poll_ready` or `poll_close`, after which no more messages are accepted for sending.
+	///
+	/// The code must be allowed to be sent over the wire and the reason must fit in a control frame,
+	/// which leaves 123 bytes.
+	//
+	pub(crate) fn close_with( &mut self, frame: CloseFrame<'static> ) -> Result<(), WsErr>
+	{
+		if !frame.code.is_allowed()
+		{
+			return Err( WsErr::InvalidCloseCode );
+		}
+
+		if frame.reason.len() > MAX_CLOSE_REASON
+		{
+			return Err( WsErr::CloseReasonTooLong );
+		}
+
+		if self.state.contains( State::SINK_CLOSED )
+		{
+			return Err( WsErr::Closed );
+		}
+
+		self.set_state( State::SINK_CLOSED, true );
+		self.set_state( State::CLOSER_PEND, true );
+
+		self.closer.queue( frame ).map_err( |_| WsErr::Closed )
+	}
+
+
+	/// Queue a ping to measure the round trip time. It will be sent out by the next call to `poll_next` or
+	/// `poll_ready`. The receiver resolves when the matching pong comes in, which requires polling the stream.
+	/// It is dropped when the connection closes before that.
+	//
+	pub(crate) fn ping( &mut self ) -> Result< oneshot::Receiver<Duration>, WsErr >
+	{
+		if self.state.contains( State::SINK_CLOSED )
+		{
+			return Err( WsErr::Closed );
+		}
+
+		// Forget the pings of callers that stopped waiting, otherwise a remote that doesn't answer would
+		// make us refuse pings forever.
+		//
+		let pinger = &mut self.pinger;
+
+		self.rtt_waiters.retain( |(payload, tx)|
+		{
+			if tx.is_canceled() { pinger.forget( payload ); }
+
+			!tx.is_canceled()
+		});
+
+		// Every waiter has its ping in the pinger, so this bounds both.
+		//
+		if self.rtt_waiters.len() >= MAX_PINGS
+		{
+			return Err( WsErr::TooManyPings );
+		}
+
+		let (tx, rx) = oneshot::channel();
+		let payload  = self.pinger.next_payload();
+
+		self.pinger.queue( payload.clone() );
+		self.rtt_waiters.push(( payload, tx ));
+
+		self.set_state( State::PINGER_PEND, true );
+
+		Ok( rx )
+	}
+
+
+	// Match an incoming pong against the pings we have sent. Pings that are answered notify keepalive and
+	// whoever is waiting on the round trip time. Returns the round trip time if the pong matched one of our pings.
+	//
+	fn handle_pong( &mut self, payload: &[u8] ) -> Option<Duration>
+	{
+		let mut rtt = None;
+
+		for (ping, elapsed) in self.pinger.pong( payload )
+		{
+			if let Some( keepalive ) = &mut self.keepalive
+			{
+				keepalive.pong( &ping );
+			}
+
+			if let Some( idx ) = self.rtt_waiters.iter().position( |(p, _)| *p == ping )
+			{
+				// The receiver might have been dropped, we don't care.
+				//
+				let _ = self.rtt_waiters.swap_remove( idx ).1.send( elapsed );
+			}
+
+			rtt = Some( elapsed );
+		}
+
+		rtt
 	}
 
 
@@ -110,55 +464,245 @@ impl<S> TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
 	// Will return pending until the entire sending operation is finished. We still need to poll
 	// the stream to drive the handshake to completion.
 	//
-	fn send_closeframe( &mut self, code: CloseCode, reason: Cow<'static, str>, cx: &mut Context<'_> ) -> Poll<()>
+	fn send_closeframe( &mut self, code: CloseCode, reason: Cow<'static, str>, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		// If the sink is already closed, don't try to send any more close frames.
+		//
+		if !self.state.contains( State::SINK_CLOSED )
+		{
+			// As soon as we are closing, accept no more messages for writing.
+			//
+			self.set_state( State::SINK_CLOSED, true );
+			self.set_state( State::CLOSER_PEND, true );
+
+			self.closer.queue( CloseFrame{ code, reason } )
+
+				.expect( "ws_stream_tungstenite should not queue 2 close frames" )
+			;
+		}
+
+		self.check_closer( cx )
+	}
+
+
+	// Check whether there is a close frame in progress of being sent.
+	// Returns Pending the underlying sink is flushed.
+	//
+	fn check_closer( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		if !self.state.contains( State::CLOSER_PEND )
+		{
+			return ().into();
+		}
+
+
+		let socket = self.inner.as_mut().expect( "TungWebSocket used after take_parts" );
+
+		if ready!( Pin::new( &mut self.closer ).run( socket, &mut self.notifier, &mut self.stats, &self.meter, cx ) ).is_err()
+		{
+			self.set_state( State::SINK_CLOSED, true );
+		}
+
+		self.set_state( State::CLOSER_PEND, false );
+
+
+		// Since closer might have queued events, before returning, make sure they are flushed.
+		//
+		Pin::new( self ).check_notify( cx )
+	}
+
+
+	// Check whether there are pings in progress of being sent.
+	// Returns Pending until the underlying sink is flushed.
+	//
+	fn check_pinger( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		if !self.state.contains( State::PINGER_PEND )
+		{
+			return ().into();
+		}
+
+		let socket = self.inner.as_mut().expect( "TungWebSocket used after take_parts" );
+
+		if let Err(e) = ready!( self.pinger.run( socket, &mut self.stats, cx ) )
+		{
+			self.queue_event( WsEvent::Error( Arc::new( e.into() )) );
+		}
+
+		self.set_state( State::PINGER_PEND, false );
+
+		// Make sure errors get delivered before returning.
+		//
+		Pin::new( self ).check_notify( cx )
+	}
+
+
+	// Check whether the keepalive wants to send a ping or has timed out waiting for a pong.
+	// The timer doesn't block reading, so this only returns pending if we are closing the connection
+	// and the close frame can't be sent out yet.
+	//
+	fn check_keepalive( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		// We can't send pings anymore. If the remote does not finish the close handshake, that's up to
+		// the close timeout.
+		//
+		if self.state.contains( State::SINK_CLOSED )
+		{
+			return ().into();
+		}
+
+		// After sending a ping, loop so the delay for the pong timeout gets polled and will wake us up.
+		//
+		loop
+		{
+			let Some( keepalive ) = &mut self.keepalive else { return ().into() };
+
+			match keepalive.poll_action( cx )
+			{
+				Poll::Pending => return ().into(),
+
+				Poll::Ready( Action::Ping ) =>
+				{
+					let payload = self.pinger.next_payload();
+
+					keepalive  .sent ( payload.clone() );
+					self.pinger.queue( payload         );
+
+					self.set_state( State::PINGER_PEND, true );
+				}
+
+				Poll::Ready( Action::Timeout ) =>
+				{
+					self.queue_event( WsEvent::PongTimeout );
+
+					return self.send_closeframe( CloseCode::Away, "Pong timeout".into(), cx );
+				}
+			}
+		}
+	}
+
+
+	/// Send a fragment of a binary message. The first fragment is sent as a binary frame, the others
+	/// as continuation frames. The last one must be marked final. Call `poll_ready` before each fragment,
+	/// so control frames can go out in between.
+	///
+	/// Errors are the same as for `start_send`.
+	//
+	pub(crate) fn start_send_fragment( &mut self, data: Vec<u8>, first: bool, last: bool ) -> io::Result<()>
+	{
+		let opcode = match first
+		{
+			true  => OpCode::Data( Data::Binary   ),
+			false => OpCode::Data( Data::Continue ),
+		};
+
+		let len = data.len() as u64;
+
+		self.send_message( TungMessage::Frame( Frame::message( data, opcode, last ) ) )?;
+
+		self.stats.messages_sent += u64::from( last );
+		self.stats.bytes_sent    += len;
+
+		self.meter.bytes_sent( len );
+
+		Ok(())
+	}
+
+
+	// The error for writing to a closed connection. Tells the close code if the remote closed it.
+	//
+	fn closed_err( &self ) -> WsErr
+	{
+		match &self.remote_close
+		{
+			Some( frame ) => WsErr::RemoteClosed{ code: frame.code, reason: frame.reason.to_string() },
+			None          => WsErr::Closed,
+		}
+	}
+
+
+	// Convert an error from the tungstenite sink.
+	//
+	// TODO: It's not quite clear whether the stream can remain functional when we get a sink error,
+	// but since this is a duplex connection, and poll_next also tries to send out close frames
+	// through the stream, just consider sink errors fatal.
+	//
+	fn sink_error( &mut self, err: TungErr ) -> io::Error
+	{
+		self.close_stream();
+
+		let max_write = self.socket().config().max_write_buffer_size;
+
+		send_error( err, self.closed_err(), max_write ).into()
+	}
+
+
+	// Hand a message to tungstenite.
+	//
+	fn send_message( &mut self, msg: TungMessage ) -> io::Result<()>
+	{
+		if self.state.contains( State::SINK_CLOSED )
+		{
+			return Err( self.closed_err().into() )
+		}
+
+
+		Pin::new( self.socket() ).start_send( msg ).map_err( |e| self.sink_error( e ) )
+	}
+
+
+	// The close handshake started, either because we sent a close frame or because we received one. Start
+	// the close timeout if the user configured one, and wake up the reader so it polls the delay.
+	//
+	fn start_close_timeout( &mut self )
 	{
-		// If the sink is already closed, don't try to send any more close frames.
-		//
-		if !self.state.contains( State::SINK_CLOSED )
+		let Some( timeout ) = self.config.close_timeout else { return };
+
+		if self.close_delay.is_some()
 		{
-			// As soon as we are closing, accept no more messages for writing.
-			//
-			self.state.insert( State::SINK_CLOSED );
-			self.state.insert( State::CLOSER_PEND );
+			return;
+		}
 
-			self.closer.queue( CloseFrame{ code, reason } )
+		let timer = self.config.timer.as_ref().expect( "WsStreamConfig::validate checks close_timeout has a timer" );
 
-				.expect( "ws_stream_tungstenite should not queue 2 close frames" )
-			;
-		}
+		self.close_delay = Some( timer.delay( timeout ) );
 
-		self.check_closer( cx )
+		if let Some( waker ) = self.read_waker.take()
+		{
+			waker.wake();
+		}
 	}
 
 
-	// Check whether there is a close frame in progress of being sent.
-	// Returns Pending the underlying sink is flushed.
+	// Once the close handshake is in progress, give the remote until the close timeout to finish it.
+	// Returns true when the deadline has passed, in which case the stream is considered closed.
 	//
-	fn check_closer( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	fn check_close_timeout( &mut self, cx: &mut Context<'_> ) -> bool
 	{
-		if !self.state.contains( State::CLOSER_PEND )
+		if self.state.contains( State::STREAM_CLOSED )
 		{
-			return ().into();
+			return false;
 		}
 
+		let Some( delay ) = &mut self.close_delay else { return false };
 
-		if ready!( Pin::new( &mut self.closer ).run( &mut self.inner, &mut self.notifier, cx) ).is_err()
+		if delay.as_mut().poll( cx ).is_pending()
 		{
-			self.state.insert( State::SINK_CLOSED );
+			return false;
 		}
 
-		self.state.remove( State::CLOSER_PEND );
+		self.set_state( State::CLOSER_PEND, false );
+		self.close_stream();
 
+		self.queue_event( WsEvent::CloseTimeout );
 
-		// Since closer might have queued events, before returning, make sure they are flushed.
-		//
-		Pin::new( self ).check_notify( cx )
+		true
 	}
 }
 
 
 
-impl<S: Unpin> Stream for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send
+impl<B> Stream for TungWebSocket<B> where B: WsBackend
 {
 	type Item = Result<Vec<u8>, io::Error>;
 
@@ -180,210 +724,432 @@ impl<S: Unpin> Stream for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Sen
 	//
 	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
 	{
-		// Events can provide back pressure with bounded channels. If this is pending, we don't
-		// do anything else that might generate more events before these have been delivered.
-		//
-		ready!( self.as_mut().check_notify( cx ) );
+		let _span = self.span.clone().entered();
 
-		// If we are in the middle of sending out a close frame, make sure that is finished before
-		// doing any further polling for incoming messages.
+		// While paused we are polled with a noop waker, which is of no use to wake us up.
 		//
-		// After check_closer finishes it's own work, it calls check_notify because it might have
-		// added new events to the queue.
-		//
-		ready!( self.as_mut().check_closer( cx ) );
+		if !self.state.contains( State::PAUSED )
+		{
+			match &mut self.read_waker
+			{
+				Some( waker ) => waker.clone_from( cx.waker() ),
+				None          => self.read_waker = Some( cx.waker().clone() ),
+			}
+		}
 
-		// Never poll tungstenite if we already got note that the connection is ready to be dropped.
+		// Whatever we are waiting for, don't wait for it longer than the close timeout once we are closing.
+		// If it passes, the stream is closed and polling again delivers the event and returns None.
 		//
-		if self.state.contains( State::STREAM_CLOSED )
+		match self.as_mut().poll_messages( cx )
 		{
-			return None.into();
+			Poll::Pending if self.check_close_timeout( cx ) => self.poll_messages( cx ),
+			poll                                            => poll,
 		}
+	}
+}
+
 
 
-		// Do actual reading from stream.
+impl<B> TungWebSocket<B> where B: WsBackend
+{
+	// Drive the connection and get the next binary message. See `poll_next`.
+	//
+	fn poll_messages( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option< io::Result< Vec<u8> > > >
+	{
+		// Messages that are not handed to the reader, like control frames, ignored text messages and fragments
+		// we collect, don't return. We loop around to read the next one rather than recursing, so a long run of
+		// them can't overflow the stack.
 		//
-		let res = ready!( Pin::new( &mut self.inner ).poll_next( cx ) );
+		loop
+		{
+			// Events can provide back pressure with bounded channels. If this is pending, we don't
+			// do anything else that might generate more events before these have been delivered.
+			//
+			ready!( self.as_mut().check_notify( cx ) );
 
+			// If we are in the middle of sending out a close frame, make sure that is finished before
+			// doing any further polling for incoming messages.
+			//
+			// After check_closer finishes it's own work, it calls check_notify because it might have
+			// added new events to the queue.
+			//
+			ready!( self.as_mut().check_closer( cx ) );
 
-		match res
-		{
-			None =>
+			// Never poll tungstenite if we already got note that the connection is ready to be dropped.
+			//
+			if self.state.contains( State::STREAM_CLOSED )
 			{
-				// if tungstenite is returning None here, we should no longer try to send a pending close frame.
-				//
-				self.state.remove( State::CLOSER_PEND   );
-				self.state.insert( State::STREAM_CLOSED );
+				return None.into();
+			}
 
-				None.into()
+			if self.state.contains( State::PAUSED )
+			{
+				return Poll::Pending;
 			}
 
+			// Send out pings for keepalive. This might also start the close handshake.
+			//
+			ready!( self.as_mut().check_keepalive( cx ) );
+			ready!( self.as_mut().check_pinger   ( cx ) );
+
+			// Do actual reading from stream.
+			//
+			let res = ready!( Pin::new( self.socket() ).poll_next( cx ) );
+
 
-			Some(Ok( msg )) =>
+			match res
 			{
-				match msg
+				None =>
 				{
-					TungMessage::Binary(vec) => Some(Ok( vec )).into(),
+					// if tungstenite is returning None here, we should no longer try to send a pending close frame.
+					//
+					self.set_state( State::CLOSER_PEND, false );
+					self.close_stream();
 
+					let stats = self.stats.clone();
 
-					TungMessage::Text(_) =>
+					self.queue_event( WsEvent::Closed( stats ) );
+
+					continue;
+				}
+
+
+				Some(Ok( msg )) =>
+				{
+					if msg.is_binary() || msg.is_text()
 					{
-						self.queue_event( WsEvent::Error(Arc::new( WsErr::ReceivedText )) );
+						self.stats.messages_received += 1;
+						self.stats.bytes_received    += msg.len() as u64;
 
-						let string = "Text messages are not supported.";
+						self.meter.bytes_received( msg.len() as u64 );
+					}
 
-						// If this returns pending, we don't want to recurse, the task will be woken up.
-						//
-						ready!( self.as_mut().send_closeframe( CloseCode::Unsupported, string.into(), cx ) );
+					match msg
+					{
+						TungMessage::Binary(vec) => return Some(Ok( vec )).into(),
 
-						// Continue to drive the event and the close handshake before returning.
+
+						TungMessage::Text(string) =>
+						{
+							if let Some( data ) = ready!( self.as_mut().on_text( string, cx ) )
+							{
+								return Some(Ok( data )).into();
+							}
+						}
+
+
+						TungMessage::Close(opt) =>
+						{
+							self.set_state( State::CLOSE_RECEIVED, true );
+							self.remote_close = opt.clone();
+							self.stats.close_frames_received += 1;
+							self.meter.close_code( Direction::Received, opt.as_ref().map( |frame| frame.code ) );
+
+							debug!( parent: &self.span, frame = ?opt, "close frame received" );
+
+							self.queue_event( WsEvent::CloseFrame( opt ));
+
+							// Tungstenite will keep this stream around until the underlying connection closes.
+							// It's important we don't return None here so clients don't drop the underlying connection
+							// while the other end is still processing stuff, otherwise they receive a connection reset
+							// error and can't read any more data waiting to be processed.
+							//
+							continue;
+						}
+
+
+						// Tungstenite will have answered it already
 						//
-						self.poll_next( cx )
-					}
+						TungMessage::Ping(data) =>
+						{
+							self.stats.pings_received += 1;
+							self.queue_event( WsEvent::Ping(data) );
+							continue;
+						}
+
+						// Pongs that answer our own pings are reported with the round trip time instead.
+						//
+						TungMessage::Pong(data) =>
+						{
+							self.stats.pongs_received += 1;
 
+							match self.handle_pong( &data )
+							{
+								Some( rtt ) => self.queue_event( WsEvent::Rtt ( rtt  ) ),
+								None        => self.queue_event( WsEvent::Pong( data ) ),
+							}
 
-					TungMessage::Close(opt) =>
-					{
-						self.queue_event( WsEvent::CloseFrame( opt ));
+							continue;
+						}
 
-						// Tungstenite will keep this stream around until the underlying connection closes.
-						// It's important we don't return None here so clients don't drop the underlying connection
-						// while the other end is still processing stuff, otherwise they receive a connection reset
-						// error and can't read any more data waiting to be processed.
+						// Tungstenite only returns these when writing raw frames, but a backend like FrameStream
+						// hands on the data frames of a message as they arrive.
 						//
-						self.poll_next( cx )
+						TungMessage::Frame( frame ) =>
+						{
+							if let Some( data ) = ready!( self.as_mut().on_fragment( frame, cx ) )
+							{
+								return Some(Ok( data )).into();
+							}
+						}
 					}
+				}
 
 
-					// Tungstenite will have answered it already
+
+				Some(Err( err )) =>
+				{
+					// See the wildcard at the bottom for why we need this.
 					//
-					TungMessage::Ping(data) =>
+					#[ allow( unreachable_patterns, clippy::wildcard_in_or_patterns )]
+					//
+					match err
 					{
-						self.queue_event( WsEvent::Ping(data) );
-						self.poll_next( cx )
-					}
+						// Just return None, as no more data will come in.
+						// This can mean tungstenite state is Terminated and we can safely drop the underlying connection.
+						// Note that tungstenite only set's this on the client after the server has closed the underlying
+						// connection, to comply with the RFC.
+						//
+						TungErr::ConnectionClosed |
+						TungErr::AlreadyClosed   =>
+						{
+							self.close_stream();
 
-					TungMessage::Pong(data) =>
-					{
-						self.queue_event( WsEvent::Pong(data) );
-						self.poll_next( cx )
-					}
+							let stats = self.stats.clone();
 
-					TungMessage::Frame(_) =>
-					{
-						unreachable!( "A Message::Frame(..) should be never occur from a read" );
+							self.queue_event( WsEvent::Closed( stats ) );
+
+							continue;
+						}
+
+
+						// This generally means the underlying transport is broken. Tungstenite will keep bubbling up the
+						// same error over and over, consider this fatal.
+						//
+						TungErr::Io(e) =>
+						{
+							self.close_stream();
+
+							let err = WsErr::from( e );
+
+							self.queue_event( WsEvent::Error( Arc::new( err.clone() ) ) );
+
+							return Some(Err( err.into() )).into();
+						}
+
+
+						// In principle this can fail. If the sendqueue of tungstenite is full, it will return
+						// an error and the close frame will stay in the Send future, or in the buffer of the
+						// compat sink, but the point is that it's impossible to create a full send queue with
+						// the API we provide.
+						//
+						// On every call to write on WsStream, we create a full ws message and the poll_write
+						// only
+						//
+						TungErr::Protocol( ref proto_err ) =>
+						{
+							self.meter.protocol_error();
+
+							// If this returns pending, we don't want to recurse, the task will be woken up.
+							//
+							ready!( self.as_mut().send_closeframe( CloseCode::Protocol, proto_err.to_string().into(), cx ) );
+
+
+							self.queue_event( WsEvent::Error( Arc::new( WsErr::from(err) )) );
+
+
+							// Continue to drive the event and the close handshake before returning.
+							//
+							continue;
+						}
+
+						TungErr::Utf8 => ready!( self.as_mut().on_invalid_utf8( cx ) ),
+
+
+						// The remote sent a message bigger than `max_message_size`, or with FrameStream, a frame
+						// bigger than `max_frame_size`. The backend ends the stream after this.
+						//
+						TungErr::Capacity(_) |
+
+						// None of these should occur here because they are either handshake errors or only happen
+						// when sending. Still, an update of tungstenite or a feature enabled on it by another crate
+						// should not bring down the process, so report them like any other error.
+						//
+						// async-tungstenite ends the stream after returning an error, so the next poll returns None.
+						//
+						TungErr::WriteBufferFull(_) |
+						TungErr::Url            (_) |
+
+						// I'd rather have this match exhaustive, but tungstenite has a Tls variant that
+						// is only there if they have a feature enabled. Since we cannot check whether
+						// a feature is enabled on a dependency, we have to go for wildcard here.
+						// As of tungstenite 0.19 Http and HttpFormat are also behind a feature flag.
+						//
+						_ =>
+						{
+							self.queue_event( WsEvent::Error( Arc::new( WsErr::from(err) )) );
+							continue;
+						}
 					}
 				}
 			}
+		}
+	}
+
 
+	// Handle a text message according to the text policy. Returns the data for the reader, if any.
+	//
+	fn on_text( mut self: Pin<&mut Self>, string: String, cx: &mut Context<'_> ) -> Poll< Option<Vec<u8>> >
+	{
+		match self.config.text_policy
+		{
+			TextPolicy::PassThrough => Some( string.into_bytes() ).into(),
+
+			TextPolicy::Ignore => None.into(),
 
+			TextPolicy::Event =>
+			{
+				self.queue_event( WsEvent::Text( string ) );
+				None.into()
+			}
 
-			Some(Err( err )) =>
+			TextPolicy::Reject =>
 			{
-				// See the wildcard at the bottom for why we need this.
+				self.meter.text_rejected();
+				self.queue_event( WsEvent::Error(Arc::new( WsErr::ReceivedText )) );
+
+				let reason = self.config.text_close_reason.clone();
+
+				// If this returns pending, we don't want to recurse, the task will be woken up.
 				//
-				#[ allow( unreachable_patterns, clippy::wildcard_in_or_patterns )]
+				ready!( self.as_mut().send_closeframe( CloseCode::Unsupported, reason, cx ) );
+
+				// poll_messages continues to drive the event and the close handshake before returning.
 				//
-				match err
-				{
-					// Just return None, as no more data will come in.
-					// This can mean tungstenite state is Terminated and we can safely drop the underlying connection.
-					// Note that tungstenite only set's this on the client after the server has closed the underlying
-					// connection, to comply with the RFC.
-					//
-					TungErr::ConnectionClosed |
-					TungErr::AlreadyClosed   =>
-					{
-						self.state.insert( State::STREAM_CLOSED );
+				None.into()
+			}
+		}
+	}
 
-						self.queue_event( WsEvent::Closed );
 
-						self.poll_next( cx )
-					}
+	// The remote sent a text message that is not valid UTF-8. If text messages aren't supported anyway, we
+	// don't much care for the utf errors, otherwise the remote sent us invalid data.
+	//
+	fn on_invalid_utf8( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		let (code, string) = match self.config.text_policy
+		{
+			TextPolicy::Reject => ( CloseCode::Unsupported, "Text messages are not supported"     ),
+			_                  => ( CloseCode::Invalid    , "Text message contains invalid UTF-8" ),
+		};
 
+		if self.config.text_policy == TextPolicy::Reject
+		{
+			self.meter.text_rejected();
+		}
 
-					// This generally means the underlying transport is broken. Tungstenite will keep bubbling up the
-					// same error over and over, consider this fatal.
-					//
-					TungErr::Io(e) =>
-					{
-						self.state.insert( State::STREAM_CLOSED );
+		self.queue_event( WsEvent::Error( Arc::new( WsErr::from( TungErr::Utf8 ) )) );
 
-						self.queue_event( WsEvent::Error(Arc::new( WsErr::from( io::Error::from(e.kind()) ) )) );
+		// If this returns pending, we don't want to recurse, the task will be woken up.
+		//
+		ready!( self.as_mut().send_closeframe( code, string.into(), cx ) );
 
-						Some(Err(e)).into()
-					}
+		// poll_messages continues to drive the event and the close handshake before returning.
+		//
+		().into()
+	}
 
 
-					// In principle this can fail. If the sendqueue of tungstenite is full, it will return
-					// an error and the close frame will stay in the Send future, or in the buffer of the
-					// compat sink, but the point is that it's impossible to create a full send queue with
-					// the API we provide.
-					//
-					// On every call to write on WsStream, we create a full ws message and the poll_write
-					// only
-					//
-					TungErr::Protocol( ref proto_err ) =>
-					{
-						// If this returns pending, we don't want to recurse, the task will be woken up.
-						//
-						ready!( self.as_mut().send_closeframe( CloseCode::Protocol, proto_err.to_string().into(), cx ) );
+	// A data frame from a backend that doesn't reassemble messages. Binary data is handed on as it arrives,
+	// unless we deliver whole messages. Text messages are put back together so they can be handled like any
+	// other text message. Messages we put back together are held to `max_message_size`. Returns the data
+	// for the reader, if any.
+	//
+	fn on_fragment( mut self: Pin<&mut Self>, frame: Frame, cx: &mut Context<'_> ) -> Poll< Option<Vec<u8>> >
+	{
+		let is_final = frame.header().is_final;
+		let opcode   = frame.header().opcode;
+		let data     = frame.into_data();
 
+		self.stats.bytes_received += data.len() as u64;
+		self.meter.bytes_received( data.len() as u64 );
 
-						self.queue_event( WsEvent::Error( Arc::new( WsErr::from(err) )) );
+		if is_final
+		{
+			self.stats.messages_received += 1;
+		}
 
+		let fragments = match opcode
+		{
+			OpCode::Data( Data::Continue ) => self.fragments.take().unwrap_or( Fragments::Stream ),
+			OpCode::Data( Data::Text     ) => Fragments::Collect{ text: true, data: Vec::new() },
 
-						// Continue to drive the event and the close handshake before returning.
-						//
-						self.poll_next( cx )
-					}
+			OpCode::Data( Data::Binary ) if self.whole_messages => Fragments::Collect{ text: false, data: Vec::new() },
+			OpCode::Data( Data::Binary )                        => Fragments::Stream,
 
-					// This also means the remote sent a text message which isn't supported anyway, so we don't much care
-					// for the utf errors
-					//
-					TungErr::Utf8 =>
-					{
-						let string = "Text messages are not supported";
+			_ =>
+			{
+				error!( "the websocket backend returned a raw frame that isn't a data frame: {:?}", opcode );
+				return None.into();
+			}
+		};
 
-						self.queue_event( WsEvent::Error( Arc::new( WsErr::from(err) )) );
+		match fragments
+		{
+			Fragments::Stream =>
+			{
+				if !is_final { self.fragments = Some( Fragments::Stream ); }
 
-						// If this returns pending, we don't want to recurse, the task will be woken up.
-						//
-						ready!( self.as_mut().send_closeframe( CloseCode::Unsupported, string.into(), cx ) );
+				Some( data ).into()
+			}
 
-						// Continue to drive the event and the close handshake before returning.
-						//
-						self.poll_next( cx )
-					}
+			Fragments::Discard =>
+			{
+				if !is_final { self.fragments = Some( Fragments::Discard ); }
 
+				None.into()
+			}
 
-					// The capacity for the tungstenite read buffer is currently usize::max, and there is
-					// no way for clients to change that, so this should never happen.
-					//
-					TungErr::Capacity(_) =>
-					{
-						self.queue_event( WsEvent::Error( Arc::new( WsErr::from(err) )) );
-						self.poll_next( cx )
-					}
+			Fragments::Collect{ text, data: mut message } =>
+			{
+				let max_size = self.socket().config().max_message_size.unwrap_or( usize::MAX );
+				let size     = message.len() + data.len();
 
+				if size > max_size
+				{
+					if !is_final { self.fragments = Some( Fragments::Discard ); }
 
-					// I hope none of these can occur here because they are either handshake errors
-					// or buffer capacity errors.
-					//
-					// This should only happen in the write side:
-					//
-					TungErr::WriteBufferFull(_) |
+					self.queue_event( WsEvent::Error( Arc::new( WsErr::MessageTooLarge{ size, max_size } )) );
 
-					// These are handshake errors:
+					// If this returns pending, we don't want to recurse, the task will be woken up.
 					//
-					TungErr::Url        (_)  |
+					ready!( self.as_mut().send_closeframe( CloseCode::Size, "Message too big".into(), cx ) );
 
-					// I'd rather have this match exhaustive, but tungstenite has a Tls variant that
-					// is only there if they have a feature enabled. Since we cannot check whether
-					// a feature is enabled on a dependency, we have to go for wildcard here.
-					// As of tungstenite 0.19 Http and HttpFormat are also behind a feature flag.
-					//
-					_ => unreachable!( "{:?}", err ),
+					return None.into();
+				}
+
+				message.extend( data );
+
+				if !is_final
+				{
+					self.fragments = Some( Fragments::Collect{ text, data: message } );
+					return None.into();
+				}
+
+				if !text
+				{
+					return Some( message ).into();
+				}
+
+				match String::from_utf8( message )
+				{
+					Ok( string ) => self.on_text( string, cx ),
+
+					Err( _ ) =>
+					{
+						ready!( self.as_mut().on_invalid_utf8( cx ) );
+						None.into()
+					}
 				}
 			}
 		}
@@ -392,17 +1158,38 @@ impl<S: Unpin> Stream for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Sen
 
 
 
-impl<S> Sink<Vec<u8>> for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> Drop for TungWebSocket<B> where B: WsBackend
+{
+	fn drop( &mut self )
+	{
+		// After take_parts, the connection lives on elsewhere.
+		//
+		if self.inner.is_some() && !self.state.contains( State::STREAM_CLOSED )
+		{
+			warn!( parent: &self.span, "dropped before the close handshake finished" );
+		}
+	}
+}
+
+
+
+impl<B> Sink<Vec<u8>> for TungWebSocket<B> where B: WsBackend
 {
 	type Error = io::Error;
 
 
 	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
 	{
+		let _span = self.span.clone().entered();
+
 		// If we were busy closing, first finish that. Will return on pending or OK.
 		//
 		ready!( self.as_mut().check_closer( cx ) );
 
+		// Same for pings.
+		//
+		ready!( self.as_mut().check_pinger( cx ) );
+
 		// Are there any events waiting for which we should inform observers?
 		//
 		ready!( self.as_mut().check_notify( cx ) );
@@ -410,32 +1197,26 @@ impl<S> Sink<Vec<u8>> for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Sen
 
 		if self.state.contains( State::SINK_CLOSED )
 		{
-			return Err( io::ErrorKind::NotConnected.into() ).into()
+			return Err( self.closed_err().into() ).into()
 		}
 
 
-		Pin::new( &mut self.inner ).poll_ready( cx ).map_err( |e|
-		{
-			// TODO: It's not quite clear whether the stream can remain functional when we get a sink error,
-			// but since this is a duplex connection, and poll_next also tries to send out close frames
-			// through the stream, just consider sink errors fatal.
-			//
-			self.state.insert( State::STREAM_CLOSED );
-			to_io_error( e )
-		})
+		Pin::new( self.socket() ).poll_ready( cx ).map_err( |e| self.sink_error( e ) )
 	}
 
 
 	/// ### Errors
 	///
-	/// The following errors can be returned when writing to the stream:
+	/// The following errors can be returned when writing to the stream. They all contain a [`WsErr`]:
 	///
 	/// - [`io::ErrorKind::NotConnected`]: This means that the connection is already closed. You should
 	///   no longer write to it. It is safe to drop the underlying connection when `poll_next` returns None.
+	///   Contains [`WsErr::RemoteClosed`] if the remote closed the connection with a close code, [`WsErr::Closed`]
+	///   otherwise.
 	///
 	///   TODO: if error capacity get's returned, is the socket still usable?
 	///
-	/// - [`io::ErrorKind::InvalidData`]: This means that a tungstenite::error::Capacity occurred. This means that
+	/// - [`io::ErrorKind::InvalidData`]: With [`WsErr::MessageTooLarge`]. This means that
 	///   you send in a buffer bigger than the maximum message size configured on the underlying websocket connection.
 	///   If you did not set it manually, the default for tungstenite is 64MB.
 	///
@@ -444,70 +1225,70 @@ impl<S> Sink<Vec<u8>> for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Sen
 	//
 	fn start_send( mut self: Pin<&mut Self>, item: Vec<u8> ) -> Result<(), Self::Error>
 	{
-		if self.state.contains( State::SINK_CLOSED )
-		{
-			return Err( io::ErrorKind::NotConnected.into() )
-		}
+		let _span = self.span.clone().entered();
 
+		let len = item.len() as u64;
 
-		Pin::new( &mut self.inner ).start_send( item.into() ).map_err( |e|
-		{
-			// TODO: It's not quite clear whether the stream can remain functional when we get a sink error,
-			// but since this is a duplex connection, and poll_next also tries to send out close frames
-			// through the stream, just consider sink errors fatal.
-			//
-			self.state.insert( State::STREAM_CLOSED );
-			to_io_error( e )
-		})
+		self.send_message( item.into() )?;
+
+		self.stats.messages_sent += 1;
+		self.stats.bytes_sent    += len;
+
+		self.meter.bytes_sent( len );
+
+		Ok(())
 	}
 
 	/// This will do a send under the hood, so the same errors as from start_send can occur here.
 	//
 	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
 	{
-		Pin::new( &mut self.inner ).poll_flush( cx ).map_err( |e|
-		{
-			// TODO: It's not quite clear whether the stream can remain functional when we get a sink error,
-			// but since this is a duplex connection, and poll_next also tries to send out close frames
-			// through the stream, just consider sink errors fatal.
-			//
-			self.state.insert( State::STREAM_CLOSED );
-			to_io_error( e )
-		})
+		let _span = self.span.clone().entered();
+
+		Pin::new( self.socket() ).poll_flush( cx ).map_err( |e| self.sink_error( e ) )
 	}
 
 
 	/// Will resolve immediately. Keep polling the stream until it returns None. To make sure
 	/// to keep the underlying connection alive until the close handshake is finished.
 	///
+	/// If a close frame was queued with `close_with`, it will be sent out first.
+	///
 	/// This will do a send under the hood, so the same errors as from start_send can occur here,
 	/// except InvalidData.
 	//
 	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
 	{
-		self.state.insert( State::SINK_CLOSED );
+		let _span = self.span.clone().entered();
+
+		ready!( self.as_mut().check_closer( cx ) );
+
+		// Tungstenite will send a close frame, unless one was already sent.
+		//
+		if !self.state.intersects( State::SINK_CLOSED | State::CLOSE_RECEIVED )
+		{
+			self.stats.close_frames_sent += 1;
+			self.meter.close_code( Direction::Sent, None );
+
+			debug!( parent: &self.span, "close frame sent without a close code" );
+		}
+
+		self.set_state( State::SINK_CLOSED, true );
 
 		// We ignore closed errors since that's what we want, and because after calling this method
 		// the sender task can in any case be dropped, and verifying that the connection can actually
 		// be closed should be done through the reader task.
 		//
-		Pin::new( &mut self.inner ).poll_close( cx ).map_err( |e|
-		{
-			// TODO: It's not quite clear whether the stream can remain functional when we get a sink error,
-			// but since this is a duplex connection, and poll_next also tries to send out close frames
-			// through the stream, just consider sink errors fatal.
-			//
-			self.state.insert( State::STREAM_CLOSED );
-			to_io_error( e )
-		})
+		Pin::new( self.socket() ).poll_close( cx ).map_err( |e| self.sink_error( e ) )
 	}
 }
 
 
 
-// Convert tungstenite errors that can happen during sending into io::Error.
+// Convert tungstenite errors that can happen during sending into WsErr. `closed` is returned when the connection
+// is closed and `max_write` is the tungstenite `max_write_buffer_size`.
 //
-fn to_io_error( err: TungErr ) -> io::Error
+fn send_error( err: TungErr, closed: WsErr, max_write: usize ) -> WsErr
 {
 	// See the wildcard at the bottom for why we need this.
 	//
@@ -517,7 +1298,7 @@ fn to_io_error( err: TungErr ) -> io::Error
 	{
 		// Mainly on the underlying stream. Fatal
 		//
-		TungErr::Io(err) => err,
+		TungErr::Io(err) => err.into(),
 
 
 		// Connection is closed, does not indicate something went wrong.
@@ -527,50 +1308,70 @@ fn to_io_error( err: TungErr ) -> io::Error
 		// Connection is closed, in principle this indicates that the user tries to keep using it
 		// after ConnectionClosed has already been returned.
 		//
-		TungErr::AlreadyClosed => io::ErrorKind::NotConnected.into() ,
+		TungErr::AlreadyClosed => closed,
 
 
-		// This shouldn't happen, we should not cause any protocol errors, since we abstract
-		// away the websocket protocol for users. They shouldn't be able to trigger this through our API.
-		// AFAICT the only one you can trigger on send is SendAfterClose unless you create control
-		// frames yourself, which we don't.
+		// We should not cause any protocol errors, since we abstract away the websocket protocol for users.
+		// AFAICT the only one you can trigger on send is SendAfterClosing unless you create control
+		// frames yourself, which we don't. That means the connection is closing.
 		//
-		TungErr::Protocol(source) =>
+		TungErr::Protocol( ProtocolError::SendAfterClosing ) => closed,
+
+		// Anything else would be a bug in ws_stream_tungstenite, please report at
+		// http://github.com/najamelan/ws_stream_tungstenite/issues.
+		//
+		err @ TungErr::Protocol(_) =>
 		{
-			unreachable!( "protocol error from tungstenite on send is a bug in ws_stream_tungstenite, please report at http://github.com/najamelan/ws_stream_tungstenite/issues. The error from tungstenite is {}", source );
+			error!( "protocol error from tungstenite on send, this is a bug in ws_stream_tungstenite, please report: {}", err );
+
+			WsErr::Tungstenite{ source: Arc::new( err ) }
 		}
 
 
 		// This can happen when we create a message bigger than max message size in tungstenite.
 		//
-		TungErr::Capacity(string) => io::Error::new( io::ErrorKind::InvalidData, string ),
+		TungErr::Capacity( CapacityError::MessageTooLong{ size, max_size } ) => WsErr::MessageTooLarge{ size, max_size },
 
 
 		// This can happen if we send a message bigger than the tungstenite `max_write_buffer_len`.
-		// However `WsStream` looks at the size of this buffer and only sends up to `max_write_buffer_len`
-		// bytes in one message.
+		// `WsStream` looks at the size of this buffer and only sends up to `max_write_buffer_len`
+		// bytes in one message, but `WsMessageStream` sends whatever the user gives it.
+		//
+		// Tungstenite gives back the message as a frame, but we report the size of the payload.
 		//
-		TungErr::WriteBufferFull(_) => unreachable!( "TungErr::WriteBufferFull" ),
+		TungErr::WriteBufferFull( msg ) =>
+		{
+			let size = match msg
+			{
+				TungMessage::Frame( frame ) => frame.payload().len(),
+				msg                         => msg.len(),
+			};
+
+			WsErr::MessageTooLarge{ size, max_size: max_write }
+		}
 
-		// These are handshake errors
+		// These are handshake errors, they can't happen on a connection that is established.
 		//
-		TungErr::Url(_) => unreachable!( "TungErr::Url" ),
+		TungErr::Url(_) |
 
-		// This is an error specific to Text Messages that we don't use
+		// This is an error specific to Text Messages, which we don't send.
 		//
-		TungErr::Utf8 => unreachable!( "TungErr::Utf8" ),
+		TungErr::Utf8 |
 
 		// I'd rather have this match exhaustive, but tungstenite has a Tls variant that
 		// is only there if they have a feature enabled. Since we cannot check whether
 		// a feature is enabled on a dependency, we have to go for wildcard here.
 		// As of tungstenite 0.19 Http and HttpFormat are also behind a feature flag.
 		//
-		x => unreachable!( "unmatched tungstenite error: {x}" ),
+		// These don't happen on an established connection either, but an update of tungstenite
+		// should not bring down the process.
+		//
+		_ => WsErr::Tungstenite{ source: Arc::new( err ) },
 	}
 }
 
 
-impl<S> Observable< WsEvent > for TungWebSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> Observable< WsEvent > for TungWebSocket<B> where B: WsBackend
 {
 	type Error = WsErr;
 
@@ -578,7 +1379,7 @@ impl<S> Observable< WsEvent > for TungWebSocket<S> where S: AsyncRead + AsyncWri
 	{
 		async move
 		{
-			self.notifier.observe( options ).await.map_err( Into::into )
+			self.notifier.observe( options ).await
 
 		}.boxed()
 	}
@@ -586,4 +1387,65 @@ impl<S> Observable< WsEvent > for TungWebSocket<S> where S: AsyncRead + AsyncWri
 
 
 
+#[ cfg( test ) ]
+//
+mod tests
+{
+	// Tested:
+	//
+	// ✔ every tungstenite error maps to a WsErr inside an io::Error without panicking
+	// ✔ message size and remote close code are reported
+	//
+	use super::{ *, assert_matches };
+	use tungstenite::error::UrlError;
+
+
+	#[ test ]
+	//
+	fn send_error_kinds()
+	{
+		let errors = vec!
+		[
+			( TungErr::Io( io::ErrorKind::BrokenPipe.into() )                            , io::ErrorKind::BrokenPipe   ),
+			( TungErr::ConnectionClosed                                                  , io::ErrorKind::NotConnected ),
+			( TungErr::AlreadyClosed                                                     , io::ErrorKind::NotConnected ),
+			( TungErr::Protocol( ProtocolError::SendAfterClosing )                       , io::ErrorKind::NotConnected ),
+			( TungErr::Protocol( ProtocolError::ResetWithoutClosingHandshake )           , io::ErrorKind::InvalidInput ),
+			( TungErr::Capacity( CapacityError::MessageTooLong{ size: 2, max_size: 1 } ) , io::ErrorKind::InvalidData  ),
+			( TungErr::Capacity( CapacityError::TooManyHeaders )                         , io::ErrorKind::InvalidData  ),
+			( TungErr::WriteBufferFull( TungMessage::Binary( vec![ 1 ] ) )               , io::ErrorKind::InvalidData  ),
+			( TungErr::Url( UrlError::NoHostName )                                       , io::ErrorKind::InvalidInput ),
+			( TungErr::Utf8                                                              , io::ErrorKind::InvalidData  ),
+
+			// Goes through the wildcard, like the errors behind tungstenite features.
+			//
+			( TungErr::AttackAttempt                                                     , io::ErrorKind::Other        ),
+		];
+
+		for (err, kind) in errors
+		{
+			let name = format!( "{err:?}" );
+			let err  = io::Error::from( send_error( err, WsErr::Closed, 0 ) );
+
+			assert_eq!( kind, err.kind(), "{name}" );
+			assert!( err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() ).is_some(), "{name}" );
+		}
+	}
+
 
+	// The size of the write buffer is reported for WriteBufferFull and the closed error is used when closed.
+	//
+	#[ test ]
+	//
+	fn send_error_variants()
+	{
+		let err = send_error( TungErr::WriteBufferFull( TungMessage::Binary( vec![ 1, 2 ] ) ), WsErr::Closed, 1 );
+
+		assert_matches!( err, WsErr::MessageTooLarge{ size: 2, max_size: 1 } );
+
+		let closed = WsErr::RemoteClosed{ code: CloseCode::Away, reason: "bye".into() };
+		let err    = send_error( TungErr::AlreadyClosed, closed, 1 );
+
+		assert_matches!( err, WsErr::RemoteClosed{ code: CloseCode::Away, .. } );
+	}
+}
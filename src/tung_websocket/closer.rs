@@ -1,7 +1,7 @@
 use
 {
-	crate :: { import::*, WsEvent, WsErr } ,
-	super :: { notifier::Notifier        } ,
+	crate :: { import::*, WsEvent, WsErr, WsStats              } ,
+	super :: { notifier::Notifier, meter::{ Meter, Direction } } ,
 };
 
 
@@ -84,16 +84,37 @@ impl Closer
 
 
 
+	// Take back a close frame that was queued but not yet handed to the sink.
+	//
+	pub(super) fn take_frame( &mut self ) -> Option< CloseFrame<'static> >
+	{
+		match std::mem::replace( &mut self.state, State::Ready )
+		{
+			State::Closing( frame ) => Some( frame ),
+
+			other =>
+			{
+				self.state = other;
+				None
+			}
+		}
+	}
+
+
+
 	// Will try to send out a close frame to the websocket. It will then poll that send for completion
 	// saving it's state and returning pending if no more progress can be made.
 	//
-	// Any errors that happen will be returned out of band as pharos events through the Notifier.
+	// Any errors that happen will be returned out of band as pharos events through the Notifier and
+	// counted in the stats.
 	//
 	pub(super) fn run
 	(
 		mut self   : Pin<&mut Self>                                         ,
 		mut socket : impl Sink<tungstenite::Message, Error=TungErr> + Unpin ,
 		    ph     : &mut Notifier                                          ,
+		    stats  : &mut WsStats                                           ,
+		    meter  : &Meter                                                 ,
 		    cx     : &mut Context<'_>	                                      ,
 	)
 		-> Poll< Result<(), ()> >
@@ -119,6 +140,7 @@ impl Closer
 					Poll::Ready(Err(e)) =>
 					{
 						ph.queue( WsEvent::Error( Arc::new( e.into() )) );
+						stats.errors += 1;
 
 						self.state = State::SinkError;
 						Err(()).into()
@@ -128,11 +150,23 @@ impl Closer
 					{
 						// Send the frame
 						//
-						if let Err(e) = Pin::new( &mut socket ).as_mut().start_send( TungMessage::Close( Some(frame.clone()) ) )
+						match Pin::new( &mut socket ).as_mut().start_send( TungMessage::Close( Some(frame.clone()) ) )
 						{
-							ph.queue( WsEvent::Error( Arc::new( e.into() )) );
+							Ok(()) =>
+							{
+								stats.close_frames_sent += 1;
+								meter.close_code( Direction::Sent, Some( frame.code ) );
 
-							self.state = State::SinkError;
+								debug!( ?frame, "close frame sent" );
+							}
+
+							Err(e) =>
+							{
+								ph.queue( WsEvent::Error( Arc::new( e.into() )) );
+								stats.errors += 1;
+
+								self.state = State::SinkError;
+							}
 						}
 
 						// Flush
@@ -159,6 +193,7 @@ impl Closer
 							Poll::Ready(Err(e)) =>
 							{
 								ph.queue( WsEvent::Error( Arc::new( e.into() )) );
+								stats.errors += 1;
 
 								self.state = State::SinkError;
 
@@ -196,6 +231,7 @@ impl Closer
 					Poll::Ready(Err(e)) =>
 					{
 						ph.queue( WsEvent::Error( Arc::new( WsErr::from(e) )) );
+						stats.errors += 1;
 
 						self.state = State::SinkError;
 
@@ -2,7 +2,7 @@
 //
 // ✔ closer actually sends out on sink
 //
-use crate :: { import::{ *, assert_matches }, tung_websocket::{ notifier::Notifier, closer::Closer } };
+use crate :: { import::{ *, assert_matches }, WsStats, WsStreamConfig, tung_websocket::{ notifier::Notifier, closer::Closer, meter::Meter } };
 
 
 #[ test ]
@@ -19,7 +19,7 @@ fn send_closeframe()
 		let mut sink   = ATungSocket::from_raw_socket( sc, Role::Server, None ).await.split().0;
 		let mut stream = ATungSocket::from_raw_socket( cs, Role::Client, None ).await.split().1;
 
-		let mut notif  = Notifier::new();
+		let mut notif  = Notifier::new( 2 );
 		let mut closer = Closer::new();
 		let     waker  = noop_waker();
 		let mut cx     = Context::from_waker( &waker );
@@ -31,7 +31,7 @@ fn send_closeframe()
 				.expect( "no double close" )
 			;
 
-			let p = Pin::new( &mut closer ).run( &mut sink, &mut notif, &mut cx );
+			let p = Pin::new( &mut closer ).run( &mut sink, &mut notif, &mut WsStats::new(), &Meter::new( &WsStreamConfig::default() ), &mut cx );
 
 			assert_matches!( p, Poll::Ready( Ok(()) ) );
 
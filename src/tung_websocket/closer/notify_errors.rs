@@ -2,7 +2,7 @@
 //
 // ✔ notifiying errors through pharos
 //
-use crate :: { import::{ *, assert_matches }, WsEvent, WsErr, tung_websocket::{ notifier::Notifier, closer::Closer } };
+use crate :: { import::{ *, assert_matches }, WsStats, WsEvent, WsErr, WsStreamConfig, tung_websocket::{ notifier::Notifier, closer::Closer, meter::Meter } };
 
 
 #[ async_std::test ]
@@ -19,7 +19,7 @@ async fn notify_errors()
 		let mut sink   = ATungSocket::from_raw_socket( sc, Role::Server, None ).await.split().0;
 		let mut stream = ATungSocket::from_raw_socket( cs, Role::Client, None ).await.split().1;
 
-		let mut notif  = Notifier::new();
+		let mut notif  = Notifier::new( 2 );
 		let mut events = notif.observe( ObserveConfig::default() ).await.expect( "observe server" );
 		let mut closer = Closer::new();
 		let     waker  = noop_waker();
@@ -34,7 +34,7 @@ async fn notify_errors()
 
 			// this will encounter an error since the sink is already closed
 			//
-			let p = Pin::new( &mut closer ).run( &mut sink, &mut notif, &mut cx );
+			let p = Pin::new( &mut closer ).run( &mut sink, &mut notif, &mut WsStats::new(), &Meter::new( &WsStreamConfig::default() ), &mut cx );
 
 			assert_matches!( p, Poll::Ready( Err(()) ) );
 
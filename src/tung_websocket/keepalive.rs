@@ -0,0 +1,91 @@
+use crate :: { import::*, timer::{ Timer, Delay } };
+
+
+// What the keepalive wants the websocket to do.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub(super) enum Action
+{
+	// It's time to send a ping.
+	//
+	Ping,
+
+	// The pong for our last ping didn't arrive in time.
+	//
+	Timeout,
+}
+
+
+
+// Keep track of when to send pings and whether the remote answers them in time.
+//
+pub(super) struct KeepAlive
+{
+	interval: Duration        ,
+	timeout : Duration        ,
+	timer   : Timer           ,
+	delay   : Option<Delay>   ,
+
+	// The payload of the ping we are waiting a pong for.
+	//
+	waiting : Option<Vec<u8>> ,
+}
+
+
+impl KeepAlive
+{
+	pub(super) fn new( interval: Duration, timeout: Duration, timer: Timer ) -> Self
+	{
+		let delay = Some( timer.delay( interval ) );
+
+		Self { interval, timeout, timer, delay, waiting: None }
+	}
+
+
+	// Poll the current delay. Once it times out, there is nothing more to do, so it
+	// returns pending from then on.
+	//
+	pub(super) fn poll_action( &mut self, cx: &mut Context<'_> ) -> Poll<Action>
+	{
+		let Some( delay ) = &mut self.delay else { return Poll::Pending };
+
+		ready!( delay.as_mut().poll( cx ) );
+
+		if self.waiting.is_some()
+		{
+			self.delay = None;
+			return Action::Timeout.into();
+		}
+
+		Action::Ping.into()
+	}
+
+
+	// Tell us the payload of the ping that was sent because we returned `Action::Ping`.
+	//
+	pub(super) fn sent( &mut self, payload: Vec<u8> )
+	{
+		self.waiting = Some( payload );
+		self.delay   = Some( self.timer.delay( self.timeout ) );
+	}
+
+
+	// Tell us a ping was answered. If it's the one we are waiting for, we wait until it's time
+	// for the next ping.
+	//
+	pub(super) fn pong( &mut self, payload: &[u8] )
+	{
+		if self.waiting.as_deref() != Some( payload )
+		{
+			return;
+		}
+
+		self.waiting = None;
+
+		if self.delay.is_some()
+		{
+			self.delay = Some( self.timer.delay( self.interval ) );
+		}
+	}
+}
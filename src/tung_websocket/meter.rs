@@ -0,0 +1,152 @@
+use crate::{ import::*, WsStreamConfig };
+
+
+// Names of the metrics we record. These are documented on `WsStreamConfig::metrics_labels`.
+//
+#[ cfg( feature = "metrics" ) ] const OPEN_CONNECTIONS: &str = "ws_stream_tungstenite_open_connections"     ;
+#[ cfg( feature = "metrics" ) ] const BYTES_RECEIVED  : &str = "ws_stream_tungstenite_bytes_received_total"  ;
+#[ cfg( feature = "metrics" ) ] const BYTES_SENT      : &str = "ws_stream_tungstenite_bytes_sent_total"      ;
+#[ cfg( feature = "metrics" ) ] const CLOSE_CODES     : &str = "ws_stream_tungstenite_close_codes_total"     ;
+#[ cfg( feature = "metrics" ) ] const PROTOCOL_ERRORS : &str = "ws_stream_tungstenite_protocol_errors_total" ;
+#[ cfg( feature = "metrics" ) ] const TEXT_REJECTIONS : &str = "ws_stream_tungstenite_text_rejections_total" ;
+
+
+// Which side sent a close frame.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub(super) enum Direction
+{
+	Sent     ,
+	Received ,
+}
+
+
+// Records metrics through the `metrics` facade with the labels from the config. Without the metrics
+// feature, this does nothing.
+//
+// The handles are registered on creation, so the recorder that is installed at that point is used
+// for the lifetime of the connection.
+//
+pub(super) struct Meter
+{
+	#[ cfg( feature = "metrics" ) ] labels          : Vec<Label>    ,
+	#[ cfg( feature = "metrics" ) ] open            : Option<Gauge> ,
+	#[ cfg( feature = "metrics" ) ] bytes_received  : Counter       ,
+	#[ cfg( feature = "metrics" ) ] bytes_sent      : Counter       ,
+	#[ cfg( feature = "metrics" ) ] protocol_errors : Counter       ,
+	#[ cfg( feature = "metrics" ) ] text_rejections : Counter       ,
+}
+
+
+
+#[ cfg( feature = "metrics" ) ]
+//
+impl Meter
+{
+	// Counts the connection as open until `closed` is called or we are dropped.
+	//
+	pub(super) fn new( config: &WsStreamConfig ) -> Self
+	{
+		let labels = config.metrics_labels.clone();
+		let open   = gauge!( OPEN_CONNECTIONS, labels.iter() );
+
+		open.increment( 1.0 );
+
+		Self
+		{
+			open           : Some( open )                               ,
+			bytes_received : counter!( BYTES_RECEIVED , labels.iter() ) ,
+			bytes_sent     : counter!( BYTES_SENT     , labels.iter() ) ,
+			protocol_errors: counter!( PROTOCOL_ERRORS, labels.iter() ) ,
+			text_rejections: counter!( TEXT_REJECTIONS, labels.iter() ) ,
+			labels                                                      ,
+		}
+	}
+
+
+	// The connection is closed. Only the first call has an effect.
+	//
+	pub(super) fn closed( &mut self )
+	{
+		if let Some( open ) = self.open.take()
+		{
+			open.decrement( 1.0 );
+		}
+	}
+
+
+	pub(super) fn bytes_received( &self, bytes: u64 )
+	{
+		self.bytes_received.increment( bytes );
+	}
+
+
+	pub(super) fn bytes_sent( &self, bytes: u64 )
+	{
+		self.bytes_sent.increment( bytes );
+	}
+
+
+	pub(super) fn protocol_error( &self )
+	{
+		self.protocol_errors.increment( 1 );
+	}
+
+
+	pub(super) fn text_rejected( &self )
+	{
+		self.text_rejections.increment( 1 );
+	}
+
+
+	// A close frame without a code is counted with the code "none".
+	//
+	pub(super) fn close_code( &self, direction: Direction, code: Option<CloseCode> )
+	{
+		let code = match code
+		{
+			Some( code ) => u16::from( code ).to_string(),
+			None         => "none".to_string(),
+		};
+
+		let direction = match direction
+		{
+			Direction::Sent     => "sent"     ,
+			Direction::Received => "received" ,
+		};
+
+		let mut labels = self.labels.clone();
+
+		labels.push( Label::new( "code"     , code      ) );
+		labels.push( Label::new( "direction", direction ) );
+
+		counter!( CLOSE_CODES, labels ).increment( 1 );
+	}
+}
+
+
+
+#[ cfg( not( feature = "metrics" ) ) ]
+//
+impl Meter
+{
+	pub(super) fn new( _config: &WsStreamConfig ) -> Self { Self {} }
+
+	pub(super) fn closed        ( &mut self                                              ) {}
+	pub(super) fn bytes_received( &self, _bytes: u64                                     ) {}
+	pub(super) fn bytes_sent    ( &self, _bytes: u64                                     ) {}
+	pub(super) fn protocol_error( &self                                                  ) {}
+	pub(super) fn text_rejected ( &self                                                  ) {}
+	pub(super) fn close_code    ( &self, _direction: Direction, _code: Option<CloseCode> ) {}
+}
+
+
+
+impl Drop for Meter
+{
+	fn drop( &mut self )
+	{
+		self.closed();
+	}
+}
@@ -45,14 +45,14 @@ pub(super) struct Notifier
 
 impl Notifier
 {
-	pub(crate) fn new() -> Self
+	// Most of the time there will probably not be many observers, so keep capacity low
+	// to keep memory consumption down. The default from WsStreamConfig is 2.
+	//
+	pub(crate) fn new( capacity: usize ) -> Self
 	{
 		Self
 		{
-			// Most of the time there will probably not be many observers
-			// this keeps memory consumption down
-			//
-			pharos: Pharos::new( 2 ) ,
+			pharos: Pharos::new( capacity ) ,
 			state : State::Ready     ,
 			events: VecDeque::new()  ,
 		}
@@ -71,6 +71,19 @@ impl Notifier
 	}
 
 
+	// Take out all events that have not yet been delivered to observers.
+	//
+	pub(crate) fn take_events( &mut self ) -> Vec<WsEvent>
+	{
+		if self.state == State::Pending
+		{
+			self.state = State::Ready;
+		}
+
+		self.events.drain(..).collect()
+	}
+
+
 	// try to send out queued events.
 	//
 	pub(crate) fn run( &mut self, cx: &mut Context<'_> ) -> Poll< Result<(), ()> >
@@ -198,6 +211,7 @@ mod tests
 	// ✔ verify everything get's delivered correctly after pharos gives back pressure
 	//
 	use super::{ *, assert_matches };
+	use crate::WsStats;
 
 
 	// verify state becomes pending when queing something and get's reset after calling run without observers.
@@ -207,7 +221,7 @@ mod tests
 	fn notifier_state()
 	{
 
-		let mut not = Notifier::new();
+		let mut not = Notifier::new( 2 );
 
 			assert_eq!( State::Ready, not.state );
 
@@ -226,7 +240,7 @@ mod tests
 
 
 
-		not.queue( WsEvent::Closed );
+		not.queue( WsEvent::Closed( WsStats::new() ) );
 
 			assert_eq!( State::Pending, not.state );
 	}
@@ -238,7 +252,7 @@ mod tests
 	//
 	async fn notifier_state_observers()
 	{
-		let mut not  = Notifier::new();
+		let mut not  = Notifier::new( 2 );
 		let mut evts = not.observe( Channel::Bounded( 1 ).into() ).await.expect( "observe" );
 
 			assert_eq!( State::Ready, not.state        );
@@ -295,7 +309,7 @@ mod tests
 	//
 	fn queue()
 	{
-		let mut not = Notifier::new();
+		let mut not = Notifier::new( 2 );
 
 			assert_eq!( 0, not.events.len() );
 
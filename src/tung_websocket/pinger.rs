@@ -0,0 +1,150 @@
+use crate :: { import::*, WsStats };
+
+
+// Keep track of our state so we can progress through it if the sink returns pending.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+enum State
+{
+	Ready,
+
+	// We handed a ping to the sink, but flushing returned pending.
+	//
+	Flushing,
+}
+
+
+
+// How many pings from `ping` can wait for a pong at once. The remote doesn't have to answer every ping, so a
+// ping is forgotten once nobody waits for its pong anymore. Keepalive pings don't count, the keepalive closes
+// the connection when they aren't answered.
+//
+pub(super) const MAX_PINGS: usize = 16;
+
+
+
+// Sends ping frames from the reader task. Like the closer, the sink might not be ready, so the pings are
+// queued and we try again on each read or write from the user until they are flushed out.
+//
+// Pings that have been handed to the sink are remembered with the time they were sent, so we can
+// measure the round trip time when the pong comes in.
+//
+pub(super) struct Pinger
+{
+	state    : State                          ,
+	queue    : VecDeque< Vec<u8> >            ,
+	in_flight: VecDeque<( Vec<u8>, Instant )> ,
+	counter  : u64                            ,
+}
+
+
+impl Pinger
+{
+	pub(super) fn new() -> Self
+	{
+		Self
+		{
+			state    : State::Ready    ,
+			queue    : VecDeque::new() ,
+			in_flight: VecDeque::new() ,
+			counter  : 0               ,
+		}
+	}
+
+
+	// Create a payload for a new ping. Every ping we send gets a unique payload so we can recognize
+	// the pong that answers it.
+	//
+	pub(super) fn next_payload( &mut self ) -> Vec<u8>
+	{
+		self.counter = self.counter.wrapping_add( 1 );
+		self.counter.to_be_bytes().to_vec()
+	}
+
+
+	pub(super) fn queue( &mut self, payload: Vec<u8> )
+	{
+		self.queue.push_back( payload );
+	}
+
+
+	// Stop tracking a ping, whether it was sent already or not.
+	//
+	pub(super) fn forget( &mut self, payload: &[u8] )
+	{
+		self.queue    .retain( |p     | p != payload );
+		self.in_flight.retain( |(p, _)| p != payload );
+	}
+
+
+	// Send out all queued pings and flush them.
+	//
+	// Returns pending until all of them are flushed. Errors from the sink are returned so the caller can
+	// report them.
+	//
+	pub(super) fn run
+	(
+		    &mut self                                                       ,
+		mut socket : impl Sink<tungstenite::Message, Error=TungErr> + Unpin ,
+		    stats  : &mut WsStats                                           ,
+		    cx     : &mut Context<'_>                                       ,
+	)
+		-> Poll< Result<(), TungErr> >
+
+	{
+		while !self.queue.is_empty()
+		{
+			if let Err(e) = ready!( Pin::new( &mut socket ).poll_ready( cx ) )
+			{
+				self.queue.clear();
+				return Err(e).into();
+			}
+
+			let payload = self.queue.pop_front().expect( "queue not to be empty" );
+
+			// If this fails, the sink is no longer usable, so there is no point in keeping other pings.
+			//
+			if let Err(e) = Pin::new( &mut socket ).start_send( TungMessage::Ping( payload.clone() ) )
+			{
+				self.queue.clear();
+				return Err(e).into();
+			}
+
+			self.in_flight.push_back(( payload, Instant::now() ));
+			stats.pings_sent += 1;
+
+			self.state = State::Flushing;
+		}
+
+
+		if self.state == State::Flushing
+		{
+			ready!( Pin::new( &mut socket ).poll_flush( cx ) )?;
+
+			self.state = State::Ready;
+		}
+
+		Ok(()).into()
+	}
+
+
+	// Match a pong against the pings we sent. Returns the payloads of the pings it answers with their
+	// round trip time, the matched ping last. Empty if the pong doesn't answer any of our pings.
+	//
+	// The remote may choose to only answer the most recent of several pings (RFC 6455 section 5.5.3),
+	// so pings sent before the matched one are considered answered as well.
+	//
+	pub(super) fn pong( &mut self, payload: &[u8] ) -> Vec<( Vec<u8>, Duration )>
+	{
+		let Some( idx ) = self.in_flight.iter().position( |(p, _)| p == payload ) else
+		{
+			return Vec::new();
+		};
+
+		self.in_flight.drain( ..=idx )
+
+			.map( |(p, sent)| (p, sent.elapsed()) )
+			.collect()
+	}
+}
@@ -0,0 +1,20 @@
+/// How the bytes written through `AsyncWrite` are turned into websocket messages. Set it with
+/// [`WsStreamConfig::write_mode`](crate::WsStreamConfig::write_mode).
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Default ) ]
+//
+pub enum WriteMode
+{
+	/// Every call to `poll_write` creates one Binary message, split up if it exceeds the maximum write size.
+	/// Data is sent out immediately, but a codec that writes a header and a body separately will produce
+	/// two messages per frame.
+	//
+	#[ default ]
+	//
+	PerWrite,
+
+	/// Writes are buffered and sent as one Binary message on each flush, or when the buffer reaches the
+	/// maximum write size. You must flush for data to be sent, which codecs do after every frame.
+	//
+	FlushDelimited,
+}
@@ -0,0 +1,68 @@
+use crate::{ import::* };
+
+
+/// A message level websocket connection that [`WsStream`](crate::WsStream) and
+/// [`WsMessageStream`](crate::WsMessageStream) can run over. It is implemented for
+/// [`async_tungstenite::WebSocketStream`], which is what you will normally use, but you can implement it for
+/// another websocket library or for a test double. With the `tokio_io` feature, it is also implemented for
+/// `tokio_tungstenite::WebSocketStream`, so tokio users don't need a compat wrapper around their connection.
+///
+/// Messages use the types from _tungstenite_ as vocabulary, so a backend converts from and to those.
+///
+/// ## Contract
+///
+/// The close handshake and error handling of _ws_stream_tungstenite_ rely on the backend behaving like
+/// _tungstenite_:
+///
+/// - Pings are answered by the backend. Incoming pings and pongs are still yielded by the stream so they
+///   can be counted, but we never reply to them ourselves.
+/// - When a close frame is received, the backend yields it and queues the reply. The reply is sent out
+///   on the next call to `poll_flush` or `poll_close`.
+/// - `poll_close` sends a close frame if none was sent yet, and flushes.
+/// - The stream ends with `None` or with [`TungErr::ConnectionClosed`] once the close handshake is done.
+///   After that, sending returns [`TungErr::AlreadyClosed`] or [`ProtocolError::SendAfterClosing`].
+/// - [`TungMessage::Frame`] is only sent when [`WsStreamConfig::fragment_size`](crate::WsStreamConfig::fragment_size)
+///   is set. It must be sent as is, without further framing.
+/// - Data messages can be yielded whole, or as one unmasked [`TungMessage::Frame`] per data frame as they arrive.
+///   The backend checks that the fragments of a message are in order.
+/// - The limits in [`WsBackend::config`] are enforced, returning [`CapacityError::MessageTooLong`] for
+///   messages that are too big and [`TungErr::WriteBufferFull`] when the write buffer is full.
+//
+pub trait WsBackend
+
+	: Stream< Item = Result<TungMessage, TungErr> >
+	+ Sink  < TungMessage, Error = TungErr        >
+	+ Send + Unpin
+{
+	/// The limits of this connection. We use `max_message_size` and `max_write_buffer_size`
+	/// to size our writes and to validate a [`WsStreamConfig`](crate::WsStreamConfig).
+	//
+	fn config( &self ) -> &WebSocketConfig;
+}
+
+
+
+impl<S> WsBackend for ATungSocket<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+{
+	fn config( &self ) -> &WebSocketConfig
+	{
+		self.get_config()
+	}
+}
+
+
+
+/// Lets you pass a `tokio_tungstenite::WebSocketStream` over a tokio `TcpStream` or any other tokio
+/// transport straight to [`WsStream::new`](crate::WsStream::new), without going through `tokio_util::compat`.
+//
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<S> WsBackend for TokioTungSocket<S> where S: TokAsyncRead + TokAsyncWrite + Send + Unpin
+{
+	fn config( &self ) -> &WebSocketConfig
+	{
+		self.get_config()
+	}
+}
@@ -2,8 +2,23 @@ use crate::{ import::* };
 
 
 /// The error type for errors happening in _ws_stream_tungstenite_.
+///
+/// Errors returned in band from `AsyncRead`/`AsyncWrite` and the `Stream`/`Sink` of [`WsMessageStream`](crate::WsMessageStream)
+/// are `io::Error`s with a `WsErr` inside, so you can get the details with:
+///
+/// ```
+/// # use ws_stream_tungstenite::WsErr;
+/// # fn handle( err: std::io::Error ) {
+/// if let Some( WsErr::MessageTooLarge{ size, max_size } ) = err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() )
+/// {
+///    println!( "message of {size} bytes is bigger than the maximum of {max_size}" );
+/// }
+/// # }
+/// ```
+///
+/// The sources are wrapped in an `Arc` so the error can be cloned.
 //
-#[ derive( Debug )            ]
+#[ derive( Debug, Clone )     ]
 #[ non_exhaustive                    ]
 #[ allow( variant_size_differences ) ]
 //
@@ -15,7 +30,7 @@ pub enum WsErr
 	{
 		/// The underlying error.
 		//
-		source: tungstenite::Error
+		source: Arc<tungstenite::Error>
 	},
 
 	/// An error from the underlying connection.
@@ -24,11 +39,10 @@ pub enum WsErr
 	{
 		/// The underlying error.
 		//
-		source: io::Error
+		source: Arc<io::Error>
 	},
 
-	/// A websocket protocol error. On read it means the remote didn't respect the websocket protocol.
-	/// On write this means there's a bug in ws_stream_tungstenite and it will panic.
+	/// A websocket protocol error. It means the remote didn't respect the websocket protocol.
 	//
 	Protocol,
 
@@ -41,6 +55,110 @@ pub enum WsErr
 	/// `poll_read` will just return `None`.
 	//
 	Closed,
+
+	/// Trying to write to a connection that the remote closed with a close code. Only happens on writing.
+	//
+	RemoteClosed
+	{
+		/// The close code sent by the remote.
+		//
+		code: CloseCode,
+
+		/// The reason sent by the remote.
+		//
+		reason: String,
+	},
+
+	/// A message is bigger than the maximum size. On writing, this is the tungstenite `max_write_buffer_size`
+	/// or `max_message_size`. On reading, the remote sent a message bigger than `max_message_size`.
+	//
+	MessageTooLarge
+	{
+		/// The size of the message.
+		//
+		size: usize,
+
+		/// The maximum size.
+		//
+		max_size: usize,
+	},
+
+	/// The websocket handshake did not complete in time.
+	//
+	HandshakeTimeout,
+
+	/// The server did not select one of the subprotocols we offered when connecting, or selected one although
+	/// we offered none.
+	//
+	Subprotocol
+	{
+		/// What was wrong with the response of the server.
+		//
+		source: SubProtocolError
+	},
+
+	/// The peer of a `ResumableWsStream` does not have the session anymore, or lost
+	/// data we no longer have, so the stream can not continue.
+	//
+	SessionLost,
+
+	/// A `Substream` was reset, by us or by the remote.
+	//
+	Reset,
+
+	/// The connection of a `Substream` ended before the remote closed the substream.
+	//
+	Aborted,
+
+	/// The close code passed to `close_with` is not allowed to be sent over the wire.
+	/// See [`CloseCode::is_allowed`](tungstenite::protocol::frame::coding::CloseCode::is_allowed).
+	//
+	InvalidCloseCode,
+
+	/// The reason passed to `close_with` is longer than the 123 bytes that fit in a close frame.
+	//
+	CloseReasonTooLong,
+
+	/// An error from pharos when observing events, eg. a bounded channel with a capacity of zero.
+	//
+	Pharos
+	{
+		/// The underlying error.
+		//
+		source: Arc<PharErr>
+	},
+
+	/// Too many pings are waiting for a pong, see [`WsStream::ping`](crate::WsStream::ping).
+	//
+	TooManyPings,
+
+	/// The [`WsStreamConfig`](crate::WsStreamConfig) is not valid.
+	//
+	InvalidConfig
+	{
+		/// What is wrong with the config.
+		//
+		reason: &'static str
+	},
+}
+
+
+
+#[ cfg( any( feature = "mux", feature = "resume" ) ) ]
+//
+impl WsErr
+{
+	// Get the WsErr back out of an io::Error returned by WsStream or WsMessageStream, so it can be stored and
+	// cloned. Other io errors are wrapped.
+	//
+	pub(crate) fn from_io( err: io::Error ) -> WsErr
+	{
+		match err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() )
+		{
+			Some( inner ) => inner.clone()      ,
+			None          => WsErr::from( err ) ,
+		}
+	}
 }
 
 
@@ -51,12 +169,24 @@ impl std::error::Error for WsErr
 	{
 		match &self
 		{
-			WsErr::Tungstenite{ ref source } => Some(source),
-			WsErr::Io         { ref source } => Some(source),
-
-			WsErr::Protocol     |
-			WsErr::ReceivedText |
-			WsErr::Closed       => None
+			WsErr::Tungstenite{ ref source } => Some( &**source ),
+			WsErr::Io         { ref source } => Some( &**source ),
+			WsErr::Pharos     { ref source } => Some( &**source ),
+			WsErr::Subprotocol{ ref source } => Some(    source ),
+
+			WsErr::Protocol           |
+			WsErr::ReceivedText       |
+			WsErr::Closed             |
+			WsErr::RemoteClosed{..}   |
+			WsErr::MessageTooLarge{..}|
+			WsErr::HandshakeTimeout   |
+			WsErr::SessionLost        |
+			WsErr::Reset              |
+			WsErr::Aborted            |
+			WsErr::InvalidCloseCode   |
+			WsErr::CloseReasonTooLong |
+			WsErr::TooManyPings       |
+			WsErr::InvalidConfig{..}  => None
 		}
 	}
 }
@@ -77,6 +207,10 @@ impl fmt::Display for WsErr
 
 				write!( f, "An io error happened: {}", source ),
 
+			WsErr::Pharos{ source } =>
+
+				write!( f, "An error happened observing events: {}", source ),
+
 			WsErr::Protocol =>
 
 				write!( f, "The remote committed a websocket protocol violation." ),
@@ -88,6 +222,50 @@ impl fmt::Display for WsErr
 			WsErr::Closed =>
 
 				write!( f, "The connection is already closed." ),
+
+			WsErr::RemoteClosed{ code, reason } =>
+
+				write!( f, "The remote closed the connection with code {}: {}", code, reason ),
+
+			WsErr::MessageTooLarge{ size, max_size } =>
+
+				write!( f, "The message of {} bytes is bigger than the maximum of {} bytes.", size, max_size ),
+
+			WsErr::HandshakeTimeout =>
+
+				write!( f, "The websocket handshake did not complete in time." ),
+
+			WsErr::Subprotocol{ source } =>
+
+				write!( f, "The server did not select one of the subprotocols we offered: {}", source ),
+
+			WsErr::SessionLost =>
+
+				write!( f, "The peer does not have our session anymore, so it can not be resumed." ),
+
+			WsErr::Reset =>
+
+				write!( f, "The substream was reset." ),
+
+			WsErr::Aborted =>
+
+				write!( f, "The connection ended before the remote closed the substream." ),
+
+			WsErr::InvalidCloseCode =>
+
+				write!( f, "This close code is not allowed to be sent over the wire." ),
+
+			WsErr::CloseReasonTooLong =>
+
+				write!( f, "The close reason is longer than the 123 bytes that fit in a close frame." ),
+
+			WsErr::TooManyPings =>
+
+				write!( f, "Too many pings are waiting for a pong." ),
+
+			WsErr::InvalidConfig{ reason } =>
+
+				write!( f, "Invalid configuration: {}", reason ),
 		}
 	}
 }
@@ -100,8 +278,17 @@ impl From< TungErr > for WsErr
 	{
 		match inner
 		{
-			TungErr::Protocol(_) => WsErr::Protocol              ,
-			source               => WsErr::Tungstenite{ source } ,
+			TungErr::Protocol( ProtocolError::SecWebSocketSubProtocolError( source ) ) =>
+
+				WsErr::Subprotocol{ source },
+
+			TungErr::Protocol(_) => WsErr::Protocol,
+
+			TungErr::Capacity( CapacityError::MessageTooLong{ size, max_size } ) =>
+
+				WsErr::MessageTooLarge{ size, max_size },
+
+			source => WsErr::Tungstenite{ source: Arc::new( source ) },
 		}
 	}
 }
@@ -112,7 +299,7 @@ impl From< io::Error > for WsErr
 {
 	fn from( source: io::Error ) -> WsErr
 	{
-		WsErr::Io { source }
+		WsErr::Io { source: Arc::new( source ) }
 	}
 }
 
@@ -124,9 +311,61 @@ impl From< PharErr > for WsErr
 	{
 		match source.kind()
 		{
-			pharos::ErrorKind::Closed => WsErr::Closed,
-			_                         => unreachable!() ,
+			pharos::ErrorKind::Closed => WsErr::Closed                                ,
+			_                         => WsErr::Pharos{ source: Arc::new( source ) } ,
 		}
 	}
 }
 
+
+
+/// Wrap the `WsErr` in an `io::Error` with a matching [`io::ErrorKind`]. This is how errors are returned
+/// from `AsyncRead`/`AsyncWrite`.
+//
+impl From< WsErr > for io::Error
+{
+	fn from( err: WsErr ) -> io::Error
+	{
+		let kind = match &err
+		{
+			WsErr::Io{ source } => source.kind(),
+
+			WsErr::Tungstenite{ source } => match &**source
+			{
+				TungErr::Io( e )            => e.kind()                   ,
+				TungErr::ConnectionClosed   |
+				TungErr::AlreadyClosed      => io::ErrorKind::NotConnected ,
+				TungErr::Capacity(_)        |
+				TungErr::WriteBufferFull(_) |
+				TungErr::Utf8               => io::ErrorKind::InvalidData  ,
+
+				// A protocol error that is not the fault of the remote, so we sent something invalid.
+				//
+				TungErr::Protocol(_)        |
+				TungErr::Url(_)             => io::ErrorKind::InvalidInput ,
+				_                           => io::ErrorKind::Other        ,
+			},
+
+			WsErr::Closed             |
+			WsErr::RemoteClosed{..}   => io::ErrorKind::NotConnected      ,
+
+			WsErr::Protocol           |
+			WsErr::ReceivedText       |
+			WsErr::Subprotocol{..}    |
+			WsErr::MessageTooLarge{..}=> io::ErrorKind::InvalidData       ,
+
+			WsErr::InvalidCloseCode   |
+			WsErr::CloseReasonTooLong |
+			WsErr::InvalidConfig{..}  => io::ErrorKind::InvalidInput      ,
+
+			WsErr::HandshakeTimeout   => io::ErrorKind::TimedOut          ,
+			WsErr::SessionLost        |
+			WsErr::Aborted            => io::ErrorKind::ConnectionAborted ,
+			WsErr::Reset              => io::ErrorKind::ConnectionReset   ,
+			WsErr::TooManyPings       |
+			WsErr::Pharos{..}         => io::ErrorKind::Other             ,
+		};
+
+		io::Error::new( kind, err )
+	}
+}
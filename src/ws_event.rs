@@ -1,11 +1,14 @@
-use crate::{ import::*, WsErr };
+use crate::{ import::*, WsErr, WsStats };
 
 
 /// Events that can happen on the websocket. These are returned through the stream you can obtain
 /// from `WsStream::observe`. These include close, ping and pong events which can not be returned
 /// through AsyncRead/AsyncWrite, and non-fatal errors.
+///
+/// New events can be added without a breaking change, so matches need a wildcard arm.
 //
 #[ derive( Debug, Clone ) ]
+#[ non_exhaustive       ]
 //
 pub enum WsEvent
 {
@@ -22,20 +25,45 @@ pub enum WsEvent
 	//
 	CloseFrame( Option< CloseFrame<'static> > ),
 
+	/// The remote sent a Text message and the [`TextPolicy`](crate::TextPolicy) is set to `Event`.
+	//
+	Text(String),
+
 	/// The remote sent a Ping message. It will automatically be answered as long as you keep polling the
 	/// `AsyncRead`. This is returned as an event in case you want to analyze the payload, since only bytes
 	/// from Binary websocket messages are passed through the `AsyncRead`.
 	//
 	Ping(Vec<u8>),
 
-	/// The remote send us a Pong. Since we never send Pings, this is a unidirectional heartbeat.
+	/// The remote send us a Pong. Pongs that answer our own pings (from keepalive or
+	/// [`WsStream::ping`](crate::WsStream::ping)) are reported as [`WsEvent::Rtt`] instead,
+	/// so this is a unidirectional heartbeat from the remote.
 	//
 	Pong(Vec<u8>),
 
+	/// A pong answered one of our pings, sent either for keepalive or with
+	/// [`WsStream::ping`](crate::WsStream::ping). Contains the measured round trip time.
+	//
+	Rtt(Duration),
+
+	/// The remote did not answer a keepalive ping in time. The connection will be closed with
+	/// [`CloseCode::Away`](tungstenite::protocol::frame::coding::CloseCode::Away).
+	/// See [`WsStreamConfig::keepalive`](crate::WsStreamConfig::keepalive).
+	//
+	PongTimeout,
+
+	/// The close handshake did not finish within the time set with
+	/// [`WsStreamConfig::close_timeout`](crate::WsStreamConfig::close_timeout). The `AsyncRead` side
+	/// returns EOF after this, so you can drop the connection.
+	//
+	CloseTimeout,
+
 	/// The connection is closed. Polling `WsStream` will return `None` on read and `io::ErrorKind::NotConnected`
 	/// on write soon. It's provided here for convenience so the task listening to these events know that
 	/// the connection closed.
 	/// You should not see any events after this one, so you can drop the Events stream.
+	///
+	/// Contains the final [`WsStats`] of the connection.
 	//
-	Closed,
+	Closed( WsStats ),
 }
@@ -0,0 +1,198 @@
+use crate::{ import::*, tung_websocket::TungWebSocket, WsEvent, WsErr, WsStreamConfig, WsStats, TextPolicy, WsBackend };
+
+#[ cfg( feature = "handshake" ) ] use crate::HandshakeInfo;
+
+
+/// Takes a [`WebSocketStream`](async_tungstenite::WebSocketStream) and implements `Stream`/`Sink` of whole
+/// binary messages. This is the message level sibling of [`WsStream`](crate::WsStream), with the same close
+/// handling and out of band events, but without turning the messages into a byte stream.
+///
+/// Every `Vec<u8>` sent into the sink becomes one Binary message, regardless of
+/// [`WsStreamConfig::max_write_size`] and [`WsStreamConfig::write_mode`], which only apply to `WsStream`.
+/// Messages bigger than the tungstenite `max_write_buffer_size` or `max_message_size` are refused with
+/// [`io::ErrorKind::InvalidData`].
+///
+/// Keep polling the stream until it returns `None` to drive the close handshake to completion, also after
+/// closing the sink.
+///
+/// ## Errors
+///
+/// Errors are the same as for `WsStream`. Only fatal io errors are returned from the stream, others are
+/// reported through [`Observable`].
+//
+pub struct WsMessageStream<B> where B: WsBackend
+{
+	inner: TungWebSocket<B>,
+}
+
+
+
+impl<B> WsMessageStream<B> where B: WsBackend
+{
+	/// Create a new WsMessageStream with the default [`WsStreamConfig`].
+	//
+	pub fn new( inner: B ) -> Self
+	{
+		Self::from_config( inner, WsStreamConfig::default() )
+	}
+
+
+	/// Create a new WsMessageStream with the given options.
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::InvalidConfig`]: See [`WsStreamConfig::validate`].
+	//
+	pub fn with_config( inner: B, config: WsStreamConfig ) -> Result<Self, WsErr>
+	{
+		config.validate( inner.config() )?;
+
+		Ok( Self::from_config( inner, config ) )
+	}
+
+
+	// The config must be valid. Messages a backend hands on in fragments are put back together.
+	//
+	fn from_config( inner: B, config: WsStreamConfig ) -> Self
+	{
+		let mut inner = TungWebSocket::new( inner, config );
+
+		inner.whole_messages();
+
+		Self { inner }
+	}
+
+
+	/// Choose what happens when the remote sends a websocket text message. See [`WsStream::set_text_policy`](crate::WsStream::set_text_policy).
+	//
+	pub fn set_text_policy( &mut self, policy: TextPolicy )
+	{
+		self.inner.set_text_policy( policy );
+	}
+
+
+	/// Start the close handshake with a close code and reason of your choosing. The close frame is sent out
+	/// when the sink is closed, or while polling the stream. See [`WsStream::close_with`](crate::WsStream::close_with).
+	///
+	/// ## Errors
+	///
+	/// Same as [`WsStream::close_with`](crate::WsStream::close_with).
+	//
+	pub fn close_with( &mut self, code: CloseCode, reason: impl Into< Cow<'static, str> > ) -> Result<(), WsErr>
+	{
+		self.inner.close_with( CloseFrame{ code, reason: reason.into() } )
+	}
+
+
+	/// Send a ping to measure the round trip time of the connection. See [`WsStream::ping`](crate::WsStream::ping).
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::Closed`]: The connection is closing or closed before the pong came in.
+	//
+	pub fn ping( &mut self ) -> impl Future< Output = Result<Duration, WsErr> > + Send + 'static
+	{
+		let rx = self.inner.ping();
+
+		async move
+		{
+			rx?.await.map_err( |_| WsErr::Closed )
+		}
+	}
+
+
+	/// What we know about the http upgrade of this connection. See [`WsStream::handshake_info`](crate::WsStream::handshake_info).
+	//
+	#[ cfg( feature = "handshake" ) ]
+	//
+	#[ cfg_attr( nightly, doc(cfg( feature = "handshake" )) ) ]
+	//
+	pub fn handshake_info( &self ) -> Option<&HandshakeInfo>
+	{
+		self.inner.handshake_info()
+	}
+
+
+	/// A snapshot of the traffic statistics of this connection. See [`WsStream::stats`](crate::WsStream::stats).
+	//
+	pub fn stats( &self ) -> WsStats
+	{
+		self.inner.stats().clone()
+	}
+
+
+	/// Recover the underlying [`WsBackend`], along with a close
+	/// frame that was queued but not yet handed to tungstenite, and events that weren't delivered yet.
+	//
+	pub fn into_inner( mut self ) -> ( B, Option< CloseFrame<'static> >, Vec<WsEvent> )
+	{
+		self.inner.take_parts()
+	}
+}
+
+
+
+impl<B> fmt::Debug for WsMessageStream<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "WsMessageStream over Tungstenite" )
+	}
+}
+
+
+
+impl<B> Stream for WsMessageStream<B> where B: WsBackend
+{
+	type Item = io::Result< Vec<u8> >;
+
+	/// Get the next binary message. When `None` is returned, it is safe to drop the connection.
+	//
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		Pin::new( &mut self.inner ).poll_next( cx )
+	}
+}
+
+
+
+impl<B> Sink< Vec<u8> > for WsMessageStream<B> where B: WsBackend
+{
+	type Error = io::Error;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		Pin::new( &mut self.inner ).poll_ready( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, item: Vec<u8> ) -> io::Result<()>
+	{
+		Pin::new( &mut self.inner ).start_send( item )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		Pin::new( &mut self.inner ).poll_flush( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		Pin::new( &mut self.inner ).poll_close( cx )
+	}
+}
+
+
+
+impl<B> Observable< WsEvent > for WsMessageStream<B> where B: WsBackend
+{
+	type Error = WsErr;
+
+	fn observe( &mut self, options: ObserveConfig< WsEvent > ) -> Observe< '_, WsEvent, Self::Error >
+	{
+		self.inner.observe( options )
+	}
+}
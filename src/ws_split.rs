@@ -0,0 +1,273 @@
+use crate::{ import::*, WsStream, WsEvent, WsErr, WsBackend };
+
+
+// Both halves share the WsStream. The lock is only ever held for the duration of a single poll call
+// or to queue a close frame, never across an await point, so a std mutex is fine here.
+//
+type Shared<B> = Arc< Mutex< WsStream<B> > >;
+
+
+// Lock the shared stream. A panic while holding the lock can only come from a poll method, after which
+// the stream is in the same state as when a panic happens on an unsplit WsStream, so ignore poisoning.
+//
+fn lock<B>( shared: &Shared<B> ) -> MutexGuard<'_, WsStream<B>>
+
+	where B: WsBackend
+{
+	shared.lock().unwrap_or_else( PoisonError::into_inner )
+}
+
+
+// Pharos registers the observer without waiting on anything, so the future always resolves on the
+// first poll. That lets us observe without holding the lock across an await point.
+//
+fn observe<B>( shared: &Shared<B>, options: ObserveConfig< WsEvent > ) -> Result< Events<WsEvent>, WsErr >
+
+	where B: WsBackend
+{
+	lock( shared ).observe( options ).now_or_never().unwrap_or( Err( WsErr::Closed ) )
+}
+
+
+
+/// The reading half of a [`WsStream`], created by [`WsStream::into_split`]. It implements `AsyncRead` and
+/// is observable, so the task that reads can also watch for events.
+///
+/// Keep reading until it returns EOF, even after the [`WsWriter`] was closed, to drive the close handshake
+/// to completion.
+//
+pub struct WsReader<B> where B: WsBackend
+{
+	shared: Shared<B>,
+}
+
+
+
+/// The writing half of a [`WsStream`], created by [`WsStream::into_split`]. It implements `AsyncWrite` and
+/// is observable. Use [`WsWriter::close_with`] to tell the remote why you are closing.
+//
+pub struct WsWriter<B> where B: WsBackend
+{
+	shared: Shared<B>,
+}
+
+
+
+impl<B> WsStream<B> where B: WsBackend
+{
+	/// Split the stream in a reading and a writing half that can be moved to different tasks. Unlike
+	/// `futures::io::split`, both halves can be observed and the writer can close the connection with a
+	/// close code. Use [`WsReader::reunite`] to get the `WsStream` back.
+	//
+	pub fn into_split( self ) -> ( WsReader<B>, WsWriter<B> )
+	{
+		let shared = Arc::new( Mutex::new( self ) );
+
+		( WsReader{ shared: shared.clone() }, WsWriter{ shared } )
+	}
+}
+
+
+
+impl<B> WsReader<B> where B: WsBackend
+{
+	/// Whether this reader and the writer came from the same [`WsStream`].
+	//
+	pub fn is_pair_of( &self, writer: &WsWriter<B> ) -> bool
+	{
+		Arc::ptr_eq( &self.shared, &writer.shared )
+	}
+
+
+	/// Put the two halves back together.
+	///
+	/// ## Errors
+	///
+	/// If the halves didn't come from the same [`WsStream`], they are returned in a [`ReuniteError`].
+	//
+	pub fn reunite( self, writer: WsWriter<B> ) -> Result< WsStream<B>, ReuniteError<B> >
+	{
+		if !self.is_pair_of( &writer )
+		{
+			return Err( ReuniteError( self, writer ) );
+		}
+
+		drop( writer );
+
+		let mutex = Arc::try_unwrap( self.shared ).expect( "WsReader and WsWriter to be the only owners" );
+
+		Ok( mutex.into_inner().unwrap_or_else( PoisonError::into_inner ) )
+	}
+}
+
+
+
+impl<B> WsWriter<B> where B: WsBackend
+{
+	/// Start the close handshake with a close code and reason of your choosing. See [`WsStream::close_with`].
+	/// The close frame is sent out when you close the writer, or while the [`WsReader`] is reading.
+	///
+	/// ## Errors
+	///
+	/// See [`WsStream::close_with`].
+	//
+	pub fn close_with( &mut self, code: CloseCode, reason: impl Into< Cow<'static, str> > ) -> Result<(), WsErr>
+	{
+		lock( &self.shared ).close_with( code, reason )
+	}
+}
+
+
+
+impl<B> fmt::Debug for WsReader<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "WsReader over Tungstenite" )
+	}
+}
+
+
+
+impl<B> fmt::Debug for WsWriter<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "WsWriter over Tungstenite" )
+	}
+}
+
+
+
+impl<B> AsyncRead for WsReader<B> where B: WsBackend
+{
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		AsyncRead::poll_read( Pin::new( &mut *lock( &self.shared ) ), cx, buf )
+	}
+
+	fn poll_read_vectored( self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &mut [IoSliceMut<'_>] ) -> Poll< io::Result<usize> >
+	{
+		AsyncRead::poll_read_vectored( Pin::new( &mut *lock( &self.shared ) ), cx, bufs )
+	}
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<B> TokAsyncRead for WsReader<B> where B: WsBackend
+{
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_> ) -> Poll< io::Result<()> >
+	{
+		TokAsyncRead::poll_read( Pin::new( &mut *lock( &self.shared ) ), cx, buf )
+	}
+}
+
+
+
+impl<B> AsyncWrite for WsWriter<B> where B: WsBackend
+{
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		AsyncWrite::poll_write( Pin::new( &mut *lock( &self.shared ) ), cx, buf )
+	}
+
+
+	fn poll_write_vectored( self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[ IoSlice<'_> ] ) -> Poll< io::Result<usize> >
+	{
+		AsyncWrite::poll_write_vectored( Pin::new( &mut *lock( &self.shared ) ), cx, bufs )
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		AsyncWrite::poll_flush( Pin::new( &mut *lock( &self.shared ) ), cx )
+	}
+
+
+	fn poll_close( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		AsyncWrite::poll_close( Pin::new( &mut *lock( &self.shared ) ), cx )
+	}
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
+//
+impl<B> TokAsyncWrite for WsWriter<B> where B: WsBackend
+{
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		TokAsyncWrite::poll_write( Pin::new( &mut *lock( &self.shared ) ), cx, buf )
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		TokAsyncWrite::poll_flush( Pin::new( &mut *lock( &self.shared ) ), cx )
+	}
+
+
+	fn poll_shutdown( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		TokAsyncWrite::poll_shutdown( Pin::new( &mut *lock( &self.shared ) ), cx )
+	}
+}
+
+
+
+impl<B> Observable< WsEvent > for WsReader<B> where B: WsBackend
+{
+	type Error = WsErr;
+
+	fn observe( &mut self, options: ObserveConfig< WsEvent > ) -> Observe< '_, WsEvent, Self::Error >
+	{
+		future::ready( observe( &self.shared, options ) ).boxed()
+	}
+}
+
+
+
+impl<B> Observable< WsEvent > for WsWriter<B> where B: WsBackend
+{
+	type Error = WsErr;
+
+	fn observe( &mut self, options: ObserveConfig< WsEvent > ) -> Observe< '_, WsEvent, Self::Error >
+	{
+		future::ready( observe( &self.shared, options ) ).boxed()
+	}
+}
+
+
+
+/// Error returned by [`WsReader::reunite`] when the halves don't belong to the same [`WsStream`]. It
+/// gives back both halves.
+//
+pub struct ReuniteError<B>( pub WsReader<B>, pub WsWriter<B> ) where B: WsBackend;
+
+
+impl<B> fmt::Debug for ReuniteError<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_tuple( "ReuniteError" ).finish_non_exhaustive()
+	}
+}
+
+
+impl<B> fmt::Display for ReuniteError<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "tried to reunite a WsReader and WsWriter that don't belong to the same WsStream" )
+	}
+}
+
+
+impl<B> std::error::Error for ReuniteError<B> where B: WsBackend {}
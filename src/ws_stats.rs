@@ -0,0 +1,87 @@
+use crate::{ import::* };
+
+
+/// Traffic statistics of a connection. Get a snapshot with [`WsStream::stats`](crate::WsStream::stats). The
+/// final snapshot is also delivered in [`WsEvent::Closed`](crate::WsEvent::Closed).
+///
+/// Bytes are counted as the payload of data messages, not including websocket framing.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+#[ non_exhaustive ]
+//
+pub struct WsStats
+{
+	/// Payload bytes of the data messages received.
+	//
+	pub bytes_received: u64,
+
+	/// Payload bytes of the data messages sent.
+	//
+	pub bytes_sent: u64,
+
+	/// Data messages received, both binary and text, regardless of the [`TextPolicy`](crate::TextPolicy).
+	//
+	pub messages_received: u64,
+
+	/// Data messages sent. A fragmented message counts as one.
+	//
+	pub messages_sent: u64,
+
+	/// Pings we sent, for keepalive or with [`WsStream::ping`](crate::WsStream::ping).
+	//
+	pub pings_sent: u64,
+
+	/// Pings received from the remote. Tungstenite answers these automatically.
+	//
+	pub pings_received: u64,
+
+	/// Pongs received from the remote, both answers to our pings and unsolicited ones.
+	//
+	pub pongs_received: u64,
+
+	/// Close frames we sent to start the close handshake. The reply tungstenite sends when the remote
+	/// starts the close handshake is not counted.
+	//
+	pub close_frames_sent: u64,
+
+	/// Close frames received from the remote.
+	//
+	pub close_frames_received: u64,
+
+	/// Errors reported as [`WsEvent::Error`](crate::WsEvent::Error).
+	//
+	pub errors: u64,
+
+	/// When the [`WsStream`](crate::WsStream) was created.
+	//
+	pub opened_at: SystemTime,
+
+	/// When the connection was closed, if it is.
+	//
+	pub closed_at: Option<SystemTime>,
+}
+
+
+
+impl WsStats
+{
+	pub(crate) fn new() -> Self
+	{
+		Self
+		{
+			bytes_received       : 0                 ,
+			bytes_sent           : 0                 ,
+			messages_received    : 0                 ,
+			messages_sent        : 0                 ,
+			pings_sent           : 0                 ,
+			pings_received       : 0                 ,
+			pongs_received       : 0                 ,
+			close_frames_sent    : 0                 ,
+			close_frames_received: 0                 ,
+			errors               : 0                 ,
+			opened_at            : SystemTime::now() ,
+			closed_at            : None              ,
+		}
+	}
+}
@@ -1,7 +1,10 @@
-use crate::{ import::*, tung_websocket::TungWebSocket, WsEvent, WsErr };
+use crate::{ import::*, tung_websocket::TungWebSocket, WsEvent, WsErr, WsStreamConfig, WsStats, TextPolicy, WriteMode, WsBackend };
+
+#[ cfg( feature = "handshake" ) ] use crate::HandshakeInfo;
 
 
 /// Takes a [`WebSocketStream`](async_tungstenite::WebSocketStream) and implements futures 0.3 `AsyncRead`/`AsyncWrite`/`AsyncBufRead`.
+/// Other websocket implementations can be used by implementing [`WsBackend`] for them.
 ///
 /// Will always create an entire Websocket message from every write. Tungstenite buffers messages up to
 /// `write_buffer_size` in their [`tungstenite::protocol::WebSocketConfig`]. If you want small messages to be sent out,
@@ -9,10 +12,13 @@ use crate::{ import::*, tung_websocket::TungWebSocket, WsEvent, WsErr };
 ///
 /// On the other hand the `max_write_buffer_size` from tokio is the maximum size we can send in one go, otherwise
 /// _tungstenite_ returns an error. Our [`AsyncWrite`] implementation never sends data that exceeds this buffer or
-/// `max_message_size`.
+/// `max_message_size`. You can lower that limit further with [`WsStreamConfig::max_write_size`].
 ///
 /// However you still must respect the `max_message_size` of the receiving end.
 ///
+/// With [`WriteMode::FlushDelimited`], writes are buffered instead and sent out as one message when you flush.
+/// With [`WsStreamConfig::fragment_size`], big messages are sent as several frames instead of several messages.
+///
 /// ## Errors
 ///
 /// Errors returned directly are generally io errors from the underlying stream. Only fatal errors are returned in
@@ -28,41 +34,428 @@ use crate::{ import::*, tung_websocket::TungWebSocket, WsEvent, WsErr };
 ///   send a text message, which is not supported, so the connection will be gracefully closed. You can just keep calling
 ///   `poll_read` until `None` is returned.
 /// - [`WsErr::ReceivedText`]: This means the remote send a text message, which is not supported, so the connection will
-///   be gracefully closed. You can just keep calling `poll_read` until `None` is returned.
+///   be gracefully closed. You can just keep calling `poll_read` until `None` is returned. This only happens with the
+///   default [`TextPolicy::Reject`], see [`WsStream::set_text_policy`] for alternatives.
+/// - Other tungstenite errors are not expected on an established connection, but if tungstenite returns them anyway,
+///   they are reported as [`WsErr::Tungstenite`] and the stream ends.
 ///
 /// On writing, eg. `AsyncWrite::*` all errors are fatal.
 ///
 /// When a Protocol error is encountered during writing, it indicates that either _ws_stream_tungstenite_ or _tungstenite_ have
-/// a bug so it will panic.
+/// a bug. It is returned as [`io::ErrorKind::InvalidInput`], unless it means the connection is closing, which is returned as
+/// [`io::ErrorKind::NotConnected`].
 //
-pub struct WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+pub struct WsStream<B> where B: WsBackend
 {
-	inner: IoStream< TungWebSocket<S>, Vec<u8> >,
+	inner: IoStream< TungWebSocket<B>, Vec<u8> >,
 	buffer_size: usize,
+	write_mode : WriteMode,
+
+	// Data buffered in WriteMode::FlushDelimited, never bigger than buffer_size.
+	//
+	write_buf: Vec<u8>,
+
+	// When fragmenting, the message we are sending and how many bytes of it have been handed to tungstenite.
+	//
+	fragment_size: Option<usize>,
+	fragmenting  : Option<( Vec<u8>, usize )>,
 }
 
 
 
-impl<S> WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> WsStream<B> where B: WsBackend
 {
-	/// Create a new WsStream.
+	/// Create a new WsStream with the default [`WsStreamConfig`].
 	//
-	pub fn new( inner: ATungSocket<S> ) -> Self
+	pub fn new( inner: B ) -> Self
 	{
-		let c           = inner.get_config();
-		let buffer_size = std::cmp::min( c.max_write_buffer_size, c.max_message_size.unwrap_or(usize::MAX) );
+		Self::from_config( inner, WsStreamConfig::default() )
+	}
+
+
+	/// Create a new WsStream with the given options.
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::InvalidConfig`]: See [`WsStreamConfig::validate`].
+	//
+	pub fn with_config( inner: B, config: WsStreamConfig ) -> Result<Self, WsErr>
+	{
+		config.validate( inner.config() )?;
+
+		Ok( Self::from_config( inner, config ) )
+	}
+
+
+	// The config must be valid.
+	//
+	fn from_config( inner: B, config: WsStreamConfig ) -> Self
+	{
+		let buffer_size = config.buffer_size( inner.config() );
 
 		Self
 		{
 			buffer_size,
-			inner      : IoStream::new( TungWebSocket::new( inner ) ),
+			write_mode   : config.write_mode,
+			write_buf    : Vec::new(),
+			fragment_size: config.fragment_size,
+			fragmenting  : None,
+			inner        : IoStream::new( TungWebSocket::new( inner, config ) ),
 		}
 	}
+
+
+	/// Choose what happens when the remote sends a websocket text message. By default they are rejected
+	/// and the connection is closed. See [`TextPolicy`] for the options. This overrides the policy set
+	/// with [`WsStreamConfig::text_policy`].
+	//
+	pub fn set_text_policy( &mut self, policy: TextPolicy )
+	{
+		self.inner.inner_mut().set_text_policy( policy );
+	}
+
+
+	/// Start the close handshake with a close code and reason of your choosing. The close frame is queued
+	/// and will be sent out on the next call to `poll_close` (eg. `AsyncWriteExt::close`), or while reading.
+	/// From this point on, writing will return [`io::ErrorKind::NotConnected`]. Keep reading until `None`
+	/// is returned to drive the close handshake to completion.
+	///
+	/// If you need to close from a separate writing task, split the stream with [`WsStream::into_split`]
+	/// and use [`WsWriter::close_with`](crate::WsWriter::close_with).
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::InvalidCloseCode`]: The code can not be sent over the wire, eg. [`CloseCode::Status`].
+	/// - [`WsErr::CloseReasonTooLong`]: The reason does not fit in a close frame (max 123 bytes).
+	/// - [`WsErr::Closed`]: We are already closing the connection, or it is closed.
+	//
+	pub fn close_with( &mut self, code: CloseCode, reason: impl Into< Cow<'static, str> > ) -> Result<(), WsErr>
+	{
+		self.inner.inner_mut().close_with( CloseFrame{ code, reason: reason.into() } )
+	}
+
+
+	/// Send a ping to measure the round trip time of the connection. The returned future resolves with
+	/// the time between sending the ping and receiving the matching pong. The measured time is also
+	/// reported to observers as [`WsEvent::Rtt`].
+	///
+	/// The ping is sent out on the next read or write, and the pong is only processed while reading, so
+	/// you need to keep polling `AsyncRead` for the future to resolve. The future doesn't borrow the
+	/// `WsStream`, so it can be awaited concurrently with reading.
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::Closed`]: The connection is closing or closed before the pong came in.
+	/// - [`WsErr::TooManyPings`]: 16 pings are already waiting for a pong. A pong answers the ping it matches
+	///   and all pings sent before it. Pings whose future was dropped and keepalive pings don't count.
+	//
+	pub fn ping( &mut self ) -> impl Future< Output = Result<Duration, WsErr> > + Send + 'static
+	{
+		let rx = self.inner.inner_mut().ping();
+
+		async move
+		{
+			rx?.await.map_err( |_| WsErr::Closed )
+		}
+	}
+
+
+	/// What we know about the http upgrade of this connection: the request uri, headers, subprotocol and
+	/// addresses. This is set by `WsListener` and `WsConnector`, or
+	/// with [`WsStreamConfig::handshake_info`].
+	//
+	#[ cfg( feature = "handshake" ) ]
+	//
+	#[ cfg_attr( nightly, doc(cfg( feature = "handshake" )) ) ]
+	//
+	pub fn handshake_info( &self ) -> Option<&HandshakeInfo>
+	{
+		self.inner.inner().handshake_info()
+	}
+
+
+	/// A snapshot of the traffic statistics of this connection. Bytes are counted per websocket message, so
+	/// data buffered in the `WsStream` but not yet handed to tungstenite is not included in `bytes_sent`.
+	//
+	pub fn stats( &self ) -> WsStats
+	{
+		self.inner.inner().stats().clone()
+	}
+
+
+	/// Read the next binary message as a whole, preserving the message boundaries that `AsyncRead` loses.
+	/// Returns `None` when the connection is closed, like `AsyncRead` returning EOF.
+	///
+	/// If part of a message was already consumed through `AsyncRead` or `AsyncBufRead`, the remainder of that
+	/// message is returned first. Close frames, pings and errors are handled exactly like when reading through
+	/// `AsyncRead`, so you can mix both as you see fit.
+	///
+	/// Empty messages are returned as empty vectors, while `AsyncRead` skips them.
+	///
+	/// With a backend that hands on the fragments of a message as they arrive, like `FrameStream`, this
+	/// returns each fragment separately. Use a [`WsMessageStream`](crate::WsMessageStream) to get whole messages.
+	///
+	/// ## Errors
+	///
+	/// The same fatal io errors as `AsyncRead`.
+	//
+	pub fn poll_next_message( &mut self, cx: &mut Context<'_> ) -> Poll< Option< io::Result< Vec<u8> > > >
+	{
+		match self.take_buffered()
+		{
+			Ok ( buf ) if buf.is_empty() => {}
+			Ok ( buf ) => return Some( Ok (buf) ).into(),
+			Err( err ) => return Some( Err(err) ).into(),
+		}
+
+		// IoStream has nothing buffered, so we can bypass it.
+		//
+		Pin::new( self.inner.inner_mut() ).poll_next( cx )
+	}
+
+
+	/// Async version of [`WsStream::poll_next_message`]. Returns `Ok(None)` when the connection is closed.
+	///
+	/// ## Errors
+	///
+	/// See [`WsStream::poll_next_message`].
+	//
+	pub async fn read_message( &mut self ) -> io::Result< Option< Vec<u8> > >
+	{
+		future::poll_fn( |cx| self.poll_next_message( cx ) ).await.transpose()
+	}
+
+
+	/// Recover the underlying [`WsBackend`] so you can go back to
+	/// working with websocket messages on the same connection, eg. to send a text message or a custom close frame.
+	///
+	/// Bytes that were already read from the connection but not yet consumed through `AsyncRead` are returned
+	/// alongside it, so no data is lost. See [`WsStream::into_parts`] if you also need the pending close frame
+	/// and undelivered events.
+	//
+	pub fn into_inner( self ) -> ( B, Vec<u8> )
+	{
+		let parts = self.into_parts();
+
+		(parts.socket, parts.read_buf)
+	}
+
+
+	/// Decompose the WsStream into the underlying [`WsBackend`] and
+	/// all state that has not yet been processed. See [`WsStreamParts`] for a description of each part.
+	///
+	/// Observers will see their event stream end, as the pharos object is dropped. Events which haven't
+	/// been delivered yet are returned in [`WsStreamParts::events`].
+	//
+	pub fn into_parts( mut self ) -> WsStreamParts<B>
+	{
+		// A buffered error would have been reported out of band already and would be fatal for this
+		// connection anyway, so we don't try to preserve it.
+		//
+		let read_buf = self.take_buffered().unwrap_or_default();
+
+		let mut write_buf = match self.fragmenting.take()
+		{
+			Some(( data, sent )) => data[sent..].to_vec(),
+			None                 => Vec::new(),
+		};
+
+		write_buf.append( &mut self.write_buf );
+
+		let (socket, close_frame, events) = self.inner.inner_mut().take_parts();
+
+		WsStreamParts { socket, read_buf, write_buf, close_frame, events }
+	}
+
+
+	// Take out the bytes IoStream has read from the websocket but which haven't been consumed yet.
+	// The message stream is paused during the call so IoStream can't pull in a new message.
+	//
+	fn take_buffered( &mut self ) -> io::Result< Vec<u8> >
+	{
+		let     waker = noop_waker();
+		let mut cx    = Context::from_waker( &waker );
+
+		self.inner.inner_mut().pause( true );
+
+		let buffered = match Pin::new( &mut self.inner ).poll_fill_buf( &mut cx )
+		{
+			Poll::Ready( Ok (buf) ) => Ok( buf.to_vec() ),
+			Poll::Ready( Err(err) ) => Err( err )        ,
+			Poll::Pending           => Ok( Vec::new() )  ,
+		};
+
+		if let Ok( buf ) = &buffered
+		{
+			Pin::new( &mut self.inner ).consume( buf.len() );
+		}
+
+		self.inner.inner_mut().pause( false );
+
+		buffered
+	}
+
+
+	// Send out the data buffered in WriteMode::FlushDelimited as one message. Also finishes sending
+	// a fragmented message.
+	//
+	fn poll_send_buffered( &mut self, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		ready!( self.poll_send_fragments( cx ) )?;
+
+		if self.write_buf.is_empty()
+		{
+			return Ok(()).into();
+		}
+
+		if self.fragment_size.is_some()
+		{
+			self.fragmenting = Some(( std::mem::take( &mut self.write_buf ), 0 ));
+
+			return self.poll_send_fragments( cx );
+		}
+
+		ready!( AsyncWrite::poll_write( Pin::new( &mut self.inner ), cx, &self.write_buf ) )?;
+
+		self.write_buf.clear();
+
+		Ok(()).into()
+	}
+
+
+	// Buffer data in WriteMode::FlushDelimited. If the buffer is full, it's sent out first.
+	//
+	fn poll_write_buffered( &mut self, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		if self.write_buf.len() >= self.buffer_size
+		{
+			ready!( self.poll_send_buffered( cx ) )?;
+		}
+
+		let take = std::cmp::min( buf.len(), self.buffer_size - self.write_buf.len() );
+
+		self.write_buf.extend_from_slice( &buf[..take] );
+
+		Ok( take ).into()
+	}
+
+
+	// Take up to buffer_size bytes to send as one fragmented message. The previous message must
+	// be sent out entirely first.
+	//
+	fn poll_write_fragmented( &mut self, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		ready!( self.poll_send_fragments( cx ) )?;
+
+		if buf.is_empty()
+		{
+			return Ok(0).into();
+		}
+
+		let take = std::cmp::min( buf.len(), self.buffer_size );
+
+		self.fragmenting = Some(( buf[..take].to_vec(), 0 ));
+
+		// Start sending right away. What doesn't go out now is sent on the next write, flush or close.
+		//
+		if let Poll::Ready( Err(e) ) = self.poll_send_fragments( cx )
+		{
+			return Err(e).into();
+		}
+
+		Ok( take ).into()
+	}
+
+
+	// Hand the fragments of the message in progress to tungstenite one by one. Because we wait for
+	// poll_ready before every fragment, pings and close frames can be sent in between.
+	//
+	fn poll_send_fragments( &mut self, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		let Some( size ) = self.fragment_size else { return Ok(()).into() };
+
+		while let Some(( data, sent )) = &mut self.fragmenting
+		{
+			let tung = self.inner.inner_mut();
+
+			let res = ready!( Pin::new( &mut *tung ).poll_ready( cx ) ).and_then( |()|
+			{
+				let end = std::cmp::min( data.len(), *sent + size );
+				let res = tung.start_send_fragment( data[*sent..end].to_vec(), *sent == 0, end == data.len() );
+
+				*sent = end;
+				res
+			});
+
+			// On error, the sink is no longer usable, so drop the rest of the message.
+			//
+			if res.is_err() || *sent == data.len()
+			{
+				self.fragmenting = None;
+			}
+
+			res?;
+		}
+
+		Ok(()).into()
+	}
+}
+
+
+
+/// The parts of a [`WsStream`], returned by [`WsStream::into_parts`].
+//
+#[ non_exhaustive ]
+//
+pub struct WsStreamParts<B> where B: WsBackend
+{
+	/// The underlying websocket connection.
+	//
+	pub socket: B,
+
+	/// Data from binary messages that was received but not yet read through `AsyncRead`.
+	//
+	pub read_buf: Vec<u8>,
+
+	/// Data written with [`WriteMode::FlushDelimited`] that was not yet sent because the stream wasn't flushed.
+	///
+	/// With [`WsStreamConfig::fragment_size`], this starts with the rest of a message of which only part of the
+	/// fragments have been sent. If so, the remote is waiting for the rest as continuation frames.
+	//
+	pub write_buf: Vec<u8>,
+
+	/// A close frame that _ws_stream_tungstenite_ had queued, but which has not yet been passed to tungstenite.
+	/// If this is `Some`, you should still send it to complete the close handshake.
+	///
+	/// If we were already in the process of sending a close frame, it is in tungstenite's buffer and will go out
+	/// on the next flush of the socket.
+	//
+	pub close_frame: Option< CloseFrame<'static> >,
+
+	/// Events that have not yet been delivered to observers.
+	//
+	pub events: Vec<WsEvent>,
+}
+
+
+
+impl<B> fmt::Debug for WsStreamParts<B> where B: WsBackend
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "WsStreamParts" )
+
+			.field( "read_buf"   , &self.read_buf    )
+			.field( "write_buf"  , &self.write_buf   )
+			.field( "close_frame", &self.close_frame )
+			.field( "events"     , &self.events      )
+			.finish_non_exhaustive()
+	}
 }
 
 
 
-impl<S> fmt::Debug for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> fmt::Debug for WsStream<B> where B: WsBackend
 {
 	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
 	{
@@ -72,10 +465,20 @@ impl<S> fmt::Debug for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpi
 
 
 
-impl<S> AsyncWrite for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> AsyncWrite for WsStream<B> where B: WsBackend
 {
 	fn poll_write( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
 	{
+		if self.write_mode == WriteMode::FlushDelimited
+		{
+			return self.poll_write_buffered( cx, buf );
+		}
+
+		if self.fragment_size.is_some()
+		{
+			return self.poll_write_fragmented( cx, buf );
+		}
+
 		let buffer_size = std::cmp::min(self.buffer_size, buf.len());
 		AsyncWrite::poll_write( Pin::new( &mut self.inner ), cx, &buf[..buffer_size] )
 	}
@@ -83,6 +486,15 @@ impl<S> AsyncWrite for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpi
 
 	fn poll_write_vectored( mut self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[ IoSlice<'_> ] ) -> Poll< io::Result<usize> >
 	{
+		// We copy into our buffer anyway, so there is nothing to gain from taking several buffers at once.
+		//
+		if self.write_mode == WriteMode::FlushDelimited || self.fragment_size.is_some()
+		{
+			let buf = bufs.iter().find( |b| !b.is_empty() ).map_or( &[][..], |b| &**b );
+
+			return AsyncWrite::poll_write( self, cx, buf );
+		}
+
 		let mut take_size = 0;
 		let mut seen_size = 0;
 		let mut next = 1;
@@ -127,12 +539,16 @@ impl<S> AsyncWrite for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpi
 
 	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
 	{
+		ready!( self.poll_send_buffered( cx ) )?;
+
 		AsyncWrite::poll_flush( Pin::new( &mut self.inner ), cx )
 	}
 
 
 	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
 	{
+		ready!( self.poll_send_buffered( cx ) )?;
+
 		Pin::new( &mut self.inner ).poll_close( cx )
 	}
 }
@@ -143,33 +559,48 @@ impl<S> AsyncWrite for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpi
 //
 #[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
 //
-impl<S> TokAsyncWrite for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> TokAsyncWrite for WsStream<B> where B: WsBackend
 {
 	/// Will always flush the underlying socket. Will always create an entire Websocket message from every write,
-	/// so call with a sufficiently large buffer if you have performance problems. Don't call with a buffer larger
-	/// than the max message size accepted by the remote endpoint.
+	/// so call with a sufficiently large buffer if you have performance problems. Like the futures `AsyncWrite`,
+	/// it takes no more than fits in one message, see [`WsStreamConfig::max_write_size`].
 	//
 	fn poll_write( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
 	{
-		TokAsyncWrite::poll_write( Pin::new( &mut self.inner ), cx, buf )
+		if self.write_mode == WriteMode::FlushDelimited
+		{
+			return self.poll_write_buffered( cx, buf );
+		}
+
+		if self.fragment_size.is_some()
+		{
+			return self.poll_write_fragmented( cx, buf );
+		}
+
+		let buffer_size = std::cmp::min(self.buffer_size, buf.len());
+		TokAsyncWrite::poll_write( Pin::new( &mut self.inner ), cx, &buf[..buffer_size] )
 	}
 
 
 	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
 	{
+		ready!( self.poll_send_buffered( cx ) )?;
+
 		TokAsyncWrite::poll_flush( Pin::new( &mut self.inner ), cx )
 	}
 
 
 	fn poll_shutdown( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
 	{
+		ready!( self.poll_send_buffered( cx ) )?;
+
 		Pin::new( &mut self.inner ).poll_close( cx )
 	}
 }
 
 
 
-impl<S> AsyncRead  for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> AsyncRead  for WsStream<B> where B: WsBackend
 {
 	fn poll_read( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
 	{
@@ -187,7 +618,7 @@ impl<S> AsyncRead  for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpi
 //
 #[ cfg_attr( nightly, doc(cfg( feature = "tokio_io" )) ) ]
 //
-impl<S> TokAsyncRead for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> TokAsyncRead for WsStream<B> where B: WsBackend
 {
 	fn poll_read( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_> ) -> Poll< io::Result<()> >
 	{
@@ -197,7 +628,7 @@ impl<S> TokAsyncRead for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Un
 
 
 
-impl<S> AsyncBufRead for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> AsyncBufRead for WsStream<B> where B: WsBackend
 {
 	fn poll_fill_buf( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<&[u8]> >
 	{
@@ -213,7 +644,7 @@ impl<S> AsyncBufRead for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Un
 
 
 
-impl<S> Observable< WsEvent > for WsStream<S> where S: AsyncRead + AsyncWrite + Send + Unpin
+impl<B> Observable< WsEvent > for WsStream<B> where B: WsBackend
 {
 	type Error = WsErr;
 
@@ -221,7 +652,7 @@ impl<S> Observable< WsEvent > for WsStream<S> where S: AsyncRead + AsyncWrite +
 	{
 		async move
 		{
-			self.inner.observe( options ).await.map_err( Into::into )
+			self.inner.observe( options ).await
 
 		}.boxed()
 	}
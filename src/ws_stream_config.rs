@@ -0,0 +1,368 @@
+use crate::{ import::*, WsErr, WsStream, WsBackend, TextPolicy, WriteMode, Timer, tung_websocket::MAX_CLOSE_REASON };
+
+#[ cfg( feature = "handshake" ) ] use crate::HandshakeInfo;
+
+
+/// Per connection options for [`WsStream`]. This complements the [`WebSocketConfig`] of tungstenite,
+/// which you pass when creating the [`WebSocketStream`](async_tungstenite::WebSocketStream).
+///
+/// ```
+/// use ws_stream_tungstenite::{ WsStreamConfig, TextPolicy };
+///
+/// let config = WsStreamConfig::new()
+///
+///    .max_write_size( 64 * 1024         )
+///    .text_policy   ( TextPolicy::Event )
+/// ;
+/// ```
+///
+/// Then call [`WsStreamConfig::build`] with a `WebSocketStream` or another [`WsBackend`] to create the `WsStream`. The options are
+/// validated against the `WebSocketConfig` of the connection at that point.
+///
+/// This is the builder for `WsStream`. There is no `WsStream::builder()`, because `WsStream` is generic over its
+/// backend, which isn't known until `build` is called.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct WsStreamConfig
+{
+	pub(crate) max_write_size    : Option<usize>                  ,
+	pub(crate) fragment_size     : Option<usize>                  ,
+	pub(crate) observer_capacity : usize                          ,
+	pub(crate) text_policy       : TextPolicy                     ,
+	pub(crate) write_mode        : WriteMode                      ,
+	pub(crate) text_close_reason : Cow<'static, str>              ,
+	pub(crate) timer             : Option<Timer>                  ,
+	pub(crate) keepalive         : Option<( Duration, Duration )> ,
+	pub(crate) close_timeout     : Option<Duration>               ,
+	pub(crate) connection_id     : Option< Cow<'static, str> >    ,
+	pub(crate) peer              : Option< Cow<'static, str> >    ,
+
+	#[ cfg( feature = "metrics" ) ]
+	//
+	pub(crate) metrics_labels    : Vec<Label>                     ,
+
+	#[ cfg( feature = "handshake" ) ]
+	//
+	pub(crate) handshake         : Option<HandshakeInfo>          ,
+}
+
+
+
+impl Default for WsStreamConfig
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			max_write_size    : None                                      ,
+			fragment_size     : None                                      ,
+			observer_capacity : 2                                         ,
+			text_policy       : TextPolicy::default()                     ,
+			write_mode        : WriteMode::default()                      ,
+			text_close_reason : "Text messages are not supported.".into() ,
+			timer             : None                                      ,
+			keepalive         : None                                      ,
+			close_timeout     : None                                      ,
+			connection_id     : None                                      ,
+			peer              : None                                      ,
+
+			#[ cfg( feature = "metrics" ) ]
+			//
+			metrics_labels    : Vec::new()                                ,
+
+			#[ cfg( feature = "handshake" ) ]
+			//
+			handshake         : None                                      ,
+		}
+	}
+}
+
+
+
+impl WsStreamConfig
+{
+	/// Create a config with the default options.
+	//
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+
+
+	/// The maximum amount of bytes sent in one websocket message. By default this is the smallest of
+	/// `max_write_buffer_size` and `max_message_size` from the [`WebSocketConfig`]. It can not be bigger than those,
+	/// except for `max_write_buffer_size` when using [`WsStreamConfig::fragment_size`].
+	///
+	/// You should set this if the remote endpoint accepts smaller messages than we do.
+	//
+	pub fn max_write_size( mut self, size: usize ) -> Self
+	{
+		self.max_write_size = Some( size );
+		self
+	}
+
+
+	/// Send every write as one websocket message split into frames of at most `size` bytes, rather than
+	/// splitting big writes up in several messages. The maximum message size is then no longer limited by
+	/// `max_write_buffer_size`, only by `max_message_size` and [`WsStreamConfig::max_write_size`].
+	///
+	/// Control frames like pings and close frames can still go out between the fragments. The fragment size
+	/// can not be bigger than `max_write_buffer_size`.
+	//
+	pub fn fragment_size( mut self, size: usize ) -> Self
+	{
+		self.fragment_size = Some( size );
+		self
+	}
+
+
+	/// Preallocate space for this number of observers of [`WsEvent`](crate::WsEvent)s. Defaults to 2.
+	//
+	pub fn observer_capacity( mut self, capacity: usize ) -> Self
+	{
+		self.observer_capacity = capacity;
+		self
+	}
+
+
+	/// What to do when the remote sends a text message. Defaults to [`TextPolicy::Reject`].
+	//
+	pub fn text_policy( mut self, policy: TextPolicy ) -> Self
+	{
+		self.text_policy = policy;
+		self
+	}
+
+
+	/// How writes are turned into websocket messages. Defaults to [`WriteMode::PerWrite`].
+	//
+	pub fn write_mode( mut self, mode: WriteMode ) -> Self
+	{
+		self.write_mode = mode;
+		self
+	}
+
+
+	/// The reason sent in the close frame when we close the connection because of a text message
+	/// with [`TextPolicy::Reject`]. It must fit in a close frame, which leaves 123 bytes.
+	//
+	pub fn text_close_reason( mut self, reason: impl Into< Cow<'static, str> > ) -> Self
+	{
+		self.text_close_reason = reason.into();
+		self
+	}
+
+
+	/// The timer used by the options that need to keep track of time, like [`WsStreamConfig::keepalive`]
+	/// and [`WsStreamConfig::close_timeout`].
+	//
+	pub fn timer( mut self, timer: Timer ) -> Self
+	{
+		self.timer = Some( timer );
+		self
+	}
+
+
+	/// Send a ping every `interval` to detect connections that have silently gone away, eg. half-open
+	/// TCP connections behind NAT. If the remote does not answer with a pong within `timeout`, a
+	/// [`WsEvent::PongTimeout`](crate::WsEvent::PongTimeout) is emitted and the connection is closed with
+	/// [`CloseCode::Away`](tungstenite::protocol::frame::coding::CloseCode::Away).
+	///
+	/// The pings are sent while reading, so you must keep polling the `AsyncRead` side of the `WsStream`,
+	/// which you should always do anyway. This requires a [`WsStreamConfig::timer`].
+	//
+	pub fn keepalive( mut self, interval: Duration, timeout: Duration ) -> Self
+	{
+		self.keepalive = Some(( interval, timeout ));
+		self
+	}
+
+
+	/// Give up on the close handshake if it's not finished within `timeout`. The deadline starts when we
+	/// close the connection or when we receive a close frame from the remote. When it passes, a
+	/// [`WsEvent::CloseTimeout`](crate::WsEvent::CloseTimeout) is emitted and the `AsyncRead` side
+	/// returns EOF, so you can drop the `WsStream`.
+	///
+	/// Without this, a client waits for the server to close the underlying connection, which might
+	/// never happen if the remote is misbehaving or gone. This requires a [`WsStreamConfig::timer`].
+	//
+	pub fn close_timeout( mut self, timeout: Duration ) -> Self
+	{
+		self.close_timeout = Some( timeout );
+		self
+	}
+
+
+	/// An id to tell this connection apart in the logs. Each connection logs in a `ws_stream` [`tracing`] span,
+	/// which has this as the `id` field.
+	//
+	pub fn connection_id( mut self, id: impl Into< Cow<'static, str> > ) -> Self
+	{
+		self.connection_id = Some( id.into() );
+		self
+	}
+
+
+	/// A label for the remote endpoint, eg. its address, recorded as the `peer` field of the `ws_stream`
+	/// [`tracing`] span of this connection.
+	//
+	pub fn peer( mut self, label: impl Into< Cow<'static, str> > ) -> Self
+	{
+		self.peer = Some( label.into() );
+		self
+	}
+
+
+	/// What we know about the http upgrade of this connection, so it's available later through
+	/// [`WsStream::handshake_info`]. `WsListener` and `WsConnector`
+	/// set this for you.
+	///
+	/// The uri, subprotocol and peer address are recorded in the `ws_stream` [`tracing`] span, so every log
+	/// event of the connection carries them. A label set with [`WsStreamConfig::peer`] takes precedence over
+	/// the peer address.
+	//
+	#[ cfg( feature = "handshake" ) ]
+	//
+	#[ cfg_attr( nightly, doc(cfg( feature = "handshake" )) ) ]
+	//
+	pub fn handshake_info( mut self, info: HandshakeInfo ) -> Self
+	{
+		self.handshake = Some( info );
+		self
+	}
+
+
+	/// Labels added to all metrics recorded for this connection, eg. to tell apart the endpoints of your
+	/// application. Metrics are recorded through the [`metrics`] facade with the recorder that is installed
+	/// when the `WsStream` is created:
+	///
+	/// - `ws_stream_tungstenite_open_connections`: gauge of connections that are not closed yet. A connection
+	///   taken back with [`WsStream::into_inner`] or [`WsStream::into_parts`] is no longer counted, even though
+	///   it's still open, because we won't see it close.
+	/// - `ws_stream_tungstenite_bytes_received_total`: payload bytes of the data messages received.
+	/// - `ws_stream_tungstenite_bytes_sent_total`: payload bytes of the data messages sent.
+	/// - `ws_stream_tungstenite_close_codes_total`: close frames sent and received, with the extra labels
+	///   `code` (the numeric close code or `none`) and `direction` (`sent` or `received`).
+	/// - `ws_stream_tungstenite_protocol_errors_total`: websocket protocol violations by the remote.
+	/// - `ws_stream_tungstenite_text_rejections_total`: text messages refused with [`TextPolicy::Reject`].
+	//
+	#[ cfg( feature = "metrics" ) ]
+	//
+	#[ cfg_attr( nightly, doc(cfg( feature = "metrics" )) ) ]
+	//
+	pub fn metrics_labels<K, V>( mut self, labels: impl IntoIterator< Item = (K, V) > ) -> Self
+
+		where K: Into<SharedString>,
+		      V: Into<SharedString>,
+	{
+		self.metrics_labels = labels.into_iter().map( |(k, v)| Label::new( k, v ) ).collect();
+		self
+	}
+
+
+	/// Verify that the options are consistent with each other and with the configuration of the
+	/// underlying tungstenite connection.
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::InvalidConfig`]: with a description of the problem.
+	//
+	pub fn validate( &self, tung: &WebSocketConfig ) -> Result<(), WsErr>
+	{
+		if let Some( size ) = self.max_write_size
+		{
+			if size == 0
+			{
+				return Err( WsErr::InvalidConfig{ reason: "max_write_size must be bigger than zero" } );
+			}
+
+			if size > tung.max_write_buffer_size && self.fragment_size.is_none()
+			{
+				return Err( WsErr::InvalidConfig{ reason: "max_write_size can not be bigger than tungstenite max_write_buffer_size" } );
+			}
+
+			if size > tung.max_message_size.unwrap_or( usize::MAX )
+			{
+				return Err( WsErr::InvalidConfig{ reason: "max_write_size can not be bigger than tungstenite max_message_size" } );
+			}
+		}
+
+		if let Some( size ) = self.fragment_size
+		{
+			if size == 0
+			{
+				return Err( WsErr::InvalidConfig{ reason: "fragment_size must be bigger than zero" } );
+			}
+
+			if size > tung.max_write_buffer_size
+			{
+				return Err( WsErr::InvalidConfig{ reason: "fragment_size can not be bigger than tungstenite max_write_buffer_size" } );
+			}
+		}
+
+		if self.text_close_reason.len() > MAX_CLOSE_REASON
+		{
+			return Err( WsErr::InvalidConfig{ reason: "text_close_reason does not fit in a close frame" } );
+		}
+
+		if let Some(( interval, timeout )) = self.keepalive
+		{
+			if self.timer.is_none()
+			{
+				return Err( WsErr::InvalidConfig{ reason: "keepalive requires a timer" } );
+			}
+
+			if interval.is_zero() || timeout.is_zero()
+			{
+				return Err( WsErr::InvalidConfig{ reason: "keepalive interval and timeout must be bigger than zero" } );
+			}
+		}
+
+		if let Some( timeout ) = self.close_timeout
+		{
+			if self.timer.is_none()
+			{
+				return Err( WsErr::InvalidConfig{ reason: "close_timeout requires a timer" } );
+			}
+
+			if timeout.is_zero()
+			{
+				return Err( WsErr::InvalidConfig{ reason: "close_timeout must be bigger than zero" } );
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// Validate the options and create a [`WsStream`]. Same as [`WsStream::with_config`].
+	///
+	/// ## Errors
+	///
+	/// - [`WsErr::InvalidConfig`]: See [`WsStreamConfig::validate`].
+	//
+	pub fn build<B>( self, socket: B ) -> Result< WsStream<B>, WsErr >
+
+		where B: WsBackend
+	{
+		WsStream::with_config( socket, self )
+	}
+
+
+	// The size of the biggest message we will send, given the tungstenite config. When fragmenting, the
+	// message doesn't have to fit in the write buffer of tungstenite.
+	//
+	pub(crate) fn buffer_size( &self, tung: &WebSocketConfig ) -> usize
+	{
+		let write_buffer = match self.fragment_size
+		{
+			Some(_) => usize::MAX,
+			None    => tung.max_write_buffer_size,
+		};
+
+		let max = std::cmp::min( write_buffer, tung.max_message_size.unwrap_or(usize::MAX) );
+
+		std::cmp::min( max, self.max_write_size.unwrap_or(usize::MAX) )
+	}
+}
@@ -0,0 +1,222 @@
+// Test running WsStream over a backend other than async-tungstenite.
+//
+// ✔ data flows both ways over a test double and the remote closing ends the stream
+// ✔ closing from our side sends a close frame and waits for the reply
+// ✔ WsMessageStream works over the same backend
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                   } ,
+	futures               :: { Stream, Sink, SinkExt, StreamExt, AsyncReadExt, AsyncWriteExt       } ,
+	futures               :: { executor::block_on                                                  } ,
+	tungstenite           :: { Message, Error as TungErr, error::ProtocolError                     } ,
+	tungstenite           :: { protocol::{ WebSocketConfig, CloseFrame, frame::coding::CloseCode } } ,
+	std                   :: { collections::VecDeque, pin::Pin, sync::{ Arc, Mutex }               } ,
+	std                   :: { task::{ Context, Poll }                                             } ,
+};
+
+
+
+// An in memory backend. It yields the `incoming` messages and records what we send. It follows the close
+// semantics of tungstenite: a received close frame is echoed and when we close first, the remote answers.
+//
+struct Mock
+{
+	incoming      : VecDeque<Message>        ,
+	sent          : Arc<Mutex<Vec<Message>>> ,
+	config        : WebSocketConfig          ,
+	close_sent    : bool                     ,
+	close_received: bool                     ,
+}
+
+
+impl Mock
+{
+	fn new( incoming: Vec<Message> ) -> ( Self, Arc<Mutex<Vec<Message>>> )
+	{
+		let sent = Arc::new( Mutex::new( Vec::new() ) );
+
+		let mock = Self
+		{
+			incoming      : incoming.into()            ,
+			sent          : sent.clone()               ,
+			config        : WebSocketConfig::default() ,
+			close_sent    : false                      ,
+			close_received: false                      ,
+		};
+
+		( mock, sent )
+	}
+}
+
+
+impl WsBackend for Mock
+{
+	fn config( &self ) -> &WebSocketConfig
+	{
+		&self.config
+	}
+}
+
+
+impl Stream for Mock
+{
+	type Item = Result<Message, TungErr>;
+
+	fn poll_next( mut self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		if self.close_received
+		{
+			return Poll::Ready( None );
+		}
+
+		match self.incoming.pop_front()
+		{
+			Some( Message::Close( frame ) ) =>
+			{
+				self.close_received = true;
+
+				if !self.close_sent
+				{
+					self.close_sent = true;
+					self.sent.lock().expect( "lock" ).push( Message::Close( frame.clone() ) );
+				}
+
+				Poll::Ready( Some( Ok( Message::Close( frame ) ) ) )
+			}
+
+			Some( msg ) => Poll::Ready( Some( Ok( msg ) ) ),
+
+			// The remote answers our close frame once it has sent everything.
+			//
+			None if self.close_sent =>
+			{
+				self.close_received = true;
+				Poll::Ready( Some( Ok( Message::Close( None ) ) ) )
+			}
+
+			None => Poll::Pending,
+		}
+	}
+}
+
+
+impl Sink<Message> for Mock
+{
+	type Error = TungErr;
+
+	fn poll_ready( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll< Result<(), TungErr> >
+	{
+		Poll::Ready( Ok(()) )
+	}
+
+	fn start_send( mut self: Pin<&mut Self>, msg: Message ) -> Result<(), TungErr>
+	{
+		if self.close_sent
+		{
+			return Err( TungErr::Protocol( ProtocolError::SendAfterClosing ) );
+		}
+
+		if msg.is_close()
+		{
+			self.close_sent = true;
+		}
+
+		self.sent.lock().expect( "lock" ).push( msg );
+
+		Ok(())
+	}
+
+	fn poll_flush( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll< Result<(), TungErr> >
+	{
+		Poll::Ready( Ok(()) )
+	}
+
+	fn poll_close( mut self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll< Result<(), TungErr> >
+	{
+		if !self.close_sent
+		{
+			self.close_sent = true;
+			self.sent.lock().expect( "lock" ).push( Message::Close( None ) );
+		}
+
+		Poll::Ready( Ok(()) )
+	}
+}
+
+
+
+#[ test ]
+//
+fn remote_closes()
+{
+	let frame = CloseFrame{ code: CloseCode::Normal, reason: "".into() };
+
+	let (mock, sent) = Mock::new( vec!
+	[
+		Message::Binary( "hello".into()        ) ,
+		Message::Binary( "world".into()        ) ,
+		Message::Close ( Some( frame.clone() ) ) ,
+	]);
+
+	block_on( async
+	{
+		let mut ws  = WsStream::new( mock );
+		let mut buf = Vec::new();
+
+		ws.write_all( b"hi" ).await.expect( "write" );
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		assert_eq!( b"helloworld".to_vec(), buf );
+	});
+
+	let sent = sent.lock().expect( "lock" );
+
+	assert_eq!( vec![ Message::Binary( "hi".into() ), Message::Close( Some( frame ) ) ], *sent );
+}
+
+
+
+#[ test ]
+//
+fn we_close()
+{
+	let (mock, sent) = Mock::new( vec![ Message::Binary( "hello".into() ) ] );
+
+	block_on( async
+	{
+		let mut ws  = WsStream::new( mock );
+		let mut buf = Vec::new();
+
+		ws.close().await.expect( "close" );
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		assert_eq!( b"hello".to_vec(), buf );
+	});
+
+	let sent = sent.lock().expect( "lock" );
+
+	assert_eq!( 1, sent.len() );
+	assert!( sent[0].is_close() );
+}
+
+
+
+#[ test ]
+//
+fn message_stream()
+{
+	let (mock, sent) = Mock::new( vec![ Message::Binary( "hello".into() ), Message::Close( None ) ] );
+
+	block_on( async
+	{
+		let mut ws = WsMessageStream::new( mock );
+
+		ws.send( b"hi".to_vec() ).await.expect( "send" );
+
+		assert_eq!( b"hello".to_vec(), ws.next().await.expect( "message" ).expect( "no error" ) );
+		assert!( ws.next().await.is_none() );
+	});
+
+	assert_eq!( Message::Binary( "hi".into() ), sent.lock().expect( "lock" )[0] );
+}
@@ -0,0 +1,158 @@
+#![ cfg( feature = "client" ) ]
+
+// The callback returns the error response of tungstenite, which we don't choose.
+//
+#![ allow( clippy::result_large_err ) ]
+
+// Test WsConnector on localhost.
+//
+// ✔ the extra headers and offered protocols are sent, the selected protocol and response headers are returned
+// ✔ the WsStream carries the handshake info
+// ✔ without offered protocols, none is selected
+// ✔ a protocol we didn't offer fails with WsErr::Subprotocol
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                         } ,
+	futures               :: { AsyncReadExt, AsyncWriteExt, future::join                 } ,
+	async_std             :: { net::{ TcpListener, TcpStream }                           } ,
+	async_tungstenite     :: { accept_hdr_async                                          } ,
+	tungstenite           :: { handshake::server::{ Request, Response }, http::HeaderMap } ,
+	tungstenite           :: { error::SubProtocolError                                   } ,
+	assert_matches        :: { assert_matches                                            } ,
+	std                   :: { net::SocketAddr                                           } ,
+};
+
+
+
+// Accept one connection, answering with the given subprotocol and an x-test header. Returns the request
+// headers and the data sent by the client.
+//
+async fn server( tcp: TcpListener, protocol: Option<&'static str> ) -> ( HeaderMap, Vec<u8> )
+{
+	let (stream, _) = tcp.accept().await.expect( "accept" );
+	let mut headers = HeaderMap::new();
+
+	let callback = |request: &Request, mut response: Response|
+	{
+		headers = request.headers().clone();
+
+		response.headers_mut().insert( "x-test", "yes".parse().expect( "header value" ) );
+
+		if let Some( protocol ) = protocol
+		{
+			response.headers_mut().insert( "sec-websocket-protocol", protocol.parse().expect( "header value" ) );
+		}
+
+		Ok( response )
+	};
+
+	let     socket = accept_hdr_async( stream, callback ).await.expect( "handshake" );
+	let mut ws     = WsStream::new( socket );
+	let mut buf    = Vec::new();
+
+	// When the client refuses our response it just drops the connection.
+	//
+	let _ = ws.read_to_end( &mut buf ).await;
+
+	( headers, buf )
+}
+
+
+
+async fn bind() -> ( TcpListener, SocketAddr )
+{
+	let tcp  = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind" );
+	let addr = tcp.local_addr().expect( "local addr" );
+
+	( tcp, addr )
+}
+
+
+
+#[ async_std::test ]
+//
+async fn negotiate()
+{
+	let (tcp, addr) = bind().await;
+
+	let client = async
+	{
+		let mut headers = HeaderMap::new();
+		headers.insert( "authorization", "Bearer token".parse().expect( "header value" ) );
+
+		let transport = TcpStream::connect( addr ).await.expect( "connect" );
+		let local     = transport.local_addr().ok();
+
+		let (mut ws, info) = WsConnector::new( format!( "ws://{addr}/chat" ) )
+
+			.protocols( [ "chat.v2", "chat.v1" ] )
+			.headers  ( headers                  )
+			.addrs    ( Some( addr ), local      )
+			.connect  ( transport                ).await.expect( "handshake" )
+		;
+
+		assert_eq!( Some( "chat.v1" ), info.subprotocol.as_deref() );
+		assert_eq!( "yes"            , info.headers[ "x-test" ]    );
+		assert_eq!( "/chat"          , info.uri.path()             );
+		assert_eq!( Some( addr )     , info.peer_addr              );
+
+		assert_eq!( Some( "chat.v1" ), ws.handshake_info().and_then( |i| i.subprotocol.as_deref() ) );
+
+		ws.write_all( b"hello" ).await.expect( "write" );
+		ws.close().await.expect( "close" );
+	};
+
+	let ((headers, data), _) = join( server( tcp, Some( "chat.v1" ) ), client ).await;
+
+	assert_eq!( "Bearer token"   , headers[ "authorization"          ] );
+	assert_eq!( "chat.v2,chat.v1", headers[ "sec-websocket-protocol" ] );
+	assert_eq!( b"hello".to_vec(), data                                );
+}
+
+
+
+#[ async_std::test ]
+//
+async fn no_protocols()
+{
+	let (tcp, addr) = bind().await;
+
+	let client = async
+	{
+		let transport  = TcpStream::connect( addr ).await.expect( "connect" );
+		let (ws, info) = WsConnector::new( format!( "ws://{addr}" ) ).connect( transport ).await.expect( "handshake" );
+
+		assert_eq!( None, info.subprotocol );
+
+		drop( ws );
+	};
+
+	let ((headers, _), _) = join( server( tcp, None ), client ).await;
+
+	assert!( !headers.contains_key( "sec-websocket-protocol" ) );
+}
+
+
+
+#[ async_std::test ]
+//
+async fn unexpected_protocol()
+{
+	let (tcp, addr) = bind().await;
+
+	let client = async
+	{
+		let transport = TcpStream::connect( addr ).await.expect( "connect" );
+
+		let err = WsConnector::new( format!( "ws://{addr}" ) )
+
+			.protocols( [ "chat" ] )
+			.connect  ( transport  ).await.expect_err( "wrong protocol" )
+		;
+
+		assert_matches!( err, WsErr::Subprotocol{ source: SubProtocolError::InvalidSubProtocol } );
+	};
+
+	join( server( tcp, Some( "evil" ) ), client ).await;
+}
@@ -0,0 +1,171 @@
+// Test WsStreamConfig::close_timeout.
+//
+// ✔ when we close and the remote never answers, reading returns EOF after the timeout
+// ✔ when the remote closes but never drops the connection, reading returns EOF after the timeout
+// ✔ when the writer half closes while the reader half waits in another task, the reader ends after the timeout
+// ✔ close_timeout requires a timer
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                        } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                                          } ,
+	tungstenite           :: { protocol::{ Role, frame::coding::CloseCode }, Message                    } ,
+	pharos                :: { Observable, ObserveConfig                                                } ,
+	futures_ringbuf       :: { Endpoint                                                                 } ,
+	futures_timer         :: { Delay                                                                    } ,
+	async_std             :: { task, future::timeout                                                    } ,
+	assert_matches        :: { assert_matches                                                           } ,
+	std                   :: { time::Duration                                                           } ,
+};
+
+
+fn config() -> WsStreamConfig
+{
+	WsStreamConfig::new()
+
+		.timer( Timer::new( Delay::new ) )
+		.close_timeout( Duration::from_millis( 50 ) )
+}
+
+
+
+#[ test ]
+//
+fn remote_silent()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = config().build( tws ).expect( "valid config" );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = Vec::new();
+
+		ws.close().await.expect( "close" );
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		drop( ws );
+
+		events.collect::<Vec<_>>().await
+	};
+
+
+	// Never read, so the close frame doesn't get answered, but keep the connection open.
+	//
+	let client = async
+	{
+		let _tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		Delay::new( Duration::from_millis( 500 ) ).await;
+	};
+
+	let (events, _) = block_on( join( server, client ) );
+
+	assert_eq!( 1, events.len() );
+	assert_matches!( events[0], WsEvent::CloseTimeout );
+}
+
+
+
+#[ test ]
+//
+fn connection_not_dropped()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	// Complete the close handshake, but don't drop the connection.
+	//
+	let server = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+
+		tws.close( None ).await.expect( "close" );
+
+		assert_matches!( tws.next().await, Some(Ok( Message::Close(None) )) );
+
+		Delay::new( Duration::from_millis( 500 ) ).await;
+	};
+
+
+	let client = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut ws     = config().build( tws ).expect( "valid config" );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = Vec::new();
+
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		drop( ws );
+
+		events.collect::<Vec<_>>().await
+	};
+
+	let (_, events) = block_on( join( server, client ) );
+
+	assert_eq!( 2, events.len() );
+	assert_matches!( events[0], WsEvent::CloseFrame( None ) );
+	assert_matches!( events[1], WsEvent::CloseTimeout       );
+}
+
+
+
+#[ async_std::test ]
+//
+async fn split_writer_closes()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	// Never read, so the close frame doesn't get answered, but keep the connection open.
+	//
+	let client = task::spawn( async
+	{
+		let _tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		Delay::new( Duration::from_millis( 500 ) ).await;
+	});
+
+	let tws                      = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+	let (mut reader, mut writer) = config().build( tws ).expect( "valid config" ).into_split();
+	let events                   = reader.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+	let read = task::spawn( async move
+	{
+		let mut buf = Vec::new();
+
+		reader.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		reader
+	});
+
+	// Let the reader wait for data before closing.
+	//
+	Delay::new( Duration::from_millis( 10 ) ).await;
+
+	writer.close_with( CloseCode::Normal, "done" ).expect( "close_with" );
+	writer.close().await.expect( "close" );
+
+	let reader = timeout( Duration::from_millis( 400 ), read ).await.expect( "reader ends before the remote drops" );
+
+	drop( reader.reunite( writer ).expect( "reunite" ) );
+
+	let events = events.collect::<Vec<_>>().await;
+
+	assert_eq!( 1, events.len() );
+	assert_matches!( events[0], WsEvent::CloseTimeout );
+
+	client.await;
+}
+
+
+
+#[ test ]
+//
+fn requires_timer()
+{
+	let config = WsStreamConfig::new().close_timeout( Duration::from_secs( 1 ) );
+
+	assert_matches!( config.validate( &Default::default() ), Err( WsErr::InvalidConfig{..} ) );
+}
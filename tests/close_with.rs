@@ -0,0 +1,88 @@
+// Test closing the connection with a custom close frame.
+//
+// ✔ the close frame is sent with the code and reason we chose
+// ✔ the read side drives the close handshake to completion
+// ✔ invalid close codes and reasons that are too long are refused
+// ✔ a second close frame is refused
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                        } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                                          } ,
+	tungstenite           :: { protocol::{ CloseFrame, frame::coding::CloseCode, Role }, Message        } ,
+	futures_ringbuf       :: { Endpoint                                                                 } ,
+	assert_matches        :: { assert_matches                                                           } ,
+	tracing               :: { *                                                                        } ,
+};
+
+
+#[ test ]
+//
+fn close_with()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStream::new( tws );
+
+		ws.close_with( CloseCode::Library(4000), "going home" ).expect( "queue close frame" );
+
+		assert_matches!( ws.close_with( CloseCode::Normal, "twice" ), Err( WsErr::Closed ) );
+		assert_matches!( ws.write( b"data" ).await.map_err( |e| e.kind() ), Err( std::io::ErrorKind::NotConnected ) );
+
+		ws.close().await.expect( "close" );
+
+		let mut buf = Vec::new();
+
+		assert_eq!( 0, ws.read_to_end( &mut buf ).await.expect( "read to end" ) );
+
+		debug!( "Server task ended" );
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		let frame = CloseFrame
+		{
+			code  : CloseCode::Library(4000),
+			reason: "going home".into(),
+		};
+
+		assert_eq!( Some( Message::Close( Some(frame) ) ), tws.next().await.transpose().expect( "receive close" ) );
+		assert!   ( tws.next().await.is_none() );
+
+		debug!( "Client task ended" );
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn close_with_invalid()
+{
+	let (sc, _cs) = Endpoint::pair( 100, 100 );
+
+	let test = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = WsStream::new( tws );
+		let     reason = "a".repeat( 124 );
+
+		assert_matches!( ws.close_with( CloseCode::Status, ""     ), Err( WsErr::InvalidCloseCode   ) );
+		assert_matches!( ws.close_with( CloseCode::Normal, reason ), Err( WsErr::CloseReasonTooLong ) );
+
+		// Nothing was queued, so we can still close.
+		//
+		ws.close_with( CloseCode::Normal, "a".repeat( 123 ) ).expect( "queue close frame" );
+	};
+
+	block_on( test );
+}
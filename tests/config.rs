@@ -0,0 +1,203 @@
+// Test WsStreamConfig.
+//
+// ✔ options are validated against the tungstenite config
+// ✔ max_write_size limits the size of outgoing messages
+// ✔ max_write_size limits the size of outgoing messages written with tokio AsyncWrite
+// ✔ text_close_reason is used when rejecting text messages
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                                  } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join           } ,
+	async_tungstenite     :: { WebSocketStream                                                                    } ,
+	tungstenite           :: { protocol::{ CloseFrame, frame::coding::CloseCode, Role, WebSocketConfig }, Message } ,
+	futures_ringbuf       :: { Endpoint                                                                           } ,
+	assert_matches        :: { assert_matches                                                                     } ,
+};
+
+
+#[ test ]
+//
+fn validate()
+{
+	let tung = WebSocketConfig
+	{
+		max_write_buffer_size: 1024,
+		max_message_size     : Some( 512 ),
+		..Default::default()
+	};
+
+	assert!( WsStreamConfig::new().validate( &tung ).is_ok() );
+	assert!( WsStreamConfig::new().max_write_size( 512 ).validate( &tung ).is_ok() );
+
+	assert_matches!( WsStreamConfig::new().max_write_size(   0 ).validate( &tung ), Err( WsErr::InvalidConfig{..} ) );
+	assert_matches!( WsStreamConfig::new().max_write_size( 513 ).validate( &tung ), Err( WsErr::InvalidConfig{..} ) );
+
+	let tung = WebSocketConfig
+	{
+		max_write_buffer_size: 256,
+		..tung
+	};
+
+	assert_matches!( WsStreamConfig::new().max_write_size( 257 ).validate( &tung ), Err( WsErr::InvalidConfig{..} ) );
+
+	assert_matches!
+	(
+		WsStreamConfig::new().text_close_reason( "a".repeat(124) ).validate( &tung ),
+		Err( WsErr::InvalidConfig{..} )
+	);
+}
+
+
+
+#[ test ]
+//
+fn max_write_size()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStreamConfig::new().max_write_size( 4 ).build( tws ).expect( "valid config" );
+
+		ws.write_all( b"hello world" ).await.expect( "write" );
+		ws.close().await.expect( "close" );
+
+		let mut buf = Vec::new();
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+	};
+
+
+	let client = async
+	{
+		let     tws  = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut msgs = Vec::new();
+
+		let (_, mut stream) = tws.split();
+
+		while let Some( Ok(msg) ) = stream.next().await
+		{
+			msgs.push( msg );
+		}
+
+		msgs
+	};
+
+	let ( _, msgs ) = block_on( join( server, client ) );
+
+	assert_eq!
+	(
+		vec!
+		[
+			Message::Binary( "hell".into() ),
+			Message::Binary( "o wo".into() ),
+			Message::Binary( "rld" .into() ),
+			Message::Close ( None          ),
+		],
+
+		msgs
+	);
+}
+
+
+
+#[ cfg( feature = "tokio_io" ) ]
+//
+#[ test ]
+//
+fn max_write_size_tokio()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStreamConfig::new().max_write_size( 4 ).build( tws ).expect( "valid config" );
+
+		tokio::io::AsyncWriteExt::write_all( &mut ws, b"hello world" ).await.expect( "write" );
+		tokio::io::AsyncWriteExt::shutdown ( &mut ws                 ).await.expect( "close" );
+
+		let mut buf = Vec::new();
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+	};
+
+
+	let client = async
+	{
+		let     tws  = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut msgs = Vec::new();
+
+		let (_, mut stream) = tws.split();
+
+		while let Some( Ok(msg) ) = stream.next().await
+		{
+			msgs.push( msg );
+		}
+
+		msgs
+	};
+
+	let ( _, msgs ) = block_on( join( server, client ) );
+
+	assert_eq!
+	(
+		vec!
+		[
+			Message::Binary( "hell".into() ),
+			Message::Binary( "o wo".into() ),
+			Message::Binary( "rld" .into() ),
+			Message::Close ( None          ),
+		],
+
+		msgs
+	);
+}
+
+
+
+#[ test ]
+//
+fn text_close_reason()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+
+		let mut ws = WsStreamConfig::new()
+
+			.text_close_reason( "binary only" )
+			.build( tws )
+			.expect( "valid config" )
+		;
+
+		let mut buf = Vec::new();
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( Message::Text( "hi".into() ) ).await.expect( "send text" );
+
+		let frame = tws.next().await.transpose().expect( "receive close" );
+
+		while tws.next().await.is_some() {}
+
+		frame
+	};
+
+	let ( _, frame ) = block_on( join( server, client ) );
+
+	let expect = CloseFrame
+	{
+		code  : CloseCode::Unsupported,
+		reason: "binary only".into(),
+	};
+
+	assert_eq!( Some( Message::Close( Some(expect) ) ), frame );
+}
@@ -0,0 +1,123 @@
+// Test WsStreamConfig::fragment_size.
+//
+// ✔ a write is sent as one message made of a binary frame and continuation frames
+// ✔ a ping can go out between the fragments
+// ✔ fragment_size is validated
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                                              } ,
+	futures               :: { AsyncReadExt, AsyncWriteExt, executor::block_on, future::join, channel::oneshot                } ,
+	async_tungstenite     :: { WebSocketStream                                                                                } ,
+	tungstenite           :: { protocol::{ Role, WebSocketConfig, frame::{ FrameSocket, coding::{ OpCode, Data, Control } } } } ,
+	futures_ringbuf       :: { Endpoint                                                                                       } ,
+	assert_matches        :: { assert_matches                                                                                 } ,
+	std                   :: { io::Cursor                                                                                     } ,
+};
+
+
+
+#[ test ]
+//
+fn fragment()
+{
+	let (sc, mut cs) = Endpoint::pair( 100, 100 );
+	let (tx, rx)     = oneshot::channel();
+	let data         = ( 0..200u8 ).collect::<Vec<_>>();
+
+	let server = async
+	{
+		let tung = WebSocketConfig
+		{
+			write_buffer_size: 0,
+			..Default::default()
+		};
+
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, Some( tung ) ).await;
+		let mut ws  = WsStreamConfig::new().fragment_size( 40 ).build( tws ).expect( "valid config" );
+
+		// The message is taken at once, but only the first fragments fit in the transport.
+		//
+		ws.write_all( &data ).await.expect( "write" );
+
+		// The ping will be sent before the next fragment.
+		//
+		drop( ws.ping() );
+
+		tx.send(()).expect( "signal client" );
+
+		ws.flush().await.expect( "flush" );
+		ws.close().await.expect( "close" );
+	};
+
+
+	let client = async
+	{
+		rx.await.expect( "signal" );
+
+		let mut bytes = Vec::new();
+
+		cs.read_to_end( &mut bytes ).await.expect( "read to end" );
+
+		bytes
+	};
+
+	let (_, bytes) = block_on( join( server, client ) );
+
+	let mut frames = FrameSocket::new( Cursor::new( bytes ) );
+	let mut seen   = Vec::new();
+	let mut msg    = Vec::new();
+
+	while let Some( frame ) = frames.read( None ).expect( "valid frame" )
+	{
+		let header = frame.header();
+
+		seen.push(( header.opcode, header.is_final ));
+
+		if let OpCode::Data(_) = header.opcode
+		{
+			msg.extend( frame.into_data() );
+		}
+	}
+
+	assert_eq!
+	(
+		vec!
+		[
+			( OpCode::Data   ( Data::Binary    ), false ),
+			( OpCode::Data   ( Data::Continue  ), false ),
+			( OpCode::Data   ( Data::Continue  ), false ),
+			( OpCode::Control( Control::Ping   ), true  ),
+			( OpCode::Data   ( Data::Continue  ), false ),
+			( OpCode::Data   ( Data::Continue  ), true  ),
+			( OpCode::Control( Control::Close  ), true  ),
+		],
+
+		seen
+	);
+
+	assert_eq!( data, msg );
+}
+
+
+
+#[ test ]
+//
+fn validate()
+{
+	let tung = WebSocketConfig
+	{
+		max_write_buffer_size: 64,
+		max_message_size     : Some( 1024 ),
+		..Default::default()
+	};
+
+	assert!( WsStreamConfig::new().fragment_size( 64 ).validate( &tung ).is_ok() );
+
+	// The message can be bigger than the write buffer when fragmenting.
+	//
+	assert!( WsStreamConfig::new().fragment_size( 64 ).max_write_size( 1024 ).validate( &tung ).is_ok() );
+
+	assert_matches!( WsStreamConfig::new().fragment_size(  0 ).validate( &tung ), Err( WsErr::InvalidConfig{..} ) );
+	assert_matches!( WsStreamConfig::new().fragment_size( 65 ).validate( &tung ), Err( WsErr::InvalidConfig{..} ) );
+}
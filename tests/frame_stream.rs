@@ -0,0 +1,279 @@
+#![ cfg( feature = "frames" ) ]
+
+// Test FrameStream.
+//
+// ✔ the data of a fragmented message can be read before the rest of the message arrives
+// ✔ messages bigger than max_message_size are read as long as each fragment fits in max_frame_size
+// ✔ a frame bigger than max_frame_size is reported and ends the stream
+// ✔ WsMessageStream puts fragmented messages back together, and text messages are handled by the text policy
+// ✔ many control frames and fragments in a row don't overflow the stack
+// ✔ as a client it masks frames, answers pings and does the close handshake with a tungstenite server
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                                       } ,
+	futures               :: { StreamExt, SinkExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join       } ,
+	futures               :: { channel::oneshot                                                                        } ,
+	async_tungstenite     :: { WebSocketStream                                                                         } ,
+	tungstenite           :: { protocol::{ Role, WebSocketConfig, frame::{ Frame, coding::{ OpCode, Data } } }, Message } ,
+	pharos                :: { Observable, ObserveConfig                                                               } ,
+	futures_ringbuf       :: { Endpoint                                                                                } ,
+	assert_matches        :: { assert_matches                                                                          } ,
+};
+
+
+
+// A fragment of a binary message.
+//
+fn fragment( data: Vec<u8>, first: bool, last: bool ) -> Message
+{
+	let opcode = if first { OpCode::Data( Data::Binary ) } else { OpCode::Data( Data::Continue ) };
+
+	Message::Frame( Frame::message( data, opcode, last ) )
+}
+
+
+
+#[ test ]
+//
+fn incremental()
+{
+	let (sc, cs) = Endpoint::pair( 10_000, 10_000 );
+	let (tx, rx) = oneshot::channel();
+
+	let server = async
+	{
+		let config = WebSocketConfig
+		{
+			max_frame_size  : Some( 1024 ),
+			max_message_size: Some( 1500 ),
+			..Default::default()
+		};
+
+		let mut ws  = WsStream::new( FrameStream::new( sc, Role::Server, Some( config ) ) );
+		let mut buf = vec![ 0; 1000 ];
+
+		ws.read_exact( &mut buf ).await.expect( "first fragment" );
+		assert_eq!( vec![ 1; 1000 ], buf );
+
+		tx.send(()).expect( "signal client" );
+
+		ws.read_exact( &mut buf ).await.expect( "second fragment" );
+		assert_eq!( vec![ 2; 1000 ], buf );
+
+		ws.close().await.expect( "close" );
+
+		let mut rest = Vec::new();
+		ws.read_to_end( &mut rest ).await.expect( "read to end" );
+
+		assert!( rest.is_empty() );
+		assert_eq!( 1, ws.stats().messages_received );
+	};
+
+
+	// Only send the end of the message once the server read the start.
+	//
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( fragment( vec![ 1; 1000 ], true, false ) ).await.expect( "send" );
+
+		rx.await.expect( "signal" );
+
+		tws.send( fragment( vec![ 2; 1000 ], false, true ) ).await.expect( "send" );
+
+		assert_matches!( tws.next().await, Some(Ok( Message::Close(None) )) );
+		assert!( tws.next().await.is_none() );
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn frame_too_big()
+{
+	let (sc, cs) = Endpoint::pair( 10_000, 10_000 );
+
+	let server = async
+	{
+		let config = WebSocketConfig
+		{
+			max_frame_size: Some( 1024 ),
+			..Default::default()
+		};
+
+		let mut ws     = WsStream::new( FrameStream::new( sc, Role::Server, Some( config ) ) );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = Vec::new();
+
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+		assert!( buf.is_empty() );
+
+		drop( ws );
+
+		let events = events.collect::<Vec<_>>().await;
+
+		assert_matches!( &events[0], WsEvent::Error( e ) if matches!( **e, WsErr::MessageTooLarge{ size: 2000, max_size: 1024 } ) );
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( Message::Binary( vec![ 1; 2000 ] ) ).await.expect( "send" );
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn whole_messages()
+{
+	let (sc, cs) = Endpoint::pair( 10_000, 10_000 );
+
+	let server = async
+	{
+		let mut ws     = WsMessageStream::new( FrameStream::new( sc, Role::Server, None ) );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+		ws.set_text_policy( TextPolicy::Event );
+
+		let msg = ws.next().await.expect( "a message" ).expect( "no error" );
+
+		assert_eq!( [ vec![ 1; 300 ], vec![ 2; 300 ], vec![ 3; 300 ] ].concat(), msg );
+
+		ws.close().await.expect( "close" );
+		while ws.next().await.is_some() {}
+
+		drop( ws );
+
+		let events = events.collect::<Vec<_>>().await;
+
+		assert_matches!( &events[0], WsEvent::Text( text ) if text == "hello world" );
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.feed( Message::Frame( Frame::message( b"hello ".to_vec(), OpCode::Data( Data::Text     ), false ) ) ).await.expect( "send" );
+		tws.feed( Message::Frame( Frame::message( b"world" .to_vec(), OpCode::Data( Data::Continue ), true  ) ) ).await.expect( "send" );
+
+		tws.feed( fragment( vec![ 1; 300 ], true , false ) ).await.expect( "send" );
+		tws.feed( fragment( vec![ 2; 300 ], false, false ) ).await.expect( "send" );
+		tws.send( fragment( vec![ 3; 300 ], false, true  ) ).await.expect( "send" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn many_fragments()
+{
+	const COUNT: usize = 50_000;
+
+	// Buffer everything, so the server finds all frames ready in one poll.
+	//
+	let (sc, cs) = Endpoint::pair( 1_000_000, 1_000_000 );
+
+	let server = async
+	{
+		let mut ws  = WsMessageStream::new( FrameStream::new( sc, Role::Server, None ) );
+		let     msg = ws.next().await.expect( "a message" ).expect( "no error" );
+
+		assert_eq!( vec![ 1; COUNT ], msg );
+		assert_eq!( COUNT as u64, ws.stats().pings_received );
+
+		ws.close().await.expect( "close" );
+		while ws.next().await.is_some() {}
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		for _ in 0..COUNT
+		{
+			tws.feed( Message::Ping( Vec::new() ) ).await.expect( "send" );
+		}
+
+		for i in 0..COUNT
+		{
+			tws.feed( fragment( vec![ 1 ], i == 0, i == COUNT - 1 ) ).await.expect( "send" );
+		}
+
+		tws.flush().await.expect( "flush" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn client()
+{
+	let (sc, cs) = Endpoint::pair( 10_000, 10_000 );
+
+	// Ping the client before echoing a message. It answers while waiting for the echo.
+	//
+	let server = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let     msg = tws.next().await.expect( "a message" ).expect( "no error" );
+
+		tws.send( Message::Ping( vec![ 7 ] ) ).await.expect( "ping" );
+
+		assert_matches!( tws.next().await, Some(Ok( Message::Pong( data ) )) if data == [ 7 ] );
+
+		tws.send( msg ).await.expect( "echo" );
+
+		assert_matches!( tws.next().await, Some(Ok( Message::Close(None) )) );
+
+		// Tungstenite sends the reply on the next flush.
+		//
+		tws.flush().await.expect( "flush close reply" );
+		assert!( tws.next().await.is_none() );
+	};
+
+
+	let client = async
+	{
+		let mut ws  = WsStream::new( FrameStream::new( cs, Role::Client, None ) );
+		let mut buf = [ 0; 5 ];
+
+		ws.write_all( b"hello" ).await.expect( "write" );
+		ws.flush().await.expect( "flush" );
+
+		ws.read_exact( &mut buf ).await.expect( "read" );
+		assert_eq!( b"hello", &buf );
+
+		ws.close().await.expect( "close" );
+
+		let mut rest = Vec::new();
+		ws.read_to_end( &mut rest ).await.expect( "read to end" );
+
+		assert!( rest.is_empty() );
+		assert_eq!( 1, ws.stats().pings_received );
+	};
+
+	block_on( join( server, client ) );
+}
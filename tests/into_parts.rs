@@ -0,0 +1,64 @@
+// Test recovering the tungstenite socket from a WsStream.
+//
+// ✔ bytes that were received but not yet read are returned
+// ✔ the socket can be used for message level access afterwards
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                         } ,
+	futures               :: { StreamExt, AsyncReadExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                           } ,
+	tungstenite           :: { protocol::{ Role }, Message                               } ,
+	futures_ringbuf       :: { Endpoint                                                  } ,
+	tracing               :: { *                                                         } ,
+};
+
+
+#[ test ]
+//
+fn into_parts()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+
+		tws.send( Message::Binary( "hello world".into() ) ).await.expect( "send binary" );
+
+		assert_eq!( Some( Message::Text( "hi".into() ) ), tws.next().await.transpose().expect( "receive text" ) );
+		assert_eq!( Some( Message::Close( None )       ), tws.next().await.transpose().expect( "receive close" ) );
+		assert!   ( tws.next().await.is_none() );
+
+		debug!( "Server task ended" );
+	};
+
+
+	let client = async
+	{
+		let     tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut ws  = WsStream::new( tws );
+		let mut buf = [0u8; 5];
+
+		ws.read_exact( &mut buf ).await.expect( "read hello" );
+
+		assert_eq!( b"hello", &buf );
+
+		let parts = ws.into_parts();
+
+		assert_eq!( b" world".to_vec(), parts.read_buf );
+		assert!   ( parts.close_frame.is_none() );
+		assert!   ( parts.events.is_empty()     );
+
+		let mut tws = parts.socket;
+
+		tws.send( Message::Text( "hi".into() ) ).await.expect( "send text" );
+		tws.close( None ).await.expect( "close" );
+
+		assert!( tws.next().await.transpose().expect( "receive close" ).is_some() );
+
+		debug!( "Client task ended" );
+	};
+
+	block_on( join( server, client ) );
+}
@@ -0,0 +1,144 @@
+// Test keepalive.
+//
+// ✔ pings are sent periodically while the remote answers them
+// ✔ pongs that answer our pings are reported as Rtt rather than Pong
+// ✔ if the remote doesn't answer, PongTimeout is emitted and the connection is closed with CloseCode::Away
+// ✔ keepalive requires a timer
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                 } ,
+	futures               :: { StreamExt, AsyncReadExt, executor::block_on, future::join         } ,
+	async_tungstenite     :: { WebSocketStream                                                   } ,
+	tungstenite           :: { protocol::{ CloseFrame, frame::coding::CloseCode, Role }, Message } ,
+	pharos                :: { Observable, ObserveConfig                                         } ,
+	futures_ringbuf       :: { Endpoint                                                          } ,
+	futures_timer         :: { Delay                                                             } ,
+	assert_matches        :: { assert_matches                                                    } ,
+	std                   :: { time::Duration                                                    } ,
+};
+
+
+fn config() -> WsStreamConfig
+{
+	WsStreamConfig::new()
+
+		.timer( Timer::new( Delay::new ) )
+		.keepalive( Duration::from_millis( 10 ), Duration::from_millis( 50 ) )
+}
+
+
+
+#[ test ]
+//
+fn keepalive()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = config().build( tws ).expect( "valid config" );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = Vec::new();
+
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		drop( ws );
+
+		events.collect::<Vec<_>>().await
+	};
+
+
+	// Tungstenite answers the pings as long as we keep reading.
+	//
+	let client = async
+	{
+		let mut tws   = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut pings = 0;
+
+		while pings < 3
+		{
+			match tws.next().await
+			{
+				Some(Ok( Message::Ping(_) )) => pings += 1,
+				msg                          => unreachable!( "{:?}", msg ),
+			}
+		}
+
+		tws.close( None ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	let (events, _) = block_on( join( server, client ) );
+
+	// The pongs are reported with their round trip time, followed by the close frame.
+	//
+	let (closed, rest) = events.split_last().expect( "events" );
+	let (frame , rtts) = rest  .split_last().expect( "events" );
+
+	assert!( !rtts.is_empty() );
+	assert!( rtts.iter().all( |evt| matches!( evt, WsEvent::Rtt(_) ) ) );
+	assert_matches!( frame , WsEvent::CloseFrame( None ) );
+	assert_matches!( closed, WsEvent::Closed( _ )        );
+}
+
+
+
+#[ test ]
+//
+fn pong_timeout()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = config().build( tws ).expect( "valid config" );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = Vec::new();
+
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		drop( ws );
+
+		events.collect::<Vec<_>>().await
+	};
+
+
+	// Don't read until the timeout has passed, so the pings don't get answered.
+	//
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		Delay::new( Duration::from_millis( 100 ) ).await;
+
+		let frame = CloseFrame
+		{
+			code  : CloseCode::Away,
+			reason: "Pong timeout".into(),
+		};
+
+		assert_matches!( tws.next().await, Some(Ok( Message::Ping(_) )) );
+		assert_eq!( Some( Message::Close( Some(frame) ) ), tws.next().await.transpose().expect( "close" ) );
+
+		while tws.next().await.is_some() {}
+	};
+
+	let (events, _) = block_on( join( server, client ) );
+
+	assert_matches!( events[0], WsEvent::PongTimeout );
+}
+
+
+
+#[ test ]
+//
+fn requires_timer()
+{
+	let config = WsStreamConfig::new().keepalive( Duration::from_secs( 1 ), Duration::from_secs( 1 ) );
+
+	assert_matches!( config.validate( &Default::default() ), Err( WsErr::InvalidConfig{..} ) );
+}
@@ -0,0 +1,120 @@
+// Test WsMessageStream.
+//
+// ✔ whole messages are sent and received, including empty ones
+// ✔ close_with and close events work like on WsStream
+// ✔ messages bigger than the tungstenite write buffer are refused with InvalidData and WsErr::MessageTooLarge
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                                  } ,
+	futures               :: { StreamExt, SinkExt, executor::block_on, future::join                               } ,
+	async_tungstenite     :: { WebSocketStream                                                                    } ,
+	tungstenite           :: { protocol::{ CloseFrame, frame::coding::CloseCode, Role, WebSocketConfig }, Message } ,
+	pharos                :: { Observable, ObserveConfig                                                          } ,
+	futures_ringbuf       :: { Endpoint                                                                           } ,
+	assert_matches        :: { assert_matches                                                                     } ,
+	std                   :: { io                                                                                 } ,
+};
+
+
+
+#[ test ]
+//
+fn message_stream()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = WsMessageStream::new( tws );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+		let one = ws.next().await.expect( "message" ).expect( "no error" );
+		let two = ws.next().await.expect( "message" ).expect( "no error" );
+
+		ws.send( b"three".to_vec() ).await.expect( "send" );
+		ws.close_with( CloseCode::Normal, "bye" ).expect( "close_with" );
+		ws.close().await.expect( "close" );
+
+		while ws.next().await.is_some() {}
+
+		drop( ws );
+
+		( vec![ one, two ], events.collect::<Vec<_>>().await )
+	};
+
+
+	let client = async
+	{
+		let mut tws  = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut msgs = Vec::new();
+
+		tws.send( Message::Binary( "one".into() ) ).await.expect( "send" );
+		tws.send( Message::Binary( ""   .into() ) ).await.expect( "send" );
+
+		while let Some( Ok(msg) ) = tws.next().await
+		{
+			msgs.push( msg );
+		}
+
+		msgs
+	};
+
+	let ((received, events), msgs) = block_on( join( server, client ) );
+
+	let frame = CloseFrame
+	{
+		code  : CloseCode::Normal,
+		reason: "bye".into(),
+	};
+
+	assert_eq!( vec![ b"one".to_vec(), Vec::new() ], received );
+
+	assert_eq!
+	(
+		vec!
+		[
+			Message::Binary( "three".into()        ),
+			Message::Close ( Some( frame.clone() ) ),
+		],
+
+		msgs
+	);
+
+	assert_eq!( 2, events.len() );
+	assert_matches!( &events[0], WsEvent::CloseFrame( Some(f) ) if f == &frame );
+	assert_matches!( &events[1], WsEvent::Closed( _ ) );
+}
+
+
+
+#[ test ]
+//
+fn too_big()
+{
+	let (sc, _cs) = Endpoint::pair( 100, 100 );
+
+	block_on( async
+	{
+		let config = WebSocketConfig
+		{
+			write_buffer_size    : 0,
+			max_write_buffer_size: 16,
+			..Default::default()
+		};
+
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, Some( config ) ).await;
+		let mut ws  = WsMessageStream::new( tws );
+
+		let err = ws.send( vec![ 0; 32 ] ).await.expect_err( "message too big" );
+
+		assert_eq!( io::ErrorKind::InvalidData, err.kind() );
+
+		assert_matches!
+		(
+			err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() ),
+			Some( WsErr::MessageTooLarge{ size: 32, max_size: 16 } )
+		);
+	});
+}
@@ -0,0 +1,127 @@
+#![ cfg( feature = "metrics" ) ]
+
+// Test the metrics feature with a local debugging recorder.
+//
+// ✔ the open connections gauge goes up on creation and down when the connection closes
+// ✔ bytes in and out are counted
+// ✔ text rejections and close codes in both directions are counted
+// ✔ the labels from the config are added to every metric
+// ✔ a connection taken back with into_inner is no longer counted as open
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                        } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                                          } ,
+	tungstenite           :: { protocol::{ Role, frame::coding::CloseCode }, Message                    } ,
+	futures_ringbuf       :: { Endpoint                                                                 } ,
+	metrics_util          :: { debugging::{ DebuggingRecorder, DebugValue }, CompositeKey               } ,
+	metrics               :: { Unit, SharedString                                                       } ,
+};
+
+
+type Metrics = Vec<( CompositeKey, Option<Unit>, Option<SharedString>, DebugValue )>;
+
+
+
+// Find the value of the metric with the given name and extra labels. The configured label must always be present.
+//
+fn value<'a>( snapshot: &'a Metrics, name: &str, extra: &[(&str, &str)] ) -> Option<&'a DebugValue>
+{
+	snapshot.iter().find_map( |(key, _, _, value)|
+	{
+		let key    = key.key();
+		let labels = key.labels().map( |l| ( l.key(), l.value() ) ).collect::<Vec<_>>();
+
+		let matches = key.name() == name
+			&& labels.contains( &( "endpoint", "test" ) )
+			&& labels.len() == extra.len() + 1
+			&& extra.iter().all( |l| labels.contains( l ) )
+		;
+
+		matches.then_some( value )
+	})
+}
+
+
+
+#[ test ]
+//
+fn metrics()
+{
+	let recorder    = DebuggingRecorder::new();
+	let snapshotter = recorder.snapshotter();
+	let (sc, cs)    = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let     config = WsStreamConfig::new().metrics_labels([ ( "endpoint", "test" ) ]);
+		let mut ws     = config.build( tws ).expect( "valid config" );
+
+		let snapshot = snapshotter.snapshot().into_vec();
+
+		assert_eq!( Some( &DebugValue::Gauge( 1.0.into() ) ), value( &snapshot, "ws_stream_tungstenite_open_connections", &[] ) );
+
+		ws.write_all( b"hi" ).await.expect( "write" );
+
+		let mut bytes = Vec::new();
+
+		ws.read_to_end( &mut bytes ).await.expect( "read to end" );
+
+		assert_eq!( b"hello".to_vec(), bytes );
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( Message::Binary( "hello".into() ) ).await.expect( "send" );
+		tws.send( Message::Text  ( "text" .into() ) ).await.expect( "send" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	metrics::with_local_recorder( &recorder, || block_on( join( server, client ) ) );
+
+	// Taking a snapshot resets the values, so the gauge shows the change since the snapshot taken by the server.
+	// The rejected text message counts as received bytes too.
+	//
+	let snapshot = snapshotter.snapshot().into_vec();
+	let code     = u16::from( CloseCode::Unsupported ).to_string();
+	let sent     = [ ( "code", code.as_str() ), ( "direction", "sent"     ) ];
+	let recv     = [ ( "code", code.as_str() ), ( "direction", "received" ) ];
+
+	assert_eq!( Some( &DebugValue::Gauge  ( (-1.0).into() ) ), value( &snapshot, "ws_stream_tungstenite_open_connections"     , &[]    ) );
+	assert_eq!( Some( &DebugValue::Counter( 9             ) ), value( &snapshot, "ws_stream_tungstenite_bytes_received_total" , &[]    ) );
+	assert_eq!( Some( &DebugValue::Counter( 2             ) ), value( &snapshot, "ws_stream_tungstenite_bytes_sent_total"     , &[]    ) );
+	assert_eq!( Some( &DebugValue::Counter( 1             ) ), value( &snapshot, "ws_stream_tungstenite_text_rejections_total", &[]    ) );
+	assert_eq!( Some( &DebugValue::Counter( 0             ) ), value( &snapshot, "ws_stream_tungstenite_protocol_errors_total", &[]    ) );
+	assert_eq!( Some( &DebugValue::Counter( 1             ) ), value( &snapshot, "ws_stream_tungstenite_close_codes_total"    , &sent ) );
+	assert_eq!( Some( &DebugValue::Counter( 1             ) ), value( &snapshot, "ws_stream_tungstenite_close_codes_total"    , &recv ) );
+}
+
+
+
+#[ test ]
+//
+fn into_inner()
+{
+	let recorder    = DebuggingRecorder::new();
+	let snapshotter = recorder.snapshotter();
+	let (sc, _cs)   = Endpoint::pair( 100, 100 );
+
+	let _tws = metrics::with_local_recorder( &recorder, || block_on( async
+	{
+		let tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let config = WsStreamConfig::new().metrics_labels([ ( "endpoint", "test" ) ]);
+		let ws     = config.build( tws ).expect( "valid config" );
+
+		ws.into_inner()
+	}));
+
+	let snapshot = snapshotter.snapshot().into_vec();
+
+	assert_eq!( Some( &DebugValue::Gauge( 0.0.into() ) ), value( &snapshot, "ws_stream_tungstenite_open_connections", &[] ) );
+}
@@ -0,0 +1,527 @@
+#![ cfg( feature = "mux" ) ]
+
+// Test Mux and Substream over an in memory connection.
+//
+// ✔ a substream opened by the client is accepted by the server, data flows both ways and close gives EOF
+// ✔ both sides can open several substreams at the same time
+// ✔ a substream nobody reads doesn't block the others
+// ✔ reset and dropping a substream before closing it give ConnectionReset on the remote
+// ✔ all errors of substreams wrap a WsErr
+// ✔ substreams that are open when the connection ends give ConnectionAborted
+// ✔ an invalid frame fails all substreams with InvalidData and ends the mux
+// ✔ an open with an id of the other side is answered with a reset
+// ✔ an open with an id that is in use fails all substreams with InvalidData and ends the mux
+// ✔ the remote can't open more substreams than max_streams
+// ✔ a remote that floods us with opens we refuse, without reading the resets, can't make us queue them without limit
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                        } ,
+	futures               :: { AsyncReadExt, AsyncWriteExt, StreamExt                   } ,
+	futures               :: { executor::block_on, future::{ join, join3, select, Either } } ,
+	futures               :: { executor::LocalPool, task::LocalSpawnExt                 } ,
+	async_tungstenite     :: { WebSocketStream                                          } ,
+	tungstenite           :: { protocol::Role, Message                                  } ,
+	futures_ringbuf       :: { Endpoint                                                 } ,
+	std                   :: { io, rc::Rc, cell::Cell                                   } ,
+};
+
+
+type TestMux = Mux< WebSocketStream<Endpoint> >;
+
+
+async fn pair( config: MuxConfig ) -> ( TestMux, TestMux )
+{
+	let (sc, cs) = Endpoint::pair( 1000, 1000 );
+
+	let server = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+	let client = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+	(
+		Mux::with_config( WsMessageStream::new( server ), Role::Server, config ),
+		Mux::with_config( WsMessageStream::new( client ), Role::Client, config ),
+	)
+}
+
+
+fn ws_err( err: &io::Error ) -> Option<&WsErr>
+{
+	err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() )
+}
+
+
+// A client mux with a plain websocket as the server, so tests can send any frame they like.
+//
+async fn raw_pair( config: MuxConfig ) -> ( WebSocketStream<Endpoint>, TestMux )
+{
+	let (sc, cs) = Endpoint::pair( 1000, 1000 );
+
+	let server = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+	let client = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+	( server, Mux::with_config( WsMessageStream::new( client ), Role::Client, config ) )
+}
+
+
+// Read frames from a plain websocket until one of `kind` comes in for substream `id`.
+//
+async fn expect_frame( ws: &mut WebSocketStream<Endpoint>, id: u32, kind: u8 )
+{
+	while let Some( msg ) = ws.next().await
+	{
+		let msg = msg.expect( "message" ).into_data();
+
+		if msg.len() >= 5 && msg[ ..4 ] == id.to_be_bytes() && msg[4] == kind
+		{
+			return;
+		}
+	}
+
+	panic!( "the connection ended before frame {kind} for substream {id} came in" );
+}
+
+
+const CREDIT: u8 = 2;
+const RESET : u8 = 4;
+
+
+// Close the connection from the client and wait for both sides to end.
+//
+async fn shutdown( mut server: TestMux, mut client: TestMux )
+{
+	let client = async move
+	{
+		client.close().await.expect( "close mux" );
+		while client.next().await.is_some() {}
+	};
+
+	let server = async move
+	{
+		while server.next().await.is_some() {}
+	};
+
+	join( client, server ).await;
+}
+
+
+
+#[ test ]
+//
+fn open_accept()
+{
+	block_on( async
+	{
+		let (mut server, client) = pair( MuxConfig::default() ).await;
+
+		let opener = async
+		{
+			let mut stream = client.open().expect( "open" );
+			let mut buf    = Vec::new();
+
+			assert_eq!( 1, stream.id() );
+
+			stream.write_all( b"hello" ).await.expect( "write" );
+			stream.close().await.expect( "close" );
+			stream.read_to_end( &mut buf ).await.expect( "read" );
+
+			buf
+		};
+
+		let acceptor = async
+		{
+			let mut stream = server.next().await.expect( "accept" );
+			let mut buf    = Vec::new();
+
+			assert_eq!( 1, stream.id() );
+
+			stream.read_to_end( &mut buf ).await.expect( "read" );
+			stream.write_all( b"world" ).await.expect( "write" );
+			stream.close().await.expect( "close" );
+
+			buf
+		};
+
+		let (from_server, from_client) = join( opener, acceptor ).await;
+
+		assert_eq!( b"hello".to_vec(), from_client );
+		assert_eq!( b"world".to_vec(), from_server );
+
+		shutdown( server, client ).await;
+	});
+}
+
+
+
+#[ test ]
+//
+fn many()
+{
+	// Open substreams, send their id and read back what the other side echoes.
+	//
+	async fn open( mux: &TestMux, count: usize ) -> Vec<( u32, Vec<u8> )>
+	{
+		let mut results = Vec::new();
+
+		for _ in 0..count
+		{
+			let mut stream = mux.open().expect( "open" );
+			let mut buf    = Vec::new();
+
+			stream.write_all( &stream.id().to_be_bytes() ).await.expect( "write" );
+			stream.close().await.expect( "close" );
+			stream.read_to_end( &mut buf ).await.expect( "read" );
+
+			results.push(( stream.id(), buf ));
+		}
+
+		results
+	}
+
+	// Echo incoming substreams until the connection ends.
+	//
+	async fn echo( mut mux: TestMux )
+	{
+		while let Some( mut stream ) = mux.next().await
+		{
+			let mut buf = Vec::new();
+
+			stream.read_to_end( &mut buf ).await.expect( "read" );
+			stream.write_all( &buf ).await.expect( "write" );
+			stream.close().await.expect( "close" );
+		}
+	}
+
+
+	block_on( async
+	{
+		let (server, client) = pair( MuxConfig::default() ).await;
+
+		// A tungstenite client waits for the server to drop the connection after the close handshake, so
+		// each handle must be dropped when its future is done.
+		//
+		let opened =
+		{
+			let server = server.clone();
+			let client = client.clone();
+
+			async move
+			{
+				let results = join( open( &server, 2 ), open( &client, 3 ) ).await;
+
+				client.close().await.expect( "close mux" );
+
+				results
+			}
+		};
+
+		let ((from_server, from_client), _, _) = join3( opened, echo( server ), echo( client ) ).await;
+
+		let expect = |ids: &[u32]| ids.iter().map( |id| (*id, id.to_be_bytes().to_vec()) ).collect::<Vec<_>>();
+
+		assert_eq!( expect( &[ 2, 4    ] ), from_server );
+		assert_eq!( expect( &[ 1, 3, 5 ] ), from_client );
+	});
+}
+
+
+
+#[ test ]
+//
+fn slow_substream()
+{
+	block_on( async
+	{
+		let config = MuxConfig::new().window( 16 ).max_frame_size( 8 );
+
+		let (mut server, client) = pair( config ).await;
+
+		let mut slow = client.open().expect( "open" );
+		let mut fast = client.open().expect( "open" );
+
+		// The slow substream has more data than fits in the window, so this waits until the server reads it.
+		//
+		let write_slow = async
+		{
+			slow.write_all( &[ 1; 100 ] ).await.expect( "write" );
+			slow.close().await.expect( "close" );
+		};
+
+		let write_fast = async
+		{
+			fast.write_all( &[ 2; 100 ] ).await.expect( "write" );
+			fast.close().await.expect( "close" );
+		};
+
+		let read = async
+		{
+			let mut slow = server.next().await.expect( "accept" );
+			let mut fast = server.next().await.expect( "accept" );
+
+			let mut buf_fast = Vec::new();
+			let mut buf_slow = Vec::new();
+
+			// Read all of the fast one before starting on the slow one.
+			//
+			fast.read_to_end( &mut buf_fast ).await.expect( "read" );
+			slow.read_to_end( &mut buf_slow ).await.expect( "read" );
+
+			( buf_slow, buf_fast, slow, fast )
+		};
+
+		let (_, _, (buf_slow, buf_fast, slow_in, fast_in)) = join3( write_slow, write_fast, read ).await;
+
+		assert_eq!( vec![ 1; 100 ], buf_slow );
+		assert_eq!( vec![ 2; 100 ], buf_fast );
+
+		drop(( slow, fast, slow_in, fast_in ));
+
+		shutdown( server, client ).await;
+	});
+}
+
+
+
+#[ test ]
+//
+fn reset()
+{
+	block_on( async
+	{
+		let (mut server, client) = pair( MuxConfig::default() ).await;
+
+		let mut reset   = client.open().expect( "open" );
+		let     dropped = client.open().expect( "open" );
+
+		let opener = async
+		{
+			reset.write_all( b"a" ).await.expect( "write" );
+
+			drop( dropped );
+
+			let err = reset.read( &mut [0; 10] ).await.expect_err( "reset" );
+			assert_eq!( io::ErrorKind::ConnectionReset, err.kind() );
+			assert!( matches!( ws_err( &err ), Some( WsErr::Reset ) ) );
+
+			let err = reset.write( b"c" ).await.expect_err( "reset" );
+			assert_eq!( io::ErrorKind::ConnectionReset, err.kind() );
+			assert!( matches!( ws_err( &err ), Some( WsErr::Reset ) ) );
+		};
+
+		let acceptor = async
+		{
+			let mut reset   = server.next().await.expect( "accept" );
+			let mut dropped = server.next().await.expect( "accept" );
+
+			let mut buf = [0; 1];
+
+			let err = dropped.read( &mut buf ).await.expect_err( "dropped" );
+			assert_eq!( io::ErrorKind::ConnectionReset, err.kind() );
+			assert!( matches!( ws_err( &err ), Some( WsErr::Reset ) ) );
+
+			reset.read_exact( &mut buf ).await.expect( "read" );
+			reset.reset();
+
+			let err = reset.read( &mut buf ).await.expect_err( "reset" );
+			assert_eq!( io::ErrorKind::ConnectionReset, err.kind() );
+			assert!( matches!( ws_err( &err ), Some( WsErr::Reset ) ) );
+
+			// Keep the mux going so the reset gets sent.
+			//
+			server
+		};
+
+		let (_, server) = join( opener, acceptor ).await;
+
+		shutdown( server, client ).await;
+	});
+}
+
+
+
+#[ test ]
+//
+fn connection_ends()
+{
+	block_on( async
+	{
+		let (mut server, mut client) = pair( MuxConfig::default() ).await;
+
+		let mut stream = client.open().expect( "open" );
+
+		let (_, accepted) = join( stream.write_all( b"a" ), server.next() ).await;
+
+		// A tungstenite client waits for the server to drop the connection after the close handshake.
+		//
+		let closer = async move
+		{
+			server.close().await.expect( "close mux" );
+			while server.next().await.is_some() {}
+
+			drop(( accepted, server ));
+		};
+
+		let reader = async
+		{
+			let err = stream.read( &mut [0; 10] ).await.expect_err( "ended" );
+			assert_eq!( io::ErrorKind::ConnectionAborted, err.kind() );
+			assert!( matches!( ws_err( &err ), Some( WsErr::Aborted ) ) );
+
+			let err = stream.write( b"b" ).await.expect_err( "ended" );
+			assert_eq!( io::ErrorKind::NotConnected, err.kind() );
+			assert!( matches!( ws_err( &err ), Some( WsErr::Closed ) ) );
+
+			assert!( client.next().await.is_none() );
+			assert!( client.open().is_err() );
+		};
+
+		join( closer, reader ).await;
+	});
+}
+
+
+
+#[ test ]
+//
+fn invalid_frame()
+{
+	block_on( async
+	{
+		let (sc, cs) = Endpoint::pair( 1000, 1000 );
+
+		let mut server = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let     client = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut client = Mux::new( WsMessageStream::new( client ), Role::Client );
+
+		let mut stream = client.open().expect( "open" );
+
+		server.send( Message::Binary( vec![ 0, 0, 0, 1, 9 ] ) ).await.expect( "send" );
+
+		assert!( client.next().await.is_none() );
+
+		let err = stream.read( &mut [0; 10] ).await.expect_err( "invalid frame" );
+
+		assert_eq!( io::ErrorKind::InvalidData, err.kind() );
+		assert!( matches!( ws_err( &err ), Some( WsErr::Protocol ) ) );
+	});
+}
+
+
+
+#[ test ]
+//
+fn open_wrong_id()
+{
+	block_on( async
+	{
+		let (mut server, mut client) = raw_pair( MuxConfig::default() ).await;
+
+		// Odd ids are for the client.
+		//
+		server.send( Message::Binary( vec![ 0, 0, 0, 1, 0, 0, 0, 0, 16 ] ) ).await.expect( "send" );
+
+		match select( client.next(), Box::pin( expect_frame( &mut server, 1, RESET ) ) ).await
+		{
+			Either::Left (_) => panic!( "accepted a substream with a wrong id" ),
+			Either::Right(_) => {}
+		};
+	});
+}
+
+
+
+#[ test ]
+//
+fn open_duplicate_id()
+{
+	block_on( async
+	{
+		let (mut server, mut client) = raw_pair( MuxConfig::default() ).await;
+
+		let open = vec![ 0, 0, 0, 2, 0, 0, 0, 0, 16 ];
+
+		server.send( Message::Binary( open.clone() ) ).await.expect( "send" );
+
+		let mut stream = client.next().await.expect( "accept" );
+
+		server.send( Message::Binary( open ) ).await.expect( "send" );
+
+		assert!( client.next().await.is_none() );
+
+		let err = stream.read( &mut [0; 10] ).await.expect_err( "duplicate open" );
+
+		assert_eq!( io::ErrorKind::InvalidData, err.kind() );
+		assert!( matches!( ws_err( &err ), Some( WsErr::Protocol ) ) );
+	});
+}
+
+
+
+#[ test ]
+//
+fn max_streams()
+{
+	let open = |id: u8| Message::Binary( vec![ 0, 0, 0, id, 0, 0, 0, 0, 16 ] );
+
+	block_on( async
+	{
+		let (mut server, mut client) = raw_pair( MuxConfig::new().max_streams( 1 ) ).await;
+
+		server.send( open( 2 ) ).await.expect( "send" );
+		server.send( open( 4 ) ).await.expect( "send" );
+
+		let (accepted, _) = join( client.next(), expect_frame( &mut server, 4, RESET ) ).await;
+
+		// Once the remote has a substream less, it can open another one.
+		//
+		drop( accepted.expect( "accept" ) );
+
+		server.send( open( 6 ) ).await.expect( "send" );
+
+		let (accepted, _) = join( client.next(), expect_frame( &mut server, 6, CREDIT ) ).await;
+
+		assert_eq!( 6, accepted.expect( "accept" ).id() );
+	});
+}
+
+
+
+#[ test ]
+//
+fn open_flood()
+{
+	const COUNT: u32 = 10_000;
+
+	let mut pool    = LocalPool::new();
+	let     spawner = pool.spawner();
+	let     sent    = Rc::new( Cell::new( 0 ) );
+	let     sent2   = sent.clone();
+
+	let (mut server, mut client) = pool.run_until( raw_pair( MuxConfig::new().max_streams( 0 ) ) );
+
+	// The server never reads the resets we answer with.
+	//
+	let flood = async move
+	{
+		for i in 0..COUNT
+		{
+			let id = ( 2 + 2 * i ).to_be_bytes();
+
+			server.send( Message::Binary( vec![ id[0], id[1], id[2], id[3], 0, 0, 0, 0, 16 ] ) ).await.expect( "send" );
+			sent2.set( i + 1 );
+		}
+	};
+
+	let accept = async move
+	{
+		while client.next().await.is_some() {}
+	};
+
+	spawner.spawn_local( flood  ).expect( "spawn" );
+	spawner.spawn_local( accept ).expect( "spawn" );
+
+	pool.run_until_stalled();
+
+	// Once the resets fill the control queue, the mux stops reading, so the server can't send all opens.
+	//
+	assert!( sent.get() < COUNT, "the mux kept reading while it couldn't send the resets" );
+}
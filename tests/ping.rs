@@ -0,0 +1,189 @@
+// Test WsStream::ping.
+//
+// ✔ ping resolves with the round trip time once the pong comes in
+// ✔ observers get the round trip time as WsEvent::Rtt
+// ✔ unmatched pongs are still reported as WsEvent::Pong
+// ✔ ping on a closed connection returns WsErr::Closed
+// ✔ ping returns WsErr::TooManyPings when 16 pings wait for a pong
+// ✔ pings nobody waits for anymore are forgotten, so ping works again
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                  } ,
+	futures               :: { StreamExt, SinkExt, AsyncReadExt, executor::block_on, future::join } ,
+	futures               :: { future::join_all                                                   } ,
+	async_tungstenite     :: { WebSocketStream                                                    } ,
+	tungstenite           :: { protocol::{ frame::coding::CloseCode, Role }, Message              } ,
+	pharos                :: { Observable, ObserveConfig                                          } ,
+	futures_ringbuf       :: { Endpoint                                                           } ,
+	assert_matches        :: { assert_matches                                                     } ,
+};
+
+
+
+#[ test ]
+//
+fn ping()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = WsStream::new( tws );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let     rtt    = ws.ping();
+		let mut buf    = Vec::new();
+
+		let (rtt, read) = join( rtt, ws.read_to_end( &mut buf ) ).await;
+
+		read.expect( "read to end" );
+		drop( ws );
+
+		( rtt, events.collect::<Vec<_>>().await )
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		assert_matches!( tws.next().await, Some(Ok( Message::Ping(_) )) );
+
+		// Make sure tungstenite sends out the answer before we send our own pong, otherwise it
+		// would replace it.
+		//
+		SinkExt::flush( &mut tws ).await.expect( "flush" );
+
+		tws.send( Message::Pong( vec![ 9 ] ) ).await.expect( "send pong" );
+		tws.close( None ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	let ((rtt, events), _) = block_on( join( server, client ) );
+
+	let rtt = rtt.expect( "round trip time" );
+
+	assert_eq!( 4, events.len() );
+	assert_matches!(  events[0], WsEvent::Rtt( d ) if d == rtt );
+	assert_matches!( &events[1], WsEvent::Pong( data ) if data == &[ 9 ] );
+	assert_matches!(  events[2], WsEvent::CloseFrame( None ) );
+	assert_matches!( &events[3], WsEvent::Closed( _ ) );
+}
+
+
+
+#[ test ]
+//
+fn ping_closed()
+{
+	let (sc, _cs) = Endpoint::pair( 100, 100 );
+
+	block_on( async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStream::new( tws );
+
+		ws.close_with( CloseCode::Normal, "bye" ).expect( "close_with" );
+
+		assert_matches!( ws.ping().await, Err( WsErr::Closed ) );
+	});
+}
+
+
+
+#[ test ]
+//
+fn too_many_pings()
+{
+	let (sc, cs) = Endpoint::pair( 1000, 1000 );
+
+	let server = async
+	{
+		let     tws   = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws    = WsStream::new( tws );
+		let     pings = ( 0..16 ).map( |_| ws.ping() ).collect::<Vec<_>>();
+		let mut buf   = Vec::new();
+
+		assert_matches!( ws.ping().await, Err( WsErr::TooManyPings ) );
+
+		// Once answered, all of them resolve.
+		//
+		let (rtts, read) = join( join_all( pings ), ws.read_to_end( &mut buf ) ).await;
+
+		read.expect( "read to end" );
+		assert!( rtts.iter().all( Result::is_ok ) );
+	};
+
+
+	let client = async
+	{
+		let mut tws   = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut count = 0;
+
+		while count < 16
+		{
+			if let Some(Ok( Message::Ping(_) )) = tws.next().await
+			{
+				count += 1;
+			}
+		}
+
+		SinkExt::flush( &mut tws ).await.expect( "flush" );
+		tws.close( None ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn abandoned_pings()
+{
+	let (sc, cs) = Endpoint::pair( 1000, 1000 );
+
+	let server = async
+	{
+		let     tws   = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws    = WsStream::new( tws );
+		let     pings = ( 0..16 ).map( |_| ws.ping() ).collect::<Vec<_>>();
+		let mut buf   = Vec::new();
+
+		assert_matches!( ws.ping().await, Err( WsErr::TooManyPings ) );
+
+		// Nobody waits for the pong of these anymore.
+		//
+		drop( pings );
+
+		let ping = ws.ping();
+
+		let (rtt, read) = join( ping, ws.read_to_end( &mut buf ) ).await;
+
+		read.expect( "read to end" );
+		assert!( rtt.is_ok() );
+
+		// The abandoned pings were never sent.
+		//
+		assert_eq!( 1, ws.stats().pings_sent );
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		while !matches!( tws.next().await, Some(Ok( Message::Ping(_) )) ) {}
+
+		SinkExt::flush( &mut tws ).await.expect( "flush" );
+		tws.close( None ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	block_on( join( server, client ) );
+}
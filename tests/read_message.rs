@@ -0,0 +1,70 @@
+// Test WsStream::read_message.
+//
+// ✔ messages are returned whole, including empty ones
+// ✔ the remainder of a message partially consumed through AsyncRead is returned first
+// ✔ None is returned when the connection closes and close frames are reported as usual
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                         } ,
+	futures               :: { StreamExt, AsyncReadExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                           } ,
+	tungstenite           :: { protocol::Role, Message                                   } ,
+	pharos                :: { Observable, ObserveConfig                                 } ,
+	futures_ringbuf       :: { Endpoint                                                  } ,
+	assert_matches        :: { assert_matches                                            } ,
+};
+
+
+
+#[ test ]
+//
+fn read_message()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = WsStream::new( tws );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut start  = [ 0u8; 2 ];
+
+		ws.read_exact( &mut start ).await.expect( "read exact" );
+
+		assert_eq!( b"he", &start );
+
+		let mut msgs = Vec::new();
+
+		while let Some( msg ) = ws.read_message().await.expect( "read message" )
+		{
+			msgs.push( msg );
+		}
+
+		drop( ws );
+
+		( msgs, events.collect::<Vec<_>>().await )
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( Message::Binary( "hello".into() ) ).await.expect( "send" );
+		tws.send( Message::Binary( ""     .into() ) ).await.expect( "send" );
+		tws.send( Message::Binary( "world".into() ) ).await.expect( "send" );
+
+		tws.close( None ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	let ((msgs, events), _) = block_on( join( server, client ) );
+
+	assert_eq!( vec![ b"llo".to_vec(), Vec::new(), b"world".to_vec() ], msgs );
+
+	assert_eq!( 2, events.len() );
+	assert_matches!( events[0], WsEvent::CloseFrame( None ) );
+	assert_matches!( events[1], WsEvent::Closed( _ ) );
+}
@@ -0,0 +1,420 @@
+#![ cfg( feature = "resume" ) ]
+
+// Test ResumableWsStream over in memory connections that we can drop at any time.
+//
+// ✔ data arrives in order and only once when connections drop in both directions, and close ends the stream
+// ✔ the sender keeps to the window of the receiver when that is smaller than its own
+// ✔ an error from the connect closure is returned and ends the stream
+// ✔ when the server lost the session, the client fails with SessionLost
+// ✔ a connection that doesn't start with a hello is refused
+// ✔ a remote that sends more data than we accept fails the stream with a protocol error
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                        } ,
+	futures               :: { AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt       } ,
+	futures               :: { StreamExt, channel::mpsc                                 } ,
+	futures               :: { executor::block_on, future::{ join, select }             } ,
+	async_tungstenite     :: { WebSocketStream                                          } ,
+	tungstenite           :: { protocol::Role                                           } ,
+	futures_ringbuf       :: { Endpoint                                                 } ,
+	std                   :: { io, pin::Pin, task::{ Context, Poll, Waker }             } ,
+	std                   :: { sync::{ Arc, Mutex, atomic::{ AtomicBool, Ordering } }  } ,
+	std                   :: { future::Future                                           } ,
+};
+
+
+type Ws = WsStream< WebSocketStream<Killable> >;
+
+
+// Lets the test drop a connection. Both ends fail from then on.
+//
+#[ derive( Default ) ]
+//
+struct Kill
+{
+	dead  : AtomicBool       ,
+	wakers: Mutex<Vec<Waker>>,
+}
+
+
+impl Kill
+{
+	fn kill( &self )
+	{
+		self.dead.store( true, Ordering::SeqCst );
+
+		self.wakers.lock().expect( "lock" ).drain(..).for_each( Waker::wake );
+	}
+
+
+	fn check( &self, cx: &mut Context<'_> ) -> io::Result<()>
+	{
+		if self.dead.load( Ordering::SeqCst )
+		{
+			return Err( io::ErrorKind::ConnectionReset.into() );
+		}
+
+		self.wakers.lock().expect( "lock" ).push( cx.waker().clone() );
+
+		Ok(())
+	}
+}
+
+
+struct Killable
+{
+	inner: Endpoint ,
+	kill : Arc<Kill>,
+}
+
+
+impl AsyncRead for Killable
+{
+	fn poll_read( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		self.kill.check( cx )?;
+		Pin::new( &mut self.inner ).poll_read( cx, buf )
+	}
+}
+
+
+impl AsyncWrite for Killable
+{
+	fn poll_write( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		self.kill.check( cx )?;
+		Pin::new( &mut self.inner ).poll_write( cx, buf )
+	}
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		self.kill.check( cx )?;
+		Pin::new( &mut self.inner ).poll_flush( cx )
+	}
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		self.kill.check( cx )?;
+		Pin::new( &mut self.inner ).poll_close( cx )
+	}
+}
+
+
+// Creates connections. The server ends go to the receiver returned by `Net::new`.
+//
+#[ derive( Clone ) ]
+//
+struct Net
+{
+	incoming: mpsc::UnboundedSender<Ws>,
+	kills   : Arc<Mutex<Vec<Arc<Kill>>>>,
+}
+
+
+impl Net
+{
+	fn new() -> ( Self, mpsc::UnboundedReceiver<Ws> )
+	{
+		let (tx, rx) = mpsc::unbounded();
+
+		( Self{ incoming: tx, kills: Default::default() }, rx )
+	}
+
+
+	// Drop the last connection.
+	//
+	fn kill( &self )
+	{
+		self.kills.lock().expect( "lock" ).last().expect( "a connection" ).kill();
+	}
+
+
+	fn connections( &self ) -> usize
+	{
+		self.kills.lock().expect( "lock" ).len()
+	}
+
+
+	fn connect( &self ) -> impl Future< Output = Result<Ws, WsErr> > + Send + 'static
+	{
+		let net = self.clone();
+
+		async move
+		{
+			let (sc, cs) = Endpoint::pair( 64 * 1024, 64 * 1024 );
+			let kill     = Arc::new( Kill::default() );
+
+			net.kills.lock().expect( "lock" ).push( kill.clone() );
+
+			let server = WebSocketStream::from_raw_socket( Killable{ inner: sc, kill: kill.clone() }, Role::Server, None ).await;
+			let client = WebSocketStream::from_raw_socket( Killable{ inner: cs, kill               }, Role::Client, None ).await;
+
+			net.incoming.unbounded_send( WsStream::new( server ) ).map_err( |_| WsErr::Closed )?;
+
+			Ok( WsStream::new( client ) )
+		}
+	}
+}
+
+
+
+#[ test ]
+//
+fn resume()
+{
+	let (net, mut incoming) = Net::new();
+
+	let data: Vec<u8> = ( 0..100_000u32 ).map( |i| ( i % 251 ) as u8 ).collect();
+
+	let client = async
+	{
+		let connect    = net.clone();
+		let mut client = ResumableWsStream::connect( move || connect.connect() );
+
+		for (i, chunk) in data.chunks( 1000 ).enumerate()
+		{
+			client.write_all( chunk ).await.expect( "write" );
+			client.flush().await.expect( "flush" );
+
+			if i == 30 || i == 60
+			{
+				net.kill();
+			}
+		}
+
+		client.close().await.expect( "close" );
+
+		let mut echo = Vec::new();
+		client.read_to_end( &mut echo ).await.expect( "read" );
+
+		echo
+	};
+
+	// Echo the data, dropping the connection halfway through reading and writing.
+	//
+	let server = async
+	{
+		let first = ResumeRequest::read( incoming.next().await.expect( "connection" ) ).await.expect( "hello" );
+
+		let (tx, rx) = mpsc::unbounded();
+
+		let forward = async move
+		{
+			while let Some( ws ) = incoming.next().await
+			{
+				tx.unbounded_send( ResumeRequest::read( ws ).await.expect( "hello" ) ).expect( "send request" );
+			}
+		};
+
+		let echo = async
+		{
+			let mut server = ResumableWsStream::accept( first, rx ).max_unacked( 4096 );
+			let mut buf    = vec![ 0; data.len() ];
+			let half       = data.len() / 2;
+
+			server.read_exact( &mut buf[ ..half ] ).await.expect( "read" );
+			net.kill();
+			server.read_exact( &mut buf[ half.. ] ).await.expect( "read" );
+
+			assert_eq!( 0, server.read( &mut [0; 10] ).await.expect( "end" ) );
+
+			server.write_all( &buf[ ..half ] ).await.expect( "write" );
+			server.flush().await.expect( "flush" );
+			net.kill();
+			server.write_all( &buf[ half.. ] ).await.expect( "write" );
+			server.close().await.expect( "close" );
+
+			buf
+		};
+
+		match select( Box::pin( forward ), Box::pin( echo ) ).await
+		{
+			futures::future::Either::Right(( buf, _ )) => buf,
+			futures::future::Either::Left (_)          => unreachable!( "the client ended first" ),
+		}
+	};
+
+	let (echo, received) = block_on( join( client, server ) );
+
+	assert_eq!( data, received );
+	assert_eq!( data, echo     );
+	assert!( net.connections() > 2 );
+}
+
+
+
+#[ test ]
+//
+fn give_up()
+{
+	let (net, mut incoming) = Net::new();
+
+	let client = async
+	{
+		let connect    = net.clone();
+		let mut first  = true;
+
+		let mut client = ResumableWsStream::connect( move ||
+		{
+			let next = connect.connect();
+			let ok   = std::mem::take( &mut first );
+
+			async move
+			{
+				if ok { next.await } else { Err( WsErr::Closed ) }
+			}
+		});
+
+		client.write_all( b"hello" ).await.expect( "write" );
+		client.flush().await.expect( "flush" );
+
+		net.kill();
+
+		client.write_all( b"world" ).await.expect( "write" );
+		let err = client.flush().await.expect_err( "gave up" );
+
+		assert_eq!( io::ErrorKind::NotConnected, err.kind() );
+		assert!( matches!( err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() ), Some( WsErr::Closed ) ) );
+
+		let err = client.write( b"!" ).await.expect_err( "ended" );
+		assert_eq!( io::ErrorKind::NotConnected, err.kind() );
+	};
+
+	let server = async
+	{
+		let request    = ResumeRequest::read( incoming.next().await.expect( "connection" ) ).await.expect( "hello" );
+		let mut server = ResumableWsStream::accept( request, futures::stream::empty() );
+		let mut buf    = [0; 10];
+
+		// The connection drops and there are no new requests. Whether "hello" made it before that depends
+		// on timing.
+		//
+		let err = loop
+		{
+			match server.read( &mut buf ).await
+			{
+				Ok ( n   ) => assert!( n > 0 ),
+				Err( err ) => break err,
+			}
+		};
+
+		assert_eq!( io::ErrorKind::NotConnected, err.kind() );
+	};
+
+	block_on( join( client, server ) );
+}
+
+
+
+#[ test ]
+//
+fn session_lost()
+{
+	let (net, mut incoming) = Net::new();
+
+	let client = async
+	{
+		let connect    = net.clone();
+		let mut client = ResumableWsStream::connect( move || connect.connect() ).max_unacked( 4 );
+
+		// The last byte only fits once the server acknowledged the rest.
+		//
+		client.write_all( b"hello" ).await.expect( "write" );
+
+		let err = client.flush().await.expect_err( "session lost" );
+
+		assert_eq!( io::ErrorKind::ConnectionAborted, err.kind() );
+		assert!( matches!( err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() ), Some( WsErr::SessionLost ) ) );
+	};
+
+	// The server forgets the session after reading from it, so it starts a new one when the client reconnects.
+	//
+	let server = async
+	{
+		let request    = ResumeRequest::read( incoming.next().await.expect( "connection" ) ).await.expect( "hello" );
+		let session    = request.session_id();
+		let mut server = ResumableWsStream::accept( request, futures::stream::empty() );
+
+		server.read_exact( &mut [0; 4] ).await.expect( "read" );
+		drop( server );
+
+		let request = ResumeRequest::read( incoming.next().await.expect( "connection" ) ).await.expect( "hello" );
+		assert_eq!( session, request.session_id() );
+
+		let mut server = ResumableWsStream::accept( request, futures::stream::empty() );
+		let _          = server.read( &mut [0; 4] ).await;
+	};
+
+	block_on( join( client, server ) );
+}
+
+
+
+#[ test ]
+//
+fn no_hello()
+{
+	block_on( async
+	{
+		let (net, mut incoming) = Net::new();
+
+		let mut client = net.connect().await.expect( "connect" );
+
+		client.write_all( &[ 9; 64 ] ).await.expect( "write" );
+		client.flush().await.expect( "flush" );
+
+		let err = ResumeRequest::read( incoming.next().await.expect( "connection" ) ).await.expect_err( "no hello" );
+
+		assert!( matches!( err, WsErr::Protocol ) );
+	});
+}
+
+
+
+#[ test ]
+//
+fn flood()
+{
+	block_on( async
+	{
+		let (net, mut incoming) = Net::new();
+
+		let mut client = net.connect().await.expect( "connect" );
+
+		// A hello for a new session that received nothing and accepts 1KiB, followed by more data than the
+		// server accepts, without waiting for acknowledgements.
+		//
+		let mut frames = vec![ 0 ];
+
+		frames.extend( [ 1; 16 ] );
+		frames.extend( 0u64   .to_be_bytes() );
+		frames.extend( 0u64   .to_be_bytes() );
+		frames.extend( 1024u64.to_be_bytes() );
+
+		for _ in 0..4
+		{
+			frames.push( 1 );
+			frames.extend( 512u32.to_be_bytes() );
+			frames.extend( [ 7; 512 ] );
+		}
+
+		client.write_all( &frames ).await.expect( "write" );
+		client.flush().await.expect( "flush" );
+
+		let request    = ResumeRequest::read( incoming.next().await.expect( "connection" ) ).await.expect( "hello" );
+		let mut server = ResumableWsStream::accept( request, futures::stream::empty() ).max_unacked( 1024 );
+
+		let err = loop
+		{
+			match server.read( &mut [0; 100] ).await
+			{
+				Ok ( n   ) => assert!( n > 0 ),
+				Err( err ) => break err,
+			}
+		};
+
+		assert_eq!( io::ErrorKind::InvalidData, err.kind() );
+		assert!( matches!( err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() ), Some( WsErr::Protocol ) ) );
+	});
+}
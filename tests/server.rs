@@ -0,0 +1,193 @@
+#![ cfg( feature = "server" ) ]
+
+// The callback returns the error response of tungstenite, which we don't choose.
+//
+#![ allow( clippy::result_large_err ) ]
+
+// Test WsListener on localhost.
+//
+// ✔ accepted connections come with the request uri, headers, selected subprotocol and addresses, also on the WsStream
+// ✔ the callback can reject a request
+// ✔ a connection that never sends an upgrade request times out
+// ✔ no new connections are taken while max_handshakes are in progress
+// ✔ the listener ends when the incoming stream ends
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, future::join, stream     } ,
+	async_std             :: { net::{ TcpListener, TcpStream }                                  } ,
+	async_tungstenite     :: { async_std::connect_async                                         } ,
+	tungstenite           :: { client::IntoClientRequest, http::{ Response, StatusCode }, Error } ,
+	assert_matches        :: { assert_matches                                                   } ,
+	std                   :: { time::Duration, io                                               } ,
+};
+
+
+fn timer() -> Timer
+{
+	Timer::new( futures_timer::Delay::new )
+}
+
+
+
+#[ async_std::test ]
+//
+async fn accept()
+{
+	let tcp  = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind" );
+	let addr = tcp.local_addr().expect( "local addr" );
+
+	let server = async
+	{
+		let mut listener = WsListener::new( tcp.incoming() )
+
+			.addrs( |tcp: &TcpStream| ( tcp.peer_addr().ok(), tcp.local_addr().ok() ) )
+
+			.callback( |_request, mut response|
+			{
+				response.headers_mut().insert( "sec-websocket-protocol", "chat".parse().expect( "header value" ) );
+				Ok( response )
+			})
+		;
+
+		let (mut ws, info) = listener.next().await.expect( "some" ).expect( "handshake" );
+
+		assert_eq!( "/room?id=1"  , info.uri                    );
+		assert_eq!( Some( "chat" ), info.subprotocol.as_deref() );
+		assert_eq!( "yes"         , info.headers[ "x-test" ]    );
+		assert_eq!( Some( addr )  , info.local_addr             );
+		assert!   ( info.peer_addr.is_some()                    );
+
+		// The WsStream carries the same info.
+		//
+		assert_eq!( Some( &info.uri ), ws.handshake_info().map( |i| &i.uri ) );
+
+		ws.write_all( b"hello" ).await.expect( "write" );
+		ws.close().await.expect( "close" );
+
+		let mut rest = Vec::new();
+		ws.read_to_end( &mut rest ).await.expect( "read to end" );
+	};
+
+	let client = async
+	{
+		let mut request = format!( "ws://{addr}/room?id=1" ).into_client_request().expect( "request" );
+
+		request.headers_mut().insert( "x-test"                , "yes" .parse().expect( "header value" ) );
+		request.headers_mut().insert( "sec-websocket-protocol", "chat".parse().expect( "header value" ) );
+
+		let (socket, _) = connect_async( request ).await.expect( "connect" );
+		let mut ws      = WsStream::new( socket );
+		let mut buf     = Vec::new();
+
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		assert_eq!( b"hello".to_vec(), buf );
+	};
+
+	join( server, client ).await;
+}
+
+
+
+#[ async_std::test ]
+//
+async fn reject()
+{
+	let tcp  = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind" );
+	let addr = tcp.local_addr().expect( "local addr" );
+
+	let server = async
+	{
+		let mut listener = WsListener::new( tcp.incoming() ).callback( |request, response|
+		{
+			match request.uri().path()
+			{
+				"/allowed" => Ok( response ),
+				_          => Err( Response::builder().status( StatusCode::FORBIDDEN ).body( None ).expect( "response" ) ),
+			}
+		});
+
+		let err = listener.next().await.expect( "some" ).expect_err( "rejected" );
+
+		assert_matches!( err, WsErr::Tungstenite{..} );
+	};
+
+	let client = async
+	{
+		let err = connect_async( format!( "ws://{addr}/forbidden" ) ).await.expect_err( "rejected" );
+
+		assert_matches!( err, Error::Http( response ) if response.status() == StatusCode::FORBIDDEN );
+	};
+
+	join( server, client ).await;
+}
+
+
+
+#[ async_std::test ]
+//
+async fn timeout()
+{
+	let tcp  = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind" );
+	let addr = tcp.local_addr().expect( "local addr" );
+
+	let mut listener = WsListener::new( tcp.incoming() ).handshake_timeout( Duration::from_millis( 50 ), timer() );
+
+	// Keep the connection open without ever sending a request.
+	//
+	let _idle = TcpStream::connect( addr ).await.expect( "connect" );
+
+	let err = listener.next().await.expect( "some" ).expect_err( "timeout" );
+
+	assert_matches!( err, WsErr::HandshakeTimeout );
+}
+
+
+
+// With room for one handshake, the idle connection blocks the second one until it times out.
+// Without the limit, the second connection would be accepted first.
+//
+#[ async_std::test ]
+//
+async fn max_handshakes()
+{
+	let tcp  = TcpListener::bind( "127.0.0.1:0" ).await.expect( "bind" );
+	let addr = tcp.local_addr().expect( "local addr" );
+
+	let _idle = TcpStream::connect( addr ).await.expect( "connect" );
+
+	let server = async
+	{
+		let mut listener = WsListener::new( tcp.incoming() )
+
+			.handshake_timeout( Duration::from_millis( 200 ), timer() )
+			.max_handshakes   ( 1                                     )
+		;
+
+		assert_matches!( listener.next().await, Some( Err( WsErr::HandshakeTimeout ) ) );
+		assert_matches!( listener.next().await, Some( Ok(_)                          ) );
+	};
+
+	let client = async
+	{
+		let (socket, _) = connect_async( format!( "ws://{addr}" ) ).await.expect( "connect" );
+
+		drop( socket );
+	};
+
+	join( server, client ).await;
+}
+
+
+
+#[ async_std::test ]
+//
+async fn incoming_ends()
+{
+	let incoming = stream::iter( Vec::<io::Result<TcpStream>>::new() );
+	let listener = WsListener::new( incoming );
+
+	assert_eq!( 0, listener.count().await );
+}
@@ -0,0 +1,126 @@
+// Test WsStream::into_split.
+//
+// ✔ the halves can read and write concurrently, and the writer can close with a code
+// ✔ both halves can be observed
+// ✔ halves from the same stream can be reunited
+// ✔ halves from different streams are given back in a ReuniteError
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                        } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                                          } ,
+	tungstenite           :: { protocol::{ CloseFrame, frame::coding::CloseCode, Role }, Message        } ,
+	pharos                :: { Observable, ObserveConfig                                                } ,
+	futures_ringbuf       :: { Endpoint                                                                 } ,
+	assert_matches        :: { assert_matches                                                           } ,
+};
+
+
+
+#[ test ]
+//
+fn split()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let tws                      = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let (mut reader, mut writer) = WsStream::new( tws ).into_split();
+
+		let read_events  = reader.observe( ObserveConfig::default() ).await.expect( "observe reader" );
+		let write_events = writer.observe( ObserveConfig::default() ).await.expect( "observe writer" );
+
+		let write = async
+		{
+			writer.write_all( b"hello" ).await.expect( "write" );
+			writer.close_with( CloseCode::Normal, "done" ).expect( "close_with" );
+			writer.close().await.expect( "close" );
+
+			writer
+		};
+
+		let read = async
+		{
+			let mut buf = Vec::new();
+			reader.read_to_end( &mut buf ).await.expect( "read to end" );
+
+			(reader, buf)
+		};
+
+		let (writer, (reader, buf)) = join( write, read ).await;
+
+		drop( reader.reunite( writer ).expect( "reunite" ) );
+
+		( buf, read_events.collect::<Vec<_>>().await, write_events.collect::<Vec<_>>().await )
+	};
+
+
+	let client = async
+	{
+		let mut tws  = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut msgs = Vec::new();
+
+		tws.send( Message::Binary( "hi".into() ) ).await.expect( "send" );
+
+		while let Some( Ok(msg) ) = tws.next().await
+		{
+			msgs.push( msg );
+		}
+
+		msgs
+	};
+
+	let ((buf, read_events, write_events), msgs) = block_on( join( server, client ) );
+
+	let frame = CloseFrame
+	{
+		code  : CloseCode::Normal,
+		reason: "done".into(),
+	};
+
+	assert_eq!( b"hi".to_vec(), buf );
+
+	assert_eq!
+	(
+		vec!
+		[
+			Message::Binary( "hello".into()        ),
+			Message::Close ( Some( frame.clone() ) ),
+		],
+
+		msgs
+	);
+
+	for events in [ read_events, write_events ]
+	{
+		assert_eq!( 2, events.len() );
+		assert_matches!( &events[0], WsEvent::CloseFrame( Some(f) ) if f == &frame );
+		assert_matches!( &events[1], WsEvent::Closed( _ ) );
+	}
+}
+
+
+
+#[ test ]
+//
+fn reunite_mismatch()
+{
+	let (sc1, _cs1) = Endpoint::pair( 100, 100 );
+	let (sc2, _cs2) = Endpoint::pair( 100, 100 );
+
+	block_on( async
+	{
+		let (reader1, writer1) = WsStream::new( WebSocketStream::from_raw_socket( sc1, Role::Server, None ).await ).into_split();
+		let (reader2, writer2) = WsStream::new( WebSocketStream::from_raw_socket( sc2, Role::Server, None ).await ).into_split();
+
+		assert!(  reader1.is_pair_of( &writer1 ) );
+		assert!( !reader1.is_pair_of( &writer2 ) );
+
+		let ReuniteError( reader1, writer2 ) = reader1.reunite( writer2 ).expect_err( "mismatched halves" );
+
+		assert!( reader1.reunite( writer1 ).is_ok() );
+		assert!( reader2.reunite( writer2 ).is_ok() );
+	});
+}
@@ -0,0 +1,78 @@
+// Test WsStream::stats.
+//
+// ✔ data messages, pings and close frames are counted in both directions
+// ✔ the final snapshot is delivered in WsEvent::Closed
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                                 } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join          } ,
+	async_tungstenite     :: { WebSocketStream                                                                   } ,
+	tungstenite           :: { protocol::Role, Message                                                           } ,
+	pharos                :: { Observable, ObserveConfig                                                         } ,
+	futures_ringbuf       :: { Endpoint                                                                          } ,
+	assert_matches        :: { assert_matches                                                                    } ,
+};
+
+
+
+#[ test ]
+//
+fn stats()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = WsStream::new( tws );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = [ 0u8; 10 ];
+
+		assert_eq!( 0, ws.stats().messages_received );
+		assert_eq!( None, ws.stats().closed_at );
+
+		ws.read_exact( &mut buf ).await.expect( "read exact" );
+		ws.write_all( b"hi" ).await.expect( "write" );
+		ws.close().await.expect( "close" );
+
+		let mut rest = Vec::new();
+
+		ws.read_to_end( &mut rest ).await.expect( "read to end" );
+
+		let stats = ws.stats();
+
+		drop( ws );
+
+		( stats, events.collect::<Vec<_>>().await )
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( Message::Binary( "hello".into() ) ).await.expect( "send" );
+		tws.send( Message::Ping  ( vec![ 1 ]      ) ).await.expect( "send" );
+		tws.send( Message::Binary( "world".into() ) ).await.expect( "send" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	let ((stats, events), _) = block_on( join( server, client ) );
+
+	assert_eq!( 10, stats.bytes_received        );
+	assert_eq!(  2, stats.messages_received     );
+	assert_eq!(  2, stats.bytes_sent            );
+	assert_eq!(  1, stats.messages_sent         );
+	assert_eq!(  1, stats.pings_received        );
+	assert_eq!(  0, stats.pings_sent            );
+	assert_eq!(  0, stats.pongs_received        );
+	assert_eq!(  1, stats.close_frames_sent     );
+	assert_eq!(  1, stats.close_frames_received );
+	assert_eq!(  0, stats.errors                );
+
+	assert!( stats.closed_at.expect( "closed" ) >= stats.opened_at );
+
+	assert_matches!( events.last(), Some( WsEvent::Closed( s ) ) if s == &stats );
+}
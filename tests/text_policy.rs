@@ -0,0 +1,168 @@
+// Test the different policies for incoming text messages.
+//
+// ✔ Reject closes the connection with CloseCode::Unsupported
+// ✔ PassThrough returns the bytes of the text message through AsyncRead
+// ✔ Ignore drops the text message and keeps the connection open
+// ✔ Event delivers the text message to observers and keeps the connection open
+// ✔ Many ignored text messages in a row don't overflow the stack
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                 } ,
+	futures               :: { StreamExt, AsyncReadExt, executor::block_on, future::join         } ,
+	async_tungstenite     :: { WebSocketStream                                                   } ,
+	tungstenite           :: { protocol::{ CloseFrame, frame::coding::CloseCode, Role }, Message } ,
+	pharos                :: { Observable, ObserveConfig, Events                                 } ,
+	futures_ringbuf       :: { Endpoint                                                          } ,
+	assert_matches        :: { assert_matches                                                    } ,
+};
+
+
+// The remote sends a text message followed by a binary message and then closes the connection.
+// Returns what we read from the WsStream and the events.
+//
+fn run( policy: TextPolicy ) -> ( Vec<u8>, Vec<WsEvent>, Option<Message> )
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws    = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws     = WsStream::new( tws );
+		let     events = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+		let mut buf    = Vec::new();
+
+		ws.set_text_policy( policy );
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		drop( ws );
+
+		( buf, events )
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.send( Message::Text  ( "hello ".into() ) ).await.expect( "send text"   );
+		tws.send( Message::Binary( "world" .into() ) ).await.expect( "send binary" );
+
+		tws.close( None ).await.expect( "close" );
+
+		// Either the remote closed on the text message or it responds to our close frame.
+		//
+		let frame = tws.next().await.transpose().expect( "receive close" );
+
+		while tws.next().await.is_some() {}
+
+		frame
+	};
+
+	let ( (buf, events), frame ) = block_on( join( server, client ) );
+
+	( buf, block_on( collect( events ) ), frame )
+}
+
+
+async fn collect( events: Events<WsEvent> ) -> Vec<WsEvent>
+{
+	events.collect().await
+}
+
+
+
+#[ test ]
+//
+fn reject()
+{
+	let (_buf, events, frame) = run( TextPolicy::Reject );
+
+	let expect = CloseFrame
+	{
+		code  : CloseCode::Unsupported,
+		reason: "Text messages are not supported.".into(),
+	};
+
+	assert_matches!( &events[0], WsEvent::Error(e) if matches!( **e, WsErr::ReceivedText ) );
+	assert_eq!( Some( Message::Close( Some(expect) ) ), frame );
+}
+
+
+#[ test ]
+//
+fn pass_through()
+{
+	let (buf, _events, frame) = run( TextPolicy::PassThrough );
+
+	assert_eq!( b"hello world".to_vec(), buf );
+	assert_eq!( Some( Message::Close( None ) ), frame );
+}
+
+
+#[ test ]
+//
+fn ignore()
+{
+	let (buf, events, frame) = run( TextPolicy::Ignore );
+
+	assert_eq!( b"world".to_vec(), buf );
+	assert_matches!( &events[0], WsEvent::CloseFrame( None ) );
+	assert_eq!( Some( Message::Close( None ) ), frame );
+}
+
+
+#[ test ]
+//
+fn event()
+{
+	let (buf, events, frame) = run( TextPolicy::Event );
+
+	assert_eq!( b"world".to_vec(), buf );
+	assert_matches!( &events[0], WsEvent::Text( text ) if text == "hello " );
+	assert_eq!( Some( Message::Close( None ) ), frame );
+}
+
+
+#[ test ]
+//
+fn ignore_many()
+{
+	const COUNT: usize = 50_000;
+
+	// Buffer everything, so the server finds all messages ready in one poll.
+	//
+	let (sc, cs) = Endpoint::pair( 500_000, 500_000 );
+
+	let server = async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStream::new( tws );
+		let mut buf = Vec::new();
+
+		ws.set_text_policy( TextPolicy::Ignore );
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		buf
+	};
+
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		for _ in 0..COUNT
+		{
+			tws.send( Message::Text( String::new() ) ).await.expect( "send text" );
+		}
+
+		tws.send( Message::Binary( "world".into() ) ).await.expect( "send binary" );
+		tws.close( None ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	let (buf, _) = block_on( join( server, client ) );
+
+	assert_eq!( b"world".to_vec(), buf );
+}
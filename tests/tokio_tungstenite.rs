@@ -0,0 +1,76 @@
+#![ cfg( feature = "tokio_io" ) ]
+//
+// Test using a tokio-tungstenite websocket directly, without a compat layer.
+//
+// ✔ frame with tokio_util::codec
+// ✔ the close handshake completes on both sides and the Closed event is emitted
+//
+use
+{
+	ws_stream_tungstenite :: { *                                      } ,
+	futures               :: { StreamExt, SinkExt, future::join       } ,
+	tokio_util::codec     :: { LinesCodec, Framed                     } ,
+	tokio                 :: { net::{ TcpListener }                   } ,
+	tokio_tungstenite     :: { accept_async, connect_async            } ,
+	pharos                :: { Observable, ObserveConfig              } ,
+	assert_matches        :: { assert_matches                         } ,
+	tracing               :: { *                                      } ,
+};
+
+
+#[ tokio::test ]
+//
+async fn tokio_tungstenite()
+{
+	let socket = TcpListener::bind( "127.0.0.1:3018" ).await.expect( "bind to port" );
+
+	let server = async
+	{
+		let (tcp_stream, _peer_addr) = socket.accept().await.expect( "tcp connect" );
+		let     s      = accept_async( tcp_stream ).await.expect( "Error during the websocket handshake occurred" );
+		let mut server = WsStream::new( s );
+		let     events = server.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+		let (mut sink, mut stream) = Framed::new( server, LinesCodec::new() ).split();
+
+		sink.send( "A line"       .to_string() ).await.expect( "Send a line" );
+		sink.send( "A second line".to_string() ).await.expect( "Send a line" );
+
+		sink.close().await.expect( "close server" );
+
+		let read = stream.next().await.transpose().expect( "close connection" );
+
+		assert!( read.is_none() );
+
+		drop( stream );
+		drop( sink   );
+
+		let events = events.collect::<Vec<_>>().await;
+
+		assert_matches!( events.last(), Some( WsEvent::Closed(_) ) );
+		debug!( "Server task ended" );
+	};
+
+
+	let client = async
+	{
+		let socket = connect_async( "ws://127.0.0.1:3018" ).await.expect( "ws handshake" );
+
+		let     client = WsStream::new( socket.0 );
+		let mut framed = Framed::new( client, LinesCodec::new() );
+
+		let res = framed.next().await.expect( "Receive some" ).expect( "Receive a line" );
+		assert_eq!( "A line".to_string(), res );
+
+
+		let res = framed.next().await.expect( "Receive some" ).expect( "Receive a second line" );
+		assert_eq!( "A second line".to_string(), res );
+
+		let res = framed.next().await;
+
+		assert!( res.is_none() );
+		debug!( "Client task ended" );
+	};
+
+	join( server, client ).await;
+}
@@ -0,0 +1,158 @@
+// Test the tracing instrumentation.
+//
+// ✔ logging happens in a span with the connection id and peer label
+// ✔ state transitions and close frames are logged
+// ✔ the handshake info is recorded in the span
+// ✔ dropping a WsStream before the close handshake finished logs a warning
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                         } ,
+	futures               :: { StreamExt, AsyncReadExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                           } ,
+	tungstenite           :: { protocol::Role                                            } ,
+	futures_ringbuf       :: { Endpoint                                                  } ,
+	tracing               :: { Level, subscriber::with_default                           } ,
+	std                   :: { io, sync::{ Arc, Mutex }                                  } ,
+};
+
+
+
+// Collects everything logged while running `f`.
+//
+fn logs( f: impl FnOnce() ) -> String
+{
+	let buf    = Arc::new( Mutex::new( Vec::new() ) );
+	let writer = buf.clone();
+
+	let subscriber = tracing_subscriber::fmt()
+
+		.with_max_level( Level::TRACE                     )
+		.with_ansi     ( false                            )
+		.with_writer   ( move || Writer( writer.clone() ) )
+		.finish()
+	;
+
+	with_default( subscriber, f );
+
+	let buf = buf.lock().expect( "lock" );
+
+	String::from_utf8( buf.clone() ).expect( "utf8" )
+}
+
+
+
+struct Writer( Arc<Mutex<Vec<u8>>> );
+
+impl io::Write for Writer
+{
+	fn write( &mut self, buf: &[u8] ) -> io::Result<usize>
+	{
+		self.0.lock().expect( "lock" ).write( buf )
+	}
+
+	fn flush( &mut self ) -> io::Result<()>
+	{
+		Ok(())
+	}
+}
+
+
+
+fn config() -> WsStreamConfig
+{
+	WsStreamConfig::new().connection_id( "conn-1" ).peer( "test-peer" )
+}
+
+
+
+#[ test ]
+//
+fn close_handshake()
+{
+	let logs = logs( ||
+	{
+		let (sc, cs) = Endpoint::pair( 100, 100 );
+
+		let server = async
+		{
+			let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+			let mut ws  = config().build( tws ).expect( "valid config" );
+			let mut buf = Vec::new();
+
+			ws.read_to_end( &mut buf ).await.expect( "read to end" );
+		};
+
+		let client = async
+		{
+			let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+			tws.close( None ).await.expect( "close" );
+
+			while tws.next().await.is_some() {}
+		};
+
+		block_on( join( server, client ) );
+	});
+
+	assert!( logs.contains( r#"ws_stream{id="conn-1" peer="test-peer"}"# ), "{logs}" );
+	assert!( logs.contains( "close frame received"                        ), "{logs}" );
+	assert!( logs.contains( "State(CLOSE_RECEIVED) set"                   ), "{logs}" );
+	assert!( logs.contains( "State(STREAM_CLOSED) set"                    ), "{logs}" );
+	assert!( !logs.contains( "WARN"                                       ), "{logs}" );
+}
+
+
+
+#[ test ]
+//
+fn dropped_early()
+{
+	let logs = logs( ||
+	{
+		let (sc, _cs) = Endpoint::pair( 100, 100 );
+
+		block_on( async
+		{
+			let tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+			let ws  = config().build( tws ).expect( "valid config" );
+
+			drop( ws );
+		});
+	});
+
+	assert!( logs.contains( "WARN" ), "{logs}" );
+	assert!( logs.contains( "dropped before the close handshake finished" ), "{logs}" );
+}
+
+
+
+#[ cfg( feature = "handshake" ) ]
+//
+#[ test ]
+//
+fn handshake_info()
+{
+	let logs = logs( ||
+	{
+		let (sc, _cs) = Endpoint::pair( 100, 100 );
+
+		block_on( async
+		{
+			let mut info = HandshakeInfo::default();
+
+			info.uri         = "/chat".parse().expect( "uri" );
+			info.subprotocol = Some( "chat.v1".to_string() );
+			info.peer_addr   = Some( ( [127, 0, 0, 1], 4000 ).into() );
+
+			let tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+			let ws  = WsStreamConfig::new().handshake_info( info ).build( tws ).expect( "valid config" );
+
+			drop( ws );
+		});
+	});
+
+	assert!( logs.contains( "uri=/chat"               ), "{logs}" );
+	assert!( logs.contains( r#"subprotocol="chat.v1""# ), "{logs}" );
+	assert!( logs.contains( "peer=127.0.0.1:4000"     ), "{logs}" );
+}
@@ -0,0 +1,80 @@
+// Test WriteMode::FlushDelimited.
+//
+// ✔ writes are sent as one message per flush
+// ✔ a full buffer is sent out without waiting for flush
+// ✔ buffered data is sent out on close
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                        } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join } ,
+	async_tungstenite     :: { WebSocketStream                                                          } ,
+	tungstenite           :: { protocol::Role, Message                                                  } ,
+	futures_ringbuf       :: { Endpoint                                                                 } ,
+};
+
+
+
+#[ test ]
+//
+fn flush_delimited()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+
+		let mut ws = WsStreamConfig::new()
+
+			.write_mode    ( WriteMode::FlushDelimited )
+			.max_write_size( 8                         )
+			.build( tws )
+			.expect( "valid config" )
+		;
+
+		ws.write_all( b"head" ).await.expect( "write" );
+		ws.write_all( b"body" ).await.expect( "write" );
+		ws.flush().await.expect( "flush" );
+
+		ws.write_all( b"0123456789abcdefghij" ).await.expect( "write" );
+		ws.flush().await.expect( "flush" );
+
+		ws.write_all( b"bye" ).await.expect( "write" );
+		ws.close().await.expect( "close" );
+
+		let mut buf = Vec::new();
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+	};
+
+
+	let client = async
+	{
+		let mut tws  = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+		let mut msgs = Vec::new();
+
+		while let Some( Ok(msg) ) = tws.next().await
+		{
+			msgs.push( msg );
+		}
+
+		msgs
+	};
+
+	let ( _, msgs ) = block_on( join( server, client ) );
+
+	assert_eq!
+	(
+		vec!
+		[
+			Message::Binary( "headbody".into() ),
+			Message::Binary( "01234567".into() ),
+			Message::Binary( "89abcdef".into() ),
+			Message::Binary( "ghij"    .into() ),
+			Message::Binary( "bye"     .into() ),
+			Message::Close ( None              ),
+		],
+
+		msgs
+	);
+}
@@ -0,0 +1,139 @@
+// Test conversions into WsErr.
+//
+// ✔ every tungstenite error converts without panicking, protocol errors to WsErr::Protocol or WsErr::Subprotocol
+// ✔ pharos errors convert without panicking, closed to WsErr::Closed
+// ✔ observing with a bounded channel of capacity zero returns an error
+// ✔ errors returned in band contain a WsErr, eg. the close code after the remote closed
+// ✔ WsErr can be cloned
+//
+use
+{
+	ws_stream_tungstenite :: { *                                                                                   } ,
+	futures               :: { StreamExt, AsyncReadExt, AsyncWriteExt, executor::block_on, future::join            } ,
+	async_tungstenite     :: { WebSocketStream                                                                     } ,
+	tungstenite           :: { Error as TungErr, Message, protocol::{ Role, CloseFrame, frame::coding::CloseCode } } ,
+	tungstenite           :: { error::{ CapacityError, ProtocolError, UrlError, SubProtocolError }                 } ,
+	pharos                :: { Observable, Channel, PharErr, ErrorKind                                             } ,
+	futures_ringbuf       :: { Endpoint                                                                            } ,
+	assert_matches        :: { assert_matches                                                                      } ,
+	std                   :: { io                                                                                  } ,
+};
+
+
+
+#[ test ]
+//
+fn from_tungstenite()
+{
+	assert_matches!( WsErr::from( TungErr::Protocol( ProtocolError::SendAfterClosing ) ), WsErr::Protocol );
+
+	assert_matches!
+	(
+		WsErr::from( TungErr::Protocol( ProtocolError::SecWebSocketSubProtocolError( SubProtocolError::NoSubProtocol ) ) ),
+		WsErr::Subprotocol{ source: SubProtocolError::NoSubProtocol }
+	);
+
+	assert_matches!
+	(
+		WsErr::from( TungErr::Capacity( CapacityError::MessageTooLong{ size: 2, max_size: 1 } ) ),
+		WsErr::MessageTooLarge{ size: 2, max_size: 1 }
+	);
+
+	let errors = vec!
+	[
+		TungErr::Io( io::ErrorKind::BrokenPipe.into() )                            ,
+		TungErr::ConnectionClosed                                                  ,
+		TungErr::AlreadyClosed                                                     ,
+		TungErr::Capacity( CapacityError::TooManyHeaders )                         ,
+		TungErr::WriteBufferFull( Message::Binary( vec![ 1 ] ) )                   ,
+		TungErr::Url( UrlError::NoHostName )                                       ,
+		TungErr::Utf8                                                              ,
+		TungErr::AttackAttempt                                                     ,
+	];
+
+	for err in errors
+	{
+		assert_matches!( WsErr::from( err ), WsErr::Tungstenite{..} );
+	}
+}
+
+
+
+#[ test ]
+//
+fn from_pharos()
+{
+	assert_matches!( WsErr::from( PharErr::from( ErrorKind::Closed            ) ), WsErr::Closed      );
+	assert_matches!( WsErr::from( PharErr::from( ErrorKind::MinChannelSizeOne ) ), WsErr::Pharos{..} );
+}
+
+
+
+#[ test ]
+//
+fn observe_bounded_zero()
+{
+	let (sc, _cs) = Endpoint::pair( 100, 100 );
+
+	block_on( async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStream::new( tws );
+
+		let err = ws.observe( Channel::Bounded( 0 ).into() ).await.expect_err( "capacity zero" );
+
+		assert_matches!( err, WsErr::Pharos{..} );
+	});
+}
+
+
+
+#[ test ]
+//
+fn remote_closed()
+{
+	let (sc, cs) = Endpoint::pair( 100, 100 );
+
+	let server = async
+	{
+		let     tws = WebSocketStream::from_raw_socket( sc, Role::Server, None ).await;
+		let mut ws  = WsStream::new( tws );
+		let mut buf = Vec::new();
+
+		ws.read_to_end( &mut buf ).await.expect( "read to end" );
+
+		let err   = ws.write( b"too late" ).await.expect_err( "closed" );
+		let inner = err.get_ref().and_then( |e| e.downcast_ref::<WsErr>() ).expect( "WsErr" ).clone();
+
+		assert_eq!( io::ErrorKind::NotConnected, err.kind() );
+		assert_matches!( inner, WsErr::RemoteClosed{ code: CloseCode::Away, ref reason } if reason == "bye" );
+	};
+
+	let client = async
+	{
+		let mut tws = WebSocketStream::from_raw_socket( cs, Role::Client, None ).await;
+
+		tws.close( Some( CloseFrame{ code: CloseCode::Away, reason: "bye".into() } ) ).await.expect( "close" );
+
+		while tws.next().await.is_some() {}
+	};
+
+	block_on( join( server, client ) );
+}
+
+
+
+#[ test ]
+//
+fn into_io_error()
+{
+	let err = io::Error::from( WsErr::MessageTooLarge{ size: 2, max_size: 1 } );
+
+	assert_eq!( io::ErrorKind::InvalidData, err.kind() );
+
+	assert_matches!
+	(
+		err.into_inner().expect( "inner" ).downcast::<WsErr>().map( |e| *e ),
+		Ok( WsErr::MessageTooLarge{ size: 2, max_size: 1 } )
+	);
+}